@@ -0,0 +1,36 @@
+// cargo +nightly fuzz run parse_request
+//
+// 아무 바이트열이나 요청 파서에 넣어 본다. 파서는 패닉 없이 에러를 돌려줘야 하고,
+// 받아들인 요청은 다시 직렬화해 파싱해도 같은 요청이어야 한다.
+#![no_main]
+
+use http::httprequest::{HttpRequest, Limits};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let limits = Limits {
+        max_request_line: 1024,
+        max_header_bytes: 4096,
+        max_header_count: 32,
+        max_body_bytes: 64 * 1024,
+    };
+    let Ok(req) = HttpRequest::from_reader_with_limits(&mut &data[..], &limits) else {
+        return;
+    };
+
+    let mut raw = Vec::new();
+    req.send_request(&mut raw).unwrap();
+    let again = HttpRequest::from_reader(&mut &raw[..]).expect("re-parse serialised request");
+    assert_eq!(req.method, again.method);
+    assert_eq!(req.resource.to_string(), again.resource.to_string());
+    assert_eq!(req.version, again.version);
+    assert_eq!(req.msg_body, again.msg_body);
+    let framing = |name: &str| {
+        name.eq_ignore_ascii_case("Content-Length") || name.eq_ignore_ascii_case("Transfer-Encoding")
+    };
+    assert!(req
+        .headers
+        .iter()
+        .filter(|(k, _)| !framing(k))
+        .eq(again.headers.iter().filter(|(k, _)| !framing(k))));
+});
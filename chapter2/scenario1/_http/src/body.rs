@@ -0,0 +1,262 @@
+use std::fmt;
+use std::fs::File;
+use std::io::{self, Read, Write};
+
+// 본문을 소켓에 옮길 때 한 번에 읽고 쓰는 최대 크기
+pub const WRITE_CHUNK_SIZE: usize = 8 * 1024;
+
+// 응답 본문. 메모리에 있는 바이트뿐 아니라 파일이나 임의의 리더를 그대로 담아 두었다가
+// 보낼 때 조금씩 읽어 쓰므로, 큰 파일도 통째로 메모리에 올리지 않는다.
+#[derive(Default)]
+pub enum Body {
+    #[default]
+    Empty,
+    Bytes(Vec<u8>),
+    // 길이를 미리 아는 파일. 파일의 현재 위치부터 len 바이트를 보낸다.
+    File {
+        file: File,
+        len: u64,
+    },
+    // 임의의 리더. 길이를 모르면 chunked 전송 코딩으로 보낸다.
+    Reader {
+        reader: Box<dyn Read + Send>,
+        len: Option<u64>,
+    },
+}
+
+impl Body {
+    // 파일 전체를 본문으로 쓴다
+    pub fn from_file(file: File) -> io::Result<Body> {
+        let len = file.metadata()?.len();
+        Ok(Body::File { file, len })
+    }
+
+    pub fn from_reader(reader: impl Read + Send + 'static, len: Option<u64>) -> Body {
+        Body::Reader {
+            reader: Box::new(reader),
+            len,
+        }
+    }
+
+    // 본문 길이. 보내 보기 전에는 알 수 없으면 None이다.
+    pub fn len(&self) -> Option<u64> {
+        match self {
+            Body::Empty => Some(0),
+            Body::Bytes(bytes) => Some(bytes.len() as u64),
+            Body::File { len, .. } => Some(*len),
+            Body::Reader { len, .. } => *len,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == Some(0)
+    }
+
+    // 메모리에 있는 본문이면 그 바이트를 돌려준다
+    pub fn as_bytes(&self) -> Option<&[u8]> {
+        match self {
+            Body::Empty => Some(&[]),
+            Body::Bytes(bytes) => Some(bytes),
+            _ => None,
+        }
+    }
+
+    // 본문을 끝까지 읽어 메모리로 가져온다
+    pub fn into_bytes(self) -> io::Result<Vec<u8>> {
+        match self {
+            Body::Empty => Ok(Vec::new()),
+            Body::Bytes(bytes) => Ok(bytes),
+            Body::File { file, len } => {
+                let mut bytes = Vec::new();
+                file.take(len).read_to_end(&mut bytes)?;
+                Ok(bytes)
+            }
+            Body::Reader { mut reader, .. } => {
+                let mut bytes = Vec::new();
+                reader.read_to_end(&mut bytes)?;
+                Ok(bytes)
+            }
+        }
+    }
+
+    // 본문을 앞에서부터 읽는 리더로 바꾼다. 길이를 아는 본문은 그 길이까지만 읽는다.
+    pub fn into_reader(self) -> Box<dyn Read + Send> {
+        match self {
+            Body::Empty => Box::new(io::empty()),
+            Body::Bytes(bytes) => Box::new(io::Cursor::new(bytes)),
+            Body::File { file, len } => Box::new(file.take(len)),
+            Body::Reader {
+                reader,
+                len: Some(len),
+            } => Box::new(reader.take(len)),
+            Body::Reader { reader, len: None } => reader,
+        }
+    }
+
+    // 본문을 WRITE_CHUNK_SIZE 단위로 읽어 스트림에 쓰고, 쓴 바이트 수를 돌려준다.
+    // 길이를 알린 본문이 그보다 일찍 끝나면 응답이 깨지므로 에러로 돌려준다.
+    pub fn write_to(self, write_stream: &mut impl Write) -> io::Result<u64> {
+        match self {
+            Body::Empty => Ok(0),
+            Body::Bytes(bytes) => {
+                write_stream.write_all(&bytes)?;
+                Ok(bytes.len() as u64)
+            }
+            Body::File { file, len } => copy_exact(&mut file.take(len), write_stream, len),
+            Body::Reader {
+                reader,
+                len: Some(len),
+            } => copy_exact(&mut reader.take(len), write_stream, len),
+            Body::Reader {
+                mut reader,
+                len: None,
+            } => copy_chunks(&mut reader, write_stream),
+        }
+    }
+}
+
+fn copy_chunks(reader: &mut impl Read, write_stream: &mut impl Write) -> io::Result<u64> {
+    let mut buf = [0; WRITE_CHUNK_SIZE];
+    let mut written = 0;
+    loop {
+        let n = match reader.read(&mut buf) {
+            Ok(0) => return Ok(written),
+            Ok(n) => n,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        };
+        write_stream.write_all(&buf[..n])?;
+        written += n as u64;
+    }
+}
+
+fn copy_exact(reader: &mut impl Read, write_stream: &mut impl Write, len: u64) -> io::Result<u64> {
+    let written = copy_chunks(reader, write_stream)?;
+    if written < len {
+        return Err(io::Error::new(
+            io::ErrorKind::UnexpectedEof,
+            format!("body ended after {} of {} bytes", written, len),
+        ));
+    }
+    Ok(written)
+}
+
+impl fmt::Debug for Body {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Body::Empty => write!(f, "Empty"),
+            Body::Bytes(bytes) => f.debug_tuple("Bytes").field(bytes).finish(),
+            Body::File { len, .. } => f.debug_struct("File").field("len", len).finish(),
+            Body::Reader { len, .. } => f.debug_struct("Reader").field("len", len).finish(),
+        }
+    }
+}
+
+// 메모리에 있는 본문끼리만 내용을 비교한다. 파일과 리더는 읽어 보기 전에는 비교할 수 없다.
+impl PartialEq for Body {
+    fn eq(&self, other: &Self) -> bool {
+        match (self.as_bytes(), other.as_bytes()) {
+            (Some(a), Some(b)) => a == b,
+            _ => false,
+        }
+    }
+}
+
+impl From<Vec<u8>> for Body {
+    fn from(bytes: Vec<u8>) -> Self {
+        Body::Bytes(bytes)
+    }
+}
+
+impl From<&[u8]> for Body {
+    fn from(bytes: &[u8]) -> Self {
+        Body::Bytes(bytes.to_vec())
+    }
+}
+
+impl From<String> for Body {
+    fn from(s: String) -> Self {
+        Body::Bytes(s.into_bytes())
+    }
+}
+
+impl From<&str> for Body {
+    fn from(s: &str) -> Self {
+        Body::Bytes(s.as_bytes().to_vec())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+    use std::fs;
+    use std::io::{Seek, SeekFrom};
+
+    #[test]
+    fn test_write_reader_in_bounded_chunks() {
+        // 쓰기 한 번의 크기를 기록하는 스트림
+        struct RecordingWriter(Vec<usize>);
+        impl Write for RecordingWriter {
+            fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+                self.0.push(buf.len());
+                Ok(buf.len())
+            }
+            fn flush(&mut self) -> io::Result<()> {
+                Ok(())
+            }
+        }
+
+        let data = vec![7u8; WRITE_CHUNK_SIZE * 2 + 10];
+        let body = Body::from_reader(io::Cursor::new(data.clone()), Some(data.len() as u64));
+        let mut out = RecordingWriter(Vec::new());
+        assert_eq!(data.len() as u64, body.write_to(&mut out).unwrap());
+        assert!(out.0.iter().all(|n| *n <= WRITE_CHUNK_SIZE));
+        assert_eq!(data.len(), out.0.iter().sum::<usize>());
+    }
+
+    #[test]
+    fn test_write_file_from_current_position() {
+        let path = env::temp_dir().join(format!("http-body-test-{}", std::process::id()));
+        fs::write(&path, b"0123456789").unwrap();
+        let mut file = File::open(&path).unwrap();
+        file.seek(SeekFrom::Start(2)).unwrap();
+        let body = Body::File { file, len: 5 };
+        let mut out = Vec::new();
+        assert_eq!(5, body.write_to(&mut out).unwrap());
+        assert_eq!(b"23456".to_vec(), out);
+
+        // 파일이 알린 길이보다 짧으면 에러가 난다
+        let body = Body::File {
+            file: File::open(&path).unwrap(),
+            len: 20,
+        };
+        let err = body.write_to(&mut Vec::new()).unwrap_err();
+        assert_eq!(io::ErrorKind::UnexpectedEof, err.kind());
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_into_reader_respects_length() {
+        let body = Body::from_reader(io::Cursor::new(b"0123456789".to_vec()), Some(4));
+        let mut out = Vec::new();
+        body.into_reader().read_to_end(&mut out).unwrap();
+        assert_eq!(b"0123".to_vec(), out);
+    }
+
+    #[test]
+    fn test_write_error_is_surfaced() {
+        struct BrokenPipe;
+        impl Write for BrokenPipe {
+            fn write(&mut self, _buf: &[u8]) -> io::Result<usize> {
+                Err(io::Error::from(io::ErrorKind::BrokenPipe))
+            }
+            fn flush(&mut self) -> io::Result<()> {
+                Ok(())
+            }
+        }
+        let body = Body::from("Item was shipped");
+        let err = body.write_to(&mut BrokenPipe).unwrap_err();
+        assert_eq!(io::ErrorKind::BrokenPipe, err.kind());
+    }
+}
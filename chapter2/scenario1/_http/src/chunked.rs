@@ -0,0 +1,141 @@
+use super::headers::HeaderMap;
+use super::httprequest::{read_header_block, read_line, Limits, ParseError};
+use std::io::{self, BufRead, Write};
+use std::str;
+
+// chunked 전송 코딩으로 인코딩된 본문을 읽어 body 뒤에 이어 붙이고 트레일러를 돌려준다.
+// 마지막 청크와 트레일러까지 읽고 나면 리더는 다음 메시지의 시작을 가리킨다.
+pub fn read_chunked_body<R: BufRead>(
+    reader: &mut R,
+    body: &mut Vec<u8>,
+    limits: &Limits,
+) -> Result<HeaderMap, ParseError> {
+    loop {
+        // 청크 크기 행을 읽는다: 16진수 크기 [; 청크 확장]
+        let mut line_budget = limits.max_header_bytes;
+        let line = match read_line(reader, &mut line_budget) {
+            Ok(line) => line.ok_or(ParseError::UnexpectedEof)?,
+            Err(ParseError::HeaderTooLarge) => return Err(ParseError::InvalidChunk),
+            Err(e) => return Err(e),
+        };
+        let size = parse_chunk_size(&line)?;
+        // 크기가 0인 마지막 청크 뒤에는 트레일러가 빈 행까지 이어진다
+        if size == 0 {
+            return read_header_block(reader, limits);
+        }
+        if size > limits.max_body_bytes - body.len() {
+            return Err(ParseError::BodyTooLarge);
+        }
+
+        let start = body.len();
+        body.resize(start + size, 0);
+        reader.read_exact(&mut body[start..])?;
+
+        // 청크 데이터 뒤에는 반드시 빈 행이 와야 한다
+        match read_line(reader, &mut 2) {
+            Ok(Some(line)) if line.is_empty() => {}
+            Ok(Some(_)) | Err(ParseError::HeaderTooLarge) => return Err(ParseError::InvalidChunk),
+            Ok(None) => return Err(ParseError::UnexpectedEof),
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+fn parse_chunk_size(line: &[u8]) -> Result<usize, ParseError> {
+    let line = str::from_utf8(line).map_err(|_| ParseError::InvalidChunk)?;
+    // 청크 확장(';' 뒤)은 해석하지 않고 버린다
+    let size = match line.split_once(';') {
+        Some((size, _ext)) => size.trim_end_matches([' ', '\t']),
+        None => line,
+    };
+    if size.is_empty() || !size.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return Err(ParseError::InvalidChunk);
+    }
+    usize::from_str_radix(size, 16).map_err(|_| ParseError::InvalidChunk)
+}
+
+// 쓰는 바이트를 chunked 전송 코딩으로 감싸서 내부 스트림에 쓴다.
+// write() 호출 한 번이 청크 하나가 되며, finish()로 마지막 청크와 트레일러를 보낸다.
+pub struct ChunkedWriter<W: Write> {
+    inner: W,
+}
+
+impl<W: Write> ChunkedWriter<W> {
+    pub fn new(inner: W) -> Self {
+        ChunkedWriter { inner }
+    }
+
+    pub fn finish(mut self, trailers: &HeaderMap) -> io::Result<W> {
+        write!(self.inner, "0\r\n{}\r\n", trailers)?;
+        self.inner.flush()?;
+        Ok(self.inner)
+    }
+}
+
+impl<W: Write> Write for ChunkedWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        // 길이가 0인 청크는 본문의 끝을 뜻하므로 빈 쓰기는 건너뛴다
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        write!(self.inner, "{:X}\r\n", buf.len())?;
+        self.inner.write_all(buf)?;
+        self.inner.write_all(b"\r\n")?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_read_chunked_body_with_trailers() {
+        let raw: &[u8] = b"4\r\nWiki\r\n5;name=value\r\npedia\r\nE\r\n in\r\n\r\nchunks.\r\n0\r\nExpires: never\r\n\r\nnext";
+        let mut reader = raw;
+        let mut body = Vec::new();
+        let trailers = read_chunked_body(&mut reader, &mut body, &Limits::default()).unwrap();
+        assert_eq!(b"Wikipedia in\r\n\r\nchunks.".to_vec(), body);
+        assert_eq!(Some("never"), trailers.get("Expires"));
+        assert_eq!(b"next", reader);
+    }
+
+    #[test]
+    fn test_read_chunked_body_invalid() {
+        let cases: [&[u8]; 4] = [
+            b"zz\r\nabc\r\n0\r\n\r\n",
+            b"3\r\nabcd\r\n0\r\n\r\n",
+            b"3\r\nabc\r\n",
+            b"FFFFFFFFFFFFFFFFFFFF\r\n",
+        ];
+        for raw in cases {
+            let mut body = Vec::new();
+            assert!(read_chunked_body(&mut &raw[..], &mut body, &Limits::default()).is_err());
+        }
+    }
+
+    #[test]
+    fn test_chunked_writer_round_trip() {
+        let mut writer = ChunkedWriter::new(Vec::new());
+        writer.write_all(b"Hello, ").unwrap();
+        writer.write_all(b"").unwrap();
+        writer.write_all(b"chunked world").unwrap();
+        let mut trailers = HeaderMap::new();
+        trailers.insert("Checksum", "abc");
+        let encoded = writer.finish(&trailers).unwrap();
+        assert_eq!(
+            b"7\r\nHello, \r\nD\r\nchunked world\r\n0\r\nChecksum: abc\r\n\r\n".to_vec(),
+            encoded
+        );
+
+        let mut body = Vec::new();
+        let decoded_trailers =
+            read_chunked_body(&mut &encoded[..], &mut body, &Limits::default()).unwrap();
+        assert_eq!(b"Hello, chunked world".to_vec(), body);
+        assert_eq!(Some("abc"), decoded_trailers.get("Checksum"));
+    }
+}
@@ -0,0 +1,579 @@
+use super::headers::HeaderMap;
+use super::httprequest::{HttpRequest, Limits, Method, ParseError, Resource, Version};
+use super::httpresponse::HttpResponse;
+use super::status::StatusCode;
+use super::uri::Uri;
+use std::collections::HashMap;
+use std::error;
+use std::fmt;
+use std::io::{self, BufReader};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::sync::Mutex;
+use std::time::Duration;
+
+// 호스트마다 남겨 두는 유휴 커넥션 수
+const MAX_IDLE_PER_HOST: usize = 4;
+
+#[derive(Debug)]
+pub enum ClientError {
+    Io(io::Error),
+    // 응답을 해석할 수 없다
+    Parse(ParseError),
+    // http://host[:port]/path 형식의 URL이 아니다
+    InvalidUrl(String),
+    // 리다이렉트를 정한 횟수보다 많이 받았다
+    TooManyRedirects(usize),
+}
+
+impl fmt::Display for ClientError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ClientError::Io(e) => write!(f, "i/o error: {}", e),
+            ClientError::Parse(e) => write!(f, "invalid response: {}", e),
+            ClientError::InvalidUrl(url) => write!(f, "invalid URL {:?}", url),
+            ClientError::TooManyRedirects(max) => write!(f, "more than {} redirects", max),
+        }
+    }
+}
+
+impl error::Error for ClientError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            ClientError::Io(e) => Some(e),
+            ClientError::Parse(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for ClientError {
+    fn from(e: io::Error) -> Self {
+        ClientError::Io(e)
+    }
+}
+
+impl From<ParseError> for ClientError {
+    fn from(e: ParseError) -> Self {
+        match e {
+            ParseError::Io(e) => ClientError::Io(e),
+            e => ClientError::Parse(e),
+        }
+    }
+}
+
+// 평문 HTTP/1.1 블로킹 클라이언트.
+// 응답을 다 읽은 커넥션은 호스트별로 남겨 두었다가 같은 호스트로 가는 다음 요청에 다시 쓰고,
+// 3xx 응답의 Location을 정한 횟수까지 따라간다. 여러 스레드에서 함께 쓸 수 있다.
+//
+// let client = Client::new().timeout(Duration::from_secs(5));
+// let response = client.get("http://localhost:3000/api/shipping/orders")?;
+pub struct Client {
+    connect_timeout: Duration,
+    timeout: Duration,
+    max_redirects: usize,
+    limits: Limits,
+    idle: Mutex<HashMap<String, Vec<BufReader<TcpStream>>>>,
+}
+
+impl Default for Client {
+    fn default() -> Self {
+        Client {
+            connect_timeout: Duration::from_secs(10),
+            timeout: Duration::from_secs(30),
+            max_redirects: 5,
+            limits: Limits {
+                max_body_bytes: 64 * 1024 * 1024,
+                ..Limits::default()
+            },
+            idle: Mutex::new(HashMap::new()),
+        }
+    }
+}
+
+impl Client {
+    pub fn new() -> Self {
+        Client::default()
+    }
+
+    pub fn connect_timeout(mut self, timeout: Duration) -> Self {
+        self.connect_timeout = timeout;
+        self
+    }
+
+    // 읽기와 쓰기 한 번을 기다리는 최대 시간
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    // 0이면 리다이렉트를 따라가지 않고 3xx 응답을 그대로 돌려준다
+    pub fn max_redirects(mut self, max_redirects: usize) -> Self {
+        self.max_redirects = max_redirects;
+        self
+    }
+
+    // 응답의 상태 행(max_request_line), 헤더, 본문 크기 제한
+    pub fn limits(mut self, limits: Limits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    pub fn get(&self, url: &str) -> Result<HttpResponse, ClientError> {
+        let req = HttpRequest::builder()
+            .target(url)
+            .build()
+            .map_err(|_| ClientError::InvalidUrl(url.to_string()))?;
+        self.send(req)
+    }
+
+    // 요청을 보내고 응답을 돌려준다. 요청 대상은 absolute-form("http://host:port/path")이어야 한다.
+    pub fn send(&self, mut req: HttpRequest) -> Result<HttpResponse, ClientError> {
+        let mut redirects = 0;
+        loop {
+            let target = Target::of(&req)?;
+            let resp = self.round_trip(&target, &req)?;
+            let location = match resp.status() {
+                StatusCode::MovedPermanently
+                | StatusCode::Found
+                | StatusCode::SeeOther
+                | StatusCode::TemporaryRedirect
+                | StatusCode::PermanentRedirect => resp.headers().get("Location"),
+                _ => None,
+            };
+            let Some(location) = location.filter(|_| self.max_redirects > 0) else {
+                return Ok(resp);
+            };
+            if redirects == self.max_redirects {
+                return Err(ClientError::TooManyRedirects(self.max_redirects));
+            }
+            redirects += 1;
+            req = redirect(req, &target, resp.status(), location)?;
+        }
+    }
+
+    // 커넥션 하나로 요청을 보내고 응답을 받는다. 다시 쓴 커넥션이 그새 닫혀 있었으면
+    // 서버가 요청을 처리하지 않았으므로 멱등 메서드에 한해 새 커넥션으로 한 번 더 보낸다.
+    fn round_trip(&self, target: &Target, req: &HttpRequest) -> Result<HttpResponse, ClientError> {
+        let (mut conn, reused) = match self.take_idle(&target.authority) {
+            Some(conn) => (conn, true),
+            None => (self.connect(target)?, false),
+        };
+        let result = match self.exchange(&mut conn, target, req) {
+            Err(e) if reused && is_stale(&e) && is_idempotent(&req.method) => {
+                conn = self.connect(target)?;
+                self.exchange(&mut conn, target, req)
+            }
+            result => result,
+        };
+        let (resp, reusable) = result?;
+        if reusable {
+            self.put_idle(&target.authority, conn);
+        }
+        Ok(resp)
+    }
+
+    // 응답과 함께 커넥션을 다음 요청에 다시 쓸 수 있는지 돌려준다
+    fn exchange(
+        &self,
+        conn: &mut BufReader<TcpStream>,
+        target: &Target,
+        req: &HttpRequest,
+    ) -> Result<(HttpResponse, bool), ClientError> {
+        let mut headers = req.headers.clone();
+        if !headers.contains("Host") {
+            headers.insert("Host", target.authority.clone());
+        }
+        let wire = HttpRequest {
+            method: req.method.clone(),
+            version: Version::V1_1,
+            resource: Resource::Path(
+                Uri::parse(&target.origin_form).map_err(ParseError::InvalidTarget)?,
+            ),
+            headers,
+            msg_body: req.msg_body.clone(),
+            trailers: HeaderMap::new(),
+        };
+        wire.send_request(conn.get_mut())?;
+
+        // 100 Continue 같은 중간 응답은 건너뛴다
+        let resp = loop {
+            let resp = HttpResponse::from_reader_with_limits(conn, &req.method, &self.limits)?;
+            if !resp.status().is_informational() {
+                break resp;
+            }
+        };
+        let headers = resp.headers();
+        // 본문 끝을 커넥션이 닫히는 것으로 알린 응답 뒤에는 커넥션을 쓸 수 없다
+        let delimited = req.method == Method::Head
+            || !resp.status().allows_body()
+            || headers.contains("Content-Length")
+            || headers.contains("Transfer-Encoding");
+        let keep_alive = if headers.has_connection_option("close")
+            || wire.headers.has_connection_option("close")
+        {
+            false
+        } else {
+            headers.has_connection_option("keep-alive") || resp.version().keep_alive_by_default()
+        };
+        Ok((resp, delimited && keep_alive))
+    }
+
+    fn connect(&self, target: &Target) -> Result<BufReader<TcpStream>, ClientError> {
+        let mut last_error = None;
+        for addr in (target.host.as_str(), target.port).to_socket_addrs()? {
+            match TcpStream::connect_timeout(&addr, self.connect_timeout) {
+                Ok(stream) => {
+                    stream.set_read_timeout(Some(self.timeout))?;
+                    stream.set_write_timeout(Some(self.timeout))?;
+                    stream.set_nodelay(true)?;
+                    return Ok(BufReader::new(stream));
+                }
+                Err(e) => last_error = Some(e),
+            }
+        }
+        Err(last_error
+            .unwrap_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no address for host"))
+            .into())
+    }
+
+    // 남겨 둔 커넥션 중 서버가 아직 닫지 않은 것을 꺼낸다
+    fn take_idle(&self, authority: &str) -> Option<BufReader<TcpStream>> {
+        let mut idle = self.idle.lock().unwrap_or_else(|e| e.into_inner());
+        let conns = idle.get_mut(authority)?;
+        while let Some(conn) = conns.pop() {
+            if is_open(&conn) {
+                return Some(conn);
+            }
+        }
+        None
+    }
+
+    fn put_idle(&self, authority: &str, conn: BufReader<TcpStream>) {
+        let mut idle = self.idle.lock().unwrap_or_else(|e| e.into_inner());
+        let conns = idle.entry(authority.to_string()).or_default();
+        if conns.len() < MAX_IDLE_PER_HOST {
+            conns.push(conn);
+        }
+    }
+}
+
+// 유휴 커넥션에서 읽을 것이 없어야 정상이다. 읽을 것이 있거나(EOF 포함) 에러면 버린다.
+fn is_open(conn: &BufReader<TcpStream>) -> bool {
+    if !conn.buffer().is_empty() {
+        return false;
+    }
+    let stream = conn.get_ref();
+    if stream.set_nonblocking(true).is_err() {
+        return false;
+    }
+    let open = matches!(stream.peek(&mut [0]), Err(e) if e.kind() == io::ErrorKind::WouldBlock);
+    stream.set_nonblocking(false).is_ok() && open
+}
+
+fn is_stale(e: &ClientError) -> bool {
+    match e {
+        ClientError::Parse(ParseError::ConnectionClosed) => true,
+        ClientError::Io(e) => matches!(
+            e.kind(),
+            io::ErrorKind::BrokenPipe
+                | io::ErrorKind::ConnectionReset
+                | io::ErrorKind::ConnectionAborted
+        ),
+        _ => false,
+    }
+}
+
+// 같은 요청을 두 번 보내도 결과가 같은 메서드 (RFC 9110 9.2.2)
+fn is_idempotent(method: &Method) -> bool {
+    matches!(
+        method,
+        Method::Get | Method::Head | Method::Put | Method::Delete | Method::Options | Method::Trace
+    )
+}
+
+// 요청 대상 URL에서 접속할 곳과 요청 행에 쓸 경로를 뽑은 것
+struct Target {
+    host: String,
+    port: u16,
+    authority: String,
+    origin_form: String,
+}
+
+impl Target {
+    fn of(req: &HttpRequest) -> Result<Target, ClientError> {
+        let invalid = || ClientError::InvalidUrl(req.resource.to_string());
+        let uri = req.resource.uri().ok_or_else(invalid)?;
+        let authority = uri.authority().ok_or_else(invalid)?;
+        if uri.scheme() != Some("http") || authority.contains('@') {
+            return Err(invalid());
+        }
+        // "[::1]:8080"처럼 IPv6 주소는 대괄호로 감싼다
+        let (host, port) = match authority.rsplit_once(':') {
+            Some((host, port)) if !port.contains(']') => {
+                (host, port.parse().map_err(|_| invalid())?)
+            }
+            _ => (authority, 80),
+        };
+        let host = host.trim_start_matches('[').trim_end_matches(']');
+        Ok(Target {
+            host: host.to_string(),
+            port,
+            authority: authority.to_string(),
+            origin_form: uri.origin_form(),
+        })
+    }
+
+    // Location 값을 지금 요청한 URL을 기준으로 절대 URL로 바꾼다
+    fn resolve(&self, location: &str) -> String {
+        if location.contains("://") {
+            location.to_string()
+        } else if location.starts_with("//") {
+            format!("http:{}", location)
+        } else if location.starts_with('/') {
+            format!("http://{}{}", self.authority, location)
+        } else {
+            let path = self.origin_form.split('?').next().unwrap_or("/");
+            let dir = &path[..path.rfind('/').map_or(0, |i| i + 1)];
+            format!("http://{}{}{}", self.authority, dir, location)
+        }
+    }
+}
+
+// 리다이렉트 응답에 따라 다음 요청을 만든다.
+// 303과, POST에 대한 301/302는 본문 없는 GET으로 바꾸고 307/308은 메서드와 본문을 그대로 보낸다.
+fn redirect(
+    mut req: HttpRequest,
+    target: &Target,
+    status: StatusCode,
+    location: &str,
+) -> Result<HttpRequest, ClientError> {
+    let url = target.resolve(location);
+    let uri = Uri::parse(&url).map_err(|_| ClientError::InvalidUrl(url.clone()))?;
+    let to_get = match status {
+        StatusCode::SeeOther => req.method != Method::Head,
+        StatusCode::MovedPermanently | StatusCode::Found => req.method == Method::Post,
+        _ => false,
+    };
+    if to_get {
+        req.method = Method::Get;
+        req.msg_body.clear();
+        req.headers.remove("Content-Type");
+    }
+    // 다른 호스트로 가면 Host를 새로 정하고 자격 증명과 쿠키는 보내지 않는다
+    if uri.authority() != Some(target.authority.as_str()) {
+        for name in ["Host", "Authorization", "Proxy-Authorization", "Cookie"] {
+            req.headers.remove(name);
+        }
+    }
+    req.resource = Resource::Path(uri);
+    Ok(req)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use std::net::TcpListener;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::{Arc, OnceLock};
+    use std::thread;
+
+    // 커넥션마다 스레드를 띄워 요청을 차례로 읽고 handler의 응답을 돌려주는 서버.
+    // 받은 커넥션 수를 센다. 테스트가 끝나면 프로세스와 함께 정리된다.
+    fn serve(handler: fn(&HttpRequest) -> String) -> (String, Arc<AtomicUsize>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap().to_string();
+        let accepted = Arc::new(AtomicUsize::new(0));
+        let counter = Arc::clone(&accepted);
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                counter.fetch_add(1, Ordering::SeqCst);
+                let mut reader = BufReader::new(stream.unwrap());
+                thread::spawn(move || {
+                    while let Ok(req) = HttpRequest::from_reader(&mut reader) {
+                        let response = handler(&req);
+                        reader.get_mut().write_all(response.as_bytes()).unwrap();
+                    }
+                });
+            }
+        });
+        (addr, accepted)
+    }
+
+    #[test]
+    fn test_reuses_connection_and_reads_bodies() {
+        let (addr, accepted) = serve(|req| match req.resource.path() {
+            "/chunked" => {
+                "HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n4\r\nItem\r\n0\r\n\r\n"
+                    .to_string()
+            }
+            _ => {
+                let body = format!("ok{}", String::from_utf8_lossy(&req.msg_body));
+                format!(
+                    "HTTP/1.1 200 OK\r\nContent-Length: {}\r\n\r\n{}",
+                    body.len(),
+                    body
+                )
+            }
+        });
+        let client = Client::new();
+        let url = format!("http://{}", addr);
+        let resp = client.get(&format!("{}/", url)).unwrap();
+        assert_eq!(Some(&b"ok"[..]), resp.body().as_bytes());
+        let resp = client.get(&format!("{}/chunked", url)).unwrap();
+        assert_eq!(Some(&b"Item"[..]), resp.body().as_bytes());
+        let req = HttpRequest::builder()
+            .method(Method::Post)
+            .target(format!("{}/echo", url))
+            .body("!")
+            .build()
+            .unwrap();
+        let resp = client.send(req).unwrap();
+        assert_eq!(Some(&b"ok!"[..]), resp.body().as_bytes());
+        assert_eq!(1, accepted.load(Ordering::SeqCst));
+    }
+
+    #[test]
+    fn test_follows_redirects() {
+        let (addr, _) = serve(|req| match req.resource.path() {
+            "/old" => {
+                "HTTP/1.1 308 Permanent Redirect\r\nLocation: new?x=1\r\nContent-Length: 0\r\n\r\n"
+                    .to_string()
+            }
+            "/form" => {
+                "HTTP/1.1 303 See Other\r\nLocation: /new\r\nContent-Length: 0\r\n\r\n".to_string()
+            }
+            "/loop" => {
+                "HTTP/1.1 302 Found\r\nLocation: /loop\r\nContent-Length: 0\r\n\r\n".to_string()
+            }
+            _ => {
+                let body = format!("{} {}", req.method, req.resource);
+                format!(
+                    "HTTP/1.1 200 OK\r\nContent-Length: {}\r\n\r\n{}",
+                    body.len(),
+                    body
+                )
+            }
+        });
+        let client = Client::new().max_redirects(2);
+        let post = |path: &str| {
+            HttpRequest::builder()
+                .method(Method::Post)
+                .target(format!("http://{}{}", addr, path))
+                .body("{}")
+                .build()
+                .unwrap()
+        };
+        let resp = client.send(post("/old")).unwrap();
+        assert_eq!(Some(&b"POST /new?x=1"[..]), resp.body().as_bytes());
+        let resp = client.send(post("/form")).unwrap();
+        assert_eq!(Some(&b"GET /new"[..]), resp.body().as_bytes());
+        assert!(matches!(
+            client.get(&format!("http://{}/loop", addr)),
+            Err(ClientError::TooManyRedirects(2))
+        ));
+        let resp = Client::new()
+            .max_redirects(0)
+            .get(&format!("http://{}/loop", addr))
+            .unwrap();
+        assert_eq!(StatusCode::Found, resp.status());
+    }
+
+    #[test]
+    fn test_redirect_to_other_host_drops_credentials() {
+        // 받은 자격 증명 헤더를 본문으로 돌려준다
+        fn echo_credentials(req: &HttpRequest) -> String {
+            let body = ["Authorization", "Proxy-Authorization", "Cookie"]
+                .iter()
+                .filter_map(|name| {
+                    req.headers
+                        .get(name)
+                        .map(|value| format!("{}={};", name, value))
+                })
+                .collect::<String>();
+            format!(
+                "HTTP/1.1 200 OK\r\nContent-Length: {}\r\n\r\n{}",
+                body.len(),
+                body
+            )
+        }
+        static OTHER: OnceLock<String> = OnceLock::new();
+        let (other, _) = serve(echo_credentials);
+        OTHER.set(other).unwrap();
+        let (addr, _) = serve(|req| {
+            match req.resource.path() {
+            "/away" => format!(
+                "HTTP/1.1 307 Temporary Redirect\r\nLocation: http://{}/\r\nContent-Length: 0\r\n\r\n",
+                OTHER.get().unwrap()
+            ),
+            "/here" => "HTTP/1.1 307 Temporary Redirect\r\nLocation: /\r\nContent-Length: 0\r\n\r\n"
+                .to_string(),
+            _ => echo_credentials(req),
+        }
+        });
+        let client = Client::new();
+        let get = |path: &str| {
+            let req = HttpRequest::builder()
+                .target(format!("http://{}{}", addr, path))
+                .header("Authorization", "Basic dXNlcjpwYXNz")
+                .header("Proxy-Authorization", "Basic cHJveHk6cGFzcw==")
+                .header("Cookie", "session=1")
+                .build()
+                .unwrap();
+            client.send(req).unwrap()
+        };
+        // 같은 호스트 안에서는 그대로 보낸다
+        let resp = get("/here");
+        assert_eq!(
+            Some(&b"Authorization=Basic dXNlcjpwYXNz;Proxy-Authorization=Basic cHJveHk6cGFzcw==;Cookie=session=1;"[..]),
+            resp.body().as_bytes()
+        );
+        let resp = get("/away");
+        assert_eq!(StatusCode::Ok, resp.status());
+        assert_eq!(Some(&b""[..]), resp.body().as_bytes());
+    }
+
+    #[test]
+    fn test_unregistered_status_codes() {
+        let (addr, _) = serve(|req| match req.resource.path() {
+            "/custom" => "HTTP/1.1 299 Custom\r\nContent-Length: 2\r\n\r\nok".to_string(),
+            "/origin" => "HTTP/1.1 520 Origin Error\r\nContent-Length: 0\r\n\r\n".to_string(),
+            _ => "HTTP/1.1 600 Nope\r\nContent-Length: 0\r\n\r\n".to_string(),
+        });
+        let client = Client::new();
+        // 표에 없는 코드도 숫자를 그대로 두고 분류대로 다룬다
+        let resp = client.get(&format!("http://{}/custom", addr)).unwrap();
+        assert_eq!(StatusCode::Other(299), resp.status());
+        assert!(resp.status().is_success());
+        assert_eq!(Some(&b"ok"[..]), resp.body().as_bytes());
+        let resp = client.get(&format!("http://{}/origin", addr)).unwrap();
+        assert_eq!(520, resp.status().as_u16());
+        assert!(resp.status().is_server_error());
+        assert!(client.get(&format!("http://{}/invalid", addr)).is_err());
+    }
+
+    #[test]
+    fn test_timeout_and_invalid_urls() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        // 커넥션을 받기만 하고 응답하지 않는 서버
+        let server = thread::spawn(move || listener.accept().unwrap());
+        let client = Client::new().timeout(Duration::from_millis(100));
+        match client.get(&format!("http://{}/", addr)) {
+            Err(ClientError::Io(e)) => assert!(matches!(
+                e.kind(),
+                io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut
+            )),
+            other => panic!("expected timeout, got {:?}", other.map(|r| r.status())),
+        }
+        server.join().unwrap();
+
+        for url in ["/relative", "https://example.com/", "http://user@host/"] {
+            assert!(
+                matches!(client.get(url), Err(ClientError::InvalidUrl(_))),
+                "{}",
+                url
+            );
+        }
+    }
+}
@@ -0,0 +1,192 @@
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+// HTTP-date (RFC 9110 5.6.7). 보낼 때는 항상 IMF-fixdate 형식을 쓰고,
+// 받을 때는 오래된 rfc850-date와 asctime-date 형식도 받아들인다.
+
+const DAY_NAMES: [&str; 7] = ["Thu", "Fri", "Sat", "Sun", "Mon", "Tue", "Wed"];
+const MONTH_NAMES: [&str; 12] = [
+    "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+];
+
+// "Sun, 06 Nov 1994 08:49:37 GMT" 형식으로 쓴다. 1초 미만은 버린다.
+pub fn fmt_http_date(time: SystemTime) -> String {
+    let t = CivilTime::from(time);
+    format!(
+        "{}, {:02} {} {} {:02}:{:02}:{:02} GMT",
+        DAY_NAMES[(t.days % 7) as usize],
+        t.day,
+        MONTH_NAMES[(t.month - 1) as usize],
+        t.year,
+        t.hour,
+        t.minute,
+        t.second
+    )
+}
+
+// 접근 로그(Common Log Format)의 "06/Nov/1994:08:49:37 +0000" 형식으로 쓴다
+pub fn fmt_clf_date(time: SystemTime) -> String {
+    let t = CivilTime::from(time);
+    format!(
+        "{:02}/{}/{}:{:02}:{:02}:{:02} +0000",
+        t.day,
+        MONTH_NAMES[(t.month - 1) as usize],
+        t.year,
+        t.hour,
+        t.minute,
+        t.second
+    )
+}
+
+// RFC 3339의 "1994-11-06T08:49:37Z" 형식으로 쓴다
+pub fn fmt_rfc3339(time: SystemTime) -> String {
+    let t = CivilTime::from(time);
+    format!(
+        "{}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        t.year, t.month, t.day, t.hour, t.minute, t.second
+    )
+}
+
+// UTC 기준으로 나눈 날짜와 시각
+struct CivilTime {
+    days: i64,
+    year: i64,
+    month: u32,
+    day: u32,
+    hour: u64,
+    minute: u64,
+    second: u64,
+}
+
+impl From<SystemTime> for CivilTime {
+    fn from(time: SystemTime) -> Self {
+        let secs = time
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or(Duration::ZERO)
+            .as_secs();
+        let days = (secs / 86400) as i64;
+        let (year, month, day) = civil_from_days(days);
+        let secs_of_day = secs % 86400;
+        CivilTime {
+            days,
+            year,
+            month,
+            day,
+            hour: secs_of_day / 3600,
+            minute: secs_of_day % 3600 / 60,
+            second: secs_of_day % 60,
+        }
+    }
+}
+
+// 세 가지 HTTP-date 형식 중 하나로 읽는다. 형식이 맞지 않으면 None이다.
+pub fn parse_http_date(value: &str) -> Option<SystemTime> {
+    let parts: Vec<&str> = value.split_whitespace().collect();
+    let (day, month, year, time) = match parts.as_slice() {
+        // IMF-fixdate: Sun, 06 Nov 1994 08:49:37 GMT
+        [_, day, month, year, time, "GMT"] if parts[0].ends_with(',') => {
+            (*day, *month, year.parse().ok()?, *time)
+        }
+        // rfc850-date: Sunday, 06-Nov-94 08:49:37 GMT
+        [_, date, time, "GMT"] if parts[0].ends_with(',') => {
+            let mut fields = date.split('-');
+            let (day, month, year) = (fields.next()?, fields.next()?, fields.next()?);
+            if fields.next().is_some() || year.len() != 2 {
+                return None;
+            }
+            // 두 자리 연도는 1970~2069로 해석한다
+            let year: i64 = year.parse().ok()?;
+            let year = if year < 70 { 2000 + year } else { 1900 + year };
+            (day, month, year, *time)
+        }
+        // asctime-date: Sun Nov  6 08:49:37 1994
+        [_, month, day, time, year] => (*day, *month, year.parse().ok()?, *time),
+        _ => return None,
+    };
+
+    let day: u32 = day.parse().ok()?;
+    let month = MONTH_NAMES.iter().position(|m| *m == month)? as u32 + 1;
+    let mut hms = time.split(':').map(|v| v.parse::<u64>().ok());
+    let (hour, minute, second) = (hms.next()??, hms.next()??, hms.next()??);
+    if hms.next().is_some() || !(1..=31).contains(&day) || hour > 23 || minute > 59 || second > 60 {
+        return None;
+    }
+
+    let days = days_from_civil(year, month, day);
+    if days < 0 {
+        return None;
+    }
+    let secs = days as u64 * 86400 + hour * 3600 + minute * 60 + second;
+    Some(UNIX_EPOCH + Duration::from_secs(secs))
+}
+
+// 1970-01-01부터 센 날 수를 그레고리력 날짜로 바꾼다 (Howard Hinnant의 civil_from_days)
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z.rem_euclid(146097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+// civil_from_days의 역변환
+fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let yoe = year.rem_euclid(400);
+    let month = month as i64;
+    let doy = (153 * (if month > 2 { month - 3 } else { month + 9 }) + 2) / 5 + day as i64 - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146097 + doe - 719468
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fmt_http_date() {
+        let time = UNIX_EPOCH + Duration::from_secs(784111777);
+        assert_eq!("Sun, 06 Nov 1994 08:49:37 GMT", fmt_http_date(time));
+        assert_eq!("Thu, 01 Jan 1970 00:00:00 GMT", fmt_http_date(UNIX_EPOCH));
+        let leap_day = UNIX_EPOCH + Duration::from_secs(951782400);
+        assert_eq!("Tue, 29 Feb 2000 00:00:00 GMT", fmt_http_date(leap_day));
+    }
+
+    #[test]
+    fn test_fmt_log_dates() {
+        let time = UNIX_EPOCH + Duration::from_secs(784111777);
+        assert_eq!("06/Nov/1994:08:49:37 +0000", fmt_clf_date(time));
+        assert_eq!("1994-11-06T08:49:37Z", fmt_rfc3339(time));
+    }
+
+    #[test]
+    fn test_parse_all_http_date_formats() {
+        let expected = Some(UNIX_EPOCH + Duration::from_secs(784111777));
+        assert_eq!(expected, parse_http_date("Sun, 06 Nov 1994 08:49:37 GMT"));
+        assert_eq!(expected, parse_http_date("Sunday, 06-Nov-94 08:49:37 GMT"));
+        assert_eq!(expected, parse_http_date("Sun Nov  6 08:49:37 1994"));
+
+        for invalid in [
+            "",
+            "Sun, 06 Nov 1994 08:49:37 PST",
+            "Sun, 06 Foo 1994 08:49:37 GMT",
+            "Sun, 06 Nov 1994 25:00:00 GMT",
+            "yesterday",
+        ] {
+            assert_eq!(None, parse_http_date(invalid), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn test_round_trip() {
+        for secs in [0, 68169600, 951782400, 1700000000, 4102444800] {
+            let time = UNIX_EPOCH + Duration::from_secs(secs);
+            assert_eq!(Some(time), parse_http_date(&fmt_http_date(time)));
+        }
+    }
+}
@@ -0,0 +1,208 @@
+use std::fmt;
+
+// HTTP 헤더 모음. 이름은 대소문자를 구분하지 않고 찾으며,
+// 같은 이름의 헤더(예: Set-Cookie)를 여러 개 담을 수 있고 들어온 순서를 그대로 유지한다.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct HeaderMap {
+    entries: Vec<(String, String)>,
+}
+
+impl HeaderMap {
+    pub fn new() -> Self {
+        HeaderMap {
+            entries: Vec::new(),
+        }
+    }
+
+    // 이름이 같은 헤더 중 첫 번째 값을 돌려준다
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.entries
+            .iter()
+            .find(|(k, _)| k.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.as_str())
+    }
+
+    // 이름이 같은 헤더의 값을 들어온 순서대로 모두 돌려준다
+    pub fn get_all(&self, name: &str) -> Vec<&str> {
+        self.entries
+            .iter()
+            .filter(|(k, _)| k.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.as_str())
+            .collect()
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.get(name).is_some()
+    }
+
+    // 같은 이름의 기존 헤더를 모두 지우고 값을 하나로 설정한다.
+    // 기존 헤더가 있으면 첫 번째 자리를 유지한다.
+    pub fn insert(&mut self, name: impl Into<String>, value: impl Into<String>) {
+        let name = name.into();
+        let value = value.into();
+        match self
+            .entries
+            .iter()
+            .position(|(k, _)| k.eq_ignore_ascii_case(&name))
+        {
+            Some(pos) => {
+                self.entries[pos] = (name, value);
+                let mut i = pos + 1;
+                while i < self.entries.len() {
+                    if self.entries[i].0.eq_ignore_ascii_case(&self.entries[pos].0) {
+                        self.entries.remove(i);
+                    } else {
+                        i += 1;
+                    }
+                }
+            }
+            None => self.entries.push((name, value)),
+        }
+    }
+
+    // 기존 헤더를 그대로 두고 같은 이름의 헤더를 하나 더 붙인다
+    pub fn append(&mut self, name: impl Into<String>, value: impl Into<String>) {
+        self.entries.push((name.into(), value.into()));
+    }
+
+    // 이름이 같은 헤더를 모두 지우고 첫 번째 값을 돌려준다
+    pub fn remove(&mut self, name: &str) -> Option<String> {
+        let mut removed = None;
+        self.entries.retain(|(k, v)| {
+            if k.eq_ignore_ascii_case(name) {
+                removed.get_or_insert_with(|| v.clone());
+                false
+            } else {
+                true
+            }
+        });
+        removed
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.entries.iter().map(|(k, v)| (k.as_str(), v.as_str()))
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    // Content-Length 값. 헤더가 없거나 10진수가 아니면 None이다.
+    pub fn content_length(&self) -> Option<usize> {
+        let value = self.get("Content-Length")?;
+        if value.is_empty() || !value.bytes().all(|b| b.is_ascii_digit()) {
+            return None;
+        }
+        value.parse().ok()
+    }
+
+    pub fn content_type(&self) -> Option<&str> {
+        self.get("Content-Type")
+    }
+
+    pub fn host(&self) -> Option<&str> {
+        self.get("Host")
+    }
+
+    // Connection 헤더에 담긴 옵션들. 여러 헤더와 쉼표로 나뉜 목록을 모두 펼친다.
+    pub fn connection(&self) -> Vec<&str> {
+        self.get_all("Connection")
+            .into_iter()
+            .flat_map(|v| v.split(','))
+            .map(str::trim)
+            .filter(|o| !o.is_empty())
+            .collect()
+    }
+
+    // Connection 헤더에 해당 옵션(예: "close")이 들어 있는지 대소문자 구분 없이 확인한다
+    pub fn has_connection_option(&self, option: &str) -> bool {
+        self.connection()
+            .iter()
+            .any(|o| o.eq_ignore_ascii_case(option))
+    }
+}
+
+impl<K: Into<String>, V: Into<String>> FromIterator<(K, V)> for HeaderMap {
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        let mut headers = HeaderMap::new();
+        for (k, v) in iter {
+            headers.append(k, v);
+        }
+        headers
+    }
+}
+
+impl fmt::Display for HeaderMap {
+    // 헤더 블록을 "이름: 값\r\n" 행의 나열로 쓴다
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (k, v) in self.iter() {
+            write!(f, "{}: {}\r\n", k, v)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_case_insensitive_lookup() {
+        let mut headers = HeaderMap::new();
+        headers.append("Content-Type", "text/html");
+        assert_eq!(Some("text/html"), headers.get("content-type"));
+        assert_eq!(Some("text/html"), headers.content_type());
+        assert!(headers.contains("CONTENT-TYPE"));
+        assert_eq!(None, headers.get("Content-Length"));
+    }
+
+    #[test]
+    fn test_multi_valued_headers() {
+        let mut headers = HeaderMap::new();
+        headers.append("Set-Cookie", "a=1");
+        headers.append("Content-Type", "text/html");
+        headers.append("set-cookie", "b=2");
+        assert_eq!(vec!["a=1", "b=2"], headers.get_all("Set-Cookie"));
+        assert_eq!(3, headers.len());
+
+        headers.insert("SET-COOKIE", "c=3");
+        assert_eq!(vec!["c=3"], headers.get_all("set-cookie"));
+        assert_eq!(
+            vec![("SET-COOKIE", "c=3"), ("Content-Type", "text/html")],
+            headers.iter().collect::<Vec<_>>()
+        );
+
+        assert_eq!(Some("c=3".to_string()), headers.remove("Set-Cookie"));
+        assert_eq!(1, headers.len());
+    }
+
+    #[test]
+    fn test_typed_accessors() {
+        let headers: HeaderMap = [
+            ("Host", "localhost:3000"),
+            ("Content-Length", "42"),
+            ("Connection", "keep-alive, Upgrade"),
+            ("connection", "TE"),
+        ]
+        .into_iter()
+        .collect();
+        assert_eq!(Some("localhost:3000"), headers.host());
+        assert_eq!(Some(42), headers.content_length());
+        assert_eq!(vec!["keep-alive", "Upgrade", "TE"], headers.connection());
+        assert!(headers.has_connection_option("upgrade"));
+        assert!(!headers.has_connection_option("close"));
+
+        let headers: HeaderMap = [("Content-Length", "+42")].into_iter().collect();
+        assert_eq!(None, headers.content_length());
+    }
+
+    #[test]
+    fn test_display_keeps_order() {
+        let headers: HeaderMap = [("B", "2"), ("A", "1")].into_iter().collect();
+        assert_eq!("B: 2\r\nA: 1\r\n", headers.to_string());
+    }
+}
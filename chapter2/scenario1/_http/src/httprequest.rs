@@ -1,8 +1,70 @@
-use std::collections::HashMap;
+use super::chunked;
+use super::headers::HeaderMap;
+use super::status::StatusCode;
+use super::uri::{Uri, UriError};
+use std::error;
+use std::fmt;
+use std::io::{self, BufRead, Read, Write};
+use std::str;
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Clone)]
 pub enum Resource {
-    Path(String),
+    // origin-form("/a?b") 또는 absolute-form("http://host/a?b") 요청 대상
+    Path(Uri),
+    // CONNECT 요청의 authority-form("host:port") 요청 대상
+    Authority(String),
+    // OPTIONS 요청의 asterisk-form("*") 요청 대상
+    Asterisk,
+}
+
+impl Resource {
+    // 요청 행의 요청 대상을 메서드에 맞는 형식으로 파싱한다 (RFC 9112 3.2)
+    pub fn parse(target: &str, method: &Method) -> Result<Resource, UriError> {
+        match (method, target) {
+            (Method::Options, "*") => Ok(Resource::Asterisk),
+            (Method::Connect, authority) => {
+                if authority.contains(['/', '?', '#', '@']) || !authority.contains(':') {
+                    return Err(UriError::Malformed);
+                }
+                Ok(Resource::Authority(authority.to_string()))
+            }
+            (_, target) => Uri::parse(target).map(Resource::Path),
+        }
+    }
+
+    pub fn uri(&self) -> Option<&Uri> {
+        match self {
+            Resource::Path(uri) => Some(uri),
+            _ => None,
+        }
+    }
+
+    // 디코딩된 경로. 경로가 없는 요청 대상이면 "*" 또는 authority를 돌려준다.
+    pub fn path(&self) -> &str {
+        match self {
+            Resource::Path(uri) => uri.path(),
+            Resource::Authority(authority) => authority,
+            Resource::Asterisk => "*",
+        }
+    }
+
+    // 디코딩된 경로 세그먼트. 경로가 없는 요청 대상이면 빈 목록이다.
+    pub fn segments(&self) -> &[String] {
+        match self {
+            Resource::Path(uri) => uri.segments(),
+            _ => &[],
+        }
+    }
+}
+
+impl fmt::Display for Resource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Resource::Path(uri) => uri.fmt(f),
+            Resource::Authority(authority) => f.write_str(authority),
+            Resource::Asterisk => f.write_str("*"),
+        }
+    }
 }
 
 #[derive(Debug)]
@@ -10,115 +72,559 @@ pub struct HttpRequest {
     pub method: Method,
     pub version: Version,
     pub resource: Resource,
-    pub headers: HashMap<String, String>,
-    pub msg_body: String,
-}
-
-impl From<String> for HttpRequest {
-    fn from(req: String) -> Self {
-        let mut parsed_method = Method::Uninitialized;
-        let mut parsed_version = Version::V1_1;
-        let mut parsed_resource = Resource::Path("".to_string());
-        let mut parsed_headers = HashMap::new();
-        let mut parsed_msg_body = "";
-
-        // 유입되는 HTTP 요청의 각 행을 읽는다
-        for line in req.lines() {
-            // 해당 행이 요청 행이면 process_req_line() 함수를 호출한다
-            if line.contains("HTTP") {
-                let (method, resource, version) = process_req_line(line);
-                parsed_method = method;
-                parsed_version = version;
-                parsed_resource = resource;
-            // 해당 행이 헤더 행이면 process_header_line() 함수를 호출한다
-            } else if line.contains(":") {
-                let (key, value) = process_header_line(line);
-                parsed_headers.insert(key, value);
-            //  해당 행이 빈 행이면 아무 것도 하지 않는다
-            } else if line.len() == 0 {
-                // 위의 어떤 행도 아니면 메시지 바디로 취급한다
-            } else {
-                parsed_msg_body = line;
+    pub headers: HeaderMap,
+    pub msg_body: Vec<u8>,
+    pub trailers: HeaderMap,
+}
+
+#[derive(Debug)]
+pub enum ParseError {
+    // 하부 리더에서 발생한 I/O 에러
+    Io(io::Error),
+    // 요청의 첫 바이트를 읽기 전에 커넥션이 닫혔다
+    ConnectionClosed,
+    // 요청을 다 읽기 전에 스트림이 끝났다
+    UnexpectedEof,
+    // 행 중간에 단독 CR이 들어 있다
+    InvalidLineEnding,
+    // 요청 행이 "메서드 SP 대상 SP 버전" 형식이 아니다
+    InvalidRequestLine,
+    // 요청 대상을 URI로 해석할 수 없다
+    InvalidTarget(UriError),
+    // 헤더 행이 "이름: 값" 형식이 아니다
+    InvalidHeader,
+    // Content-Length 값이 10진수가 아니다
+    InvalidContentLength,
+    // 청크 크기 행이나 청크 구분자가 잘못되었다
+    InvalidChunk,
+    // Transfer-Encoding과 Content-Length가 함께 왔다
+    AmbiguousBodyLength,
+    // 지원하지 않는 Transfer-Encoding
+    UnsupportedTransferEncoding,
+    // HTTP/1.x가 아닌 버전 (예: HTTP/2.0)
+    UnsupportedVersion,
+    // 요청 행이 Limits::max_request_line을 넘는다
+    RequestLineTooLong,
+    // 헤더가 Limits::max_header_bytes나 Limits::max_header_count를 넘는다
+    HeaderTooLarge,
+    // 본문이 Limits::max_body_bytes를 넘는다
+    BodyTooLarge,
+    // 응답의 상태 행이 "버전 SP 상태 코드 SP 사유" 형식이 아니다
+    InvalidStatusLine,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseError::Io(e) => write!(f, "i/o error: {}", e),
+            ParseError::ConnectionClosed => write!(f, "connection closed"),
+            ParseError::UnexpectedEof => write!(f, "unexpected end of request"),
+            ParseError::InvalidLineEnding => write!(f, "invalid line ending"),
+            ParseError::InvalidRequestLine => write!(f, "invalid request line"),
+            ParseError::InvalidTarget(e) => write!(f, "{}", e),
+            ParseError::InvalidHeader => write!(f, "invalid header line"),
+            ParseError::InvalidContentLength => write!(f, "invalid Content-Length"),
+            ParseError::InvalidChunk => write!(f, "invalid chunk"),
+            ParseError::AmbiguousBodyLength => {
+                write!(f, "both Transfer-Encoding and Content-Length present")
             }
+            ParseError::UnsupportedTransferEncoding => write!(f, "unsupported Transfer-Encoding"),
+            ParseError::UnsupportedVersion => write!(f, "unsupported HTTP version"),
+            ParseError::RequestLineTooLong => write!(f, "request line too long"),
+            ParseError::HeaderTooLarge => write!(f, "request header too large"),
+            ParseError::BodyTooLarge => write!(f, "request body too large"),
+            ParseError::InvalidStatusLine => write!(f, "invalid status line"),
+        }
+    }
+}
+
+impl ParseError {
+    // 서버가 이 에러로 요청을 거절할 때 보낼 상태 코드.
+    // I/O 에러는 원인(시간 초과 등)에 따라 다르므로 서버가 정한다.
+    pub fn status(&self) -> StatusCode {
+        match self {
+            ParseError::RequestLineTooLong => StatusCode::UriTooLong,
+            ParseError::HeaderTooLarge => StatusCode::RequestHeaderFieldsTooLarge,
+            ParseError::BodyTooLarge => StatusCode::ContentTooLarge,
+            // 모르는 전송 코딩은 501로 답한다 (RFC 9112 6.1)
+            ParseError::UnsupportedTransferEncoding => StatusCode::NotImplemented,
+            // 주 버전이 다르면 505로 답한다 (RFC 9112 2.3)
+            ParseError::UnsupportedVersion => StatusCode::HttpVersionNotSupported,
+            _ => StatusCode::BadRequest,
+        }
+    }
+}
+
+impl error::Error for ParseError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            ParseError::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for ParseError {
+    fn from(e: io::Error) -> Self {
+        match e.kind() {
+            io::ErrorKind::UnexpectedEof => ParseError::UnexpectedEof,
+            _ => ParseError::Io(e),
+        }
+    }
+}
+
+// 요청 하나에 허용하는 최대 크기. 넘는 요청은 끝까지 읽지 않고 에러로 끝낸다.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Limits {
+    // 요청 행의 바이트 수. 요청 행 앞의 빈 행도 함께 센다.
+    pub max_request_line: usize,
+    // 헤더 행들을 합친 바이트 수와 헤더 개수. 청크 트레일러에도 같은 제한을 따로 둔다.
+    pub max_header_bytes: usize,
+    pub max_header_count: usize,
+    // 본문 바이트 수. chunked 본문은 청크를 모두 합친 크기로 잰다.
+    pub max_body_bytes: usize,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Limits {
+            max_request_line: 8 * 1024,
+            max_header_bytes: 16 * 1024,
+            max_header_count: 100,
+            max_body_bytes: 1024 * 1024,
+        }
+    }
+}
+
+impl HttpRequest {
+    // 리더에서 HTTP 요청 하나를 기본 크기 제한으로 읽어 파싱한다
+    pub fn from_reader<R: BufRead>(reader: &mut R) -> Result<HttpRequest, ParseError> {
+        HttpRequest::from_reader_with_limits(reader, &Limits::default())
+    }
+
+    // 리더에서 HTTP 요청 하나를 바이트 단위로 읽어 파싱한다.
+    // 본문 뒤의 바이트는 리더에 그대로 남으므로 같은 리더로 다음 요청을 이어서 읽을 수 있다.
+    pub fn from_reader_with_limits<R: BufRead>(
+        reader: &mut R,
+        limits: &Limits,
+    ) -> Result<HttpRequest, ParseError> {
+        let mut line_budget = limits.max_request_line;
+        // 요청 행 앞의 빈 행은 무시한다 (RFC 9112 2.2)
+        let request_line = loop {
+            match read_line(reader, &mut line_budget) {
+                Ok(Some(line)) if line.is_empty() => continue,
+                Ok(Some(line)) => break line,
+                Ok(None) => return Err(ParseError::ConnectionClosed),
+                Err(ParseError::HeaderTooLarge) => return Err(ParseError::RequestLineTooLong),
+                Err(e) => return Err(e),
+            }
+        };
+        let request_line =
+            str::from_utf8(&request_line).map_err(|_| ParseError::InvalidRequestLine)?;
+        let (method, resource, version) = process_req_line(request_line)?;
+
+        // 빈 행이 나올 때까지 헤더 행을 읽는다
+        let headers = read_header_block(reader, limits)?;
+
+        // 본문은 Transfer-Encoding이 chunked면 청크 단위로, 아니면 Content-Length 만큼만 정확히 읽는다
+        let mut msg_body = Vec::new();
+        let mut trailers = HeaderMap::new();
+        let transfer_encoding = headers.get("Transfer-Encoding");
+        let content_length = headers.get("Content-Length");
+        match (transfer_encoding, content_length) {
+            // 두 헤더가 함께 오면 본문 길이가 모호하므로 거부한다 (RFC 9112 6.1)
+            (Some(_), Some(_)) => return Err(ParseError::AmbiguousBodyLength),
+            // HTTP/1.0에는 Transfer-Encoding이 없으므로 중간 장비가 무시했을 수 있다 (RFC 9112 6.1)
+            (Some(_), None) if version == Version::V1_0 => {
+                return Err(ParseError::UnsupportedTransferEncoding);
+            }
+            (Some(_), None) => {
+                let codings = headers.get_all("Transfer-Encoding");
+                if codings.len() != 1 || !codings[0].eq_ignore_ascii_case("chunked") {
+                    return Err(ParseError::UnsupportedTransferEncoding);
+                }
+                trailers = chunked::read_chunked_body(reader, &mut msg_body, limits)?;
+            }
+            (None, Some(_)) => {
+                let length = parse_content_length(&headers.get_all("Content-Length"))?;
+                // 본문을 읽기 전에 선언된 길이로 거절한다
+                if length > limits.max_body_bytes {
+                    return Err(ParseError::BodyTooLarge);
+                }
+                msg_body = vec![0; length];
+                reader.read_exact(&mut msg_body)?;
+            }
+            (None, None) => {}
+        }
+
+        Ok(HttpRequest {
+            method,
+            version,
+            resource,
+            headers,
+            msg_body,
+            trailers,
+        })
+    }
+}
+
+impl HttpRequest {
+    // 응답을 보낸 뒤 커넥션을 유지해야 하는지 Connection 헤더와 버전으로 판단한다
+    pub fn keep_alive(&self) -> bool {
+        if self.headers.has_connection_option("close") {
+            false
+        } else if self.headers.has_connection_option("keep-alive") {
+            true
+        } else {
+            self.version.keep_alive_by_default()
+        }
+    }
+}
+
+impl HttpRequest {
+    pub fn builder() -> HttpRequestBuilder {
+        HttpRequestBuilder::new()
+    }
+
+    // 요청 행, 헤더, 본문을 그대로 쓴다. 요청 대상은 resource에 든 형식 그대로 쓴다.
+    // 본문 길이는 Content-Length로 알리며, 본문이 없어도 본문을 싣는 메서드면 0을 쓴다.
+    pub fn send_request(&self, write_stream: &mut impl Write) -> io::Result<()> {
+        let mut head = format!("{} {} {}\r\n", self.method, self.resource, self.version);
+        for (k, v) in self.headers.iter() {
+            if k.eq_ignore_ascii_case("Content-Length")
+                || k.eq_ignore_ascii_case("Transfer-Encoding")
+            {
+                continue;
+            }
+            head.push_str(&format!("{}: {}\r\n", k, v));
+        }
+        if !self.msg_body.is_empty()
+            || matches!(self.method, Method::Post | Method::Put | Method::Patch)
+        {
+            head.push_str(&format!("Content-Length: {}\r\n", self.msg_body.len()));
+        }
+        head.push_str("\r\n");
+        write_stream.write_all(head.as_bytes())?;
+        write_stream.write_all(&self.msg_body)?;
+        write_stream.flush()
+    }
+}
+
+// HttpRequest를 단계적으로 만든다. 요청 대상은 build에서 파싱한다.
+//
+// let request = HttpRequest::builder()
+//     .method(Method::Post)
+//     .target("http://localhost:3000/api/shipping/orders")
+//     .header("Content-Type", "application/json")
+//     .body(r#"{"order_date":"2 Feb 2020","order_status":"Pending"}"#)
+//     .build()?;
+#[derive(Debug)]
+pub struct HttpRequestBuilder {
+    method: Method,
+    target: String,
+    version: Version,
+    headers: HeaderMap,
+    body: Vec<u8>,
+}
+
+impl HttpRequestBuilder {
+    pub fn new() -> Self {
+        HttpRequestBuilder {
+            method: Method::Get,
+            target: "/".to_string(),
+            version: Version::V1_1,
+            headers: HeaderMap::new(),
+            body: Vec::new(),
+        }
+    }
+
+    pub fn method(mut self, method: Method) -> Self {
+        self.method = method;
+        self
+    }
+
+    // origin-form("/a?b") 또는 absolute-form("http://host/a?b") 요청 대상
+    pub fn target(mut self, target: impl Into<String>) -> Self {
+        self.target = target.into();
+        self
+    }
+
+    pub fn version(mut self, version: Version) -> Self {
+        self.version = version;
+        self
+    }
+
+    // 같은 이름의 헤더가 있어도 덧붙인다
+    pub fn header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.headers.append(name, value);
+        self
+    }
+
+    pub fn body(mut self, body: impl Into<Vec<u8>>) -> Self {
+        self.body = body.into();
+        self
+    }
+
+    pub fn build(self) -> Result<HttpRequest, ParseError> {
+        let resource =
+            Resource::parse(&self.target, &self.method).map_err(ParseError::InvalidTarget)?;
+        Ok(HttpRequest {
+            method: self.method,
+            version: self.version,
+            resource,
+            headers: self.headers,
+            msg_body: self.body,
+            trailers: HeaderMap::new(),
+        })
+    }
+}
+
+impl Default for HttpRequestBuilder {
+    fn default() -> Self {
+        HttpRequestBuilder::new()
+    }
+}
+
+impl TryFrom<String> for HttpRequest {
+    type Error = ParseError;
+
+    fn try_from(req: String) -> Result<Self, Self::Error> {
+        HttpRequest::from_reader(&mut req.as_bytes())
+    }
+}
+
+// 빈 행이 나올 때까지 "이름: 값" 행을 읽는다. 헤더와 청크 트레일러에 함께 쓰인다.
+pub(crate) fn read_header_block<R: BufRead>(
+    reader: &mut R,
+    limits: &Limits,
+) -> Result<HeaderMap, ParseError> {
+    let mut headers = HeaderMap::new();
+    let mut budget = limits.max_header_bytes;
+    for count in 0.. {
+        let line = read_line(reader, &mut budget)?.ok_or(ParseError::UnexpectedEof)?;
+        if line.is_empty() {
+            break;
+        }
+        if count == limits.max_header_count {
+            return Err(ParseError::HeaderTooLarge);
         }
-        // 유입된 HTTP 요청을 HttpReqeust 구조체로 파싱한다
-        HttpRequest {
-            method: parsed_method,
-            version: parsed_version,
-            resource: parsed_resource,
-            headers: parsed_headers,
-            msg_body: parsed_msg_body.to_string(),
+        // 공백으로 시작하는 행은 앞 헤더 값을 잇는 obs-fold다. 이어 붙이는 방식이
+        // 구현마다 달라 요청 밀반입에 쓰일 수 있으므로 받지 않는다 (RFC 9112 5.2)
+        if line.starts_with(b" ") || line.starts_with(b"\t") {
+            return Err(ParseError::InvalidHeader);
         }
+        let line = str::from_utf8(&line).map_err(|_| ParseError::InvalidHeader)?;
+        let (key, value) = process_header_line(line)?;
+        headers.append(key, value);
     }
+    Ok(headers)
 }
 
-fn process_req_line(s: &str) -> (Method, Resource, Version) {
-    // 요청 행을 공백으로 구분된 개별 덩어리로 파싱한다
-    let mut words = s.split_whitespace();
+// LF로 끝나는 행 하나를 읽어 행 끝(CRLF 또는 LF)을 뗀 바이트를 돌려준다.
+// 아무것도 읽지 못하고 스트림이 끝나면 None을 돌려준다.
+// 읽은 바이트만큼 budget을 줄이고, 행이 끝나기 전에 budget을 다 쓰면 HeaderTooLarge를 돌려준다.
+pub(crate) fn read_line<R: BufRead>(
+    reader: &mut R,
+    budget: &mut usize,
+) -> Result<Option<Vec<u8>>, ParseError> {
+    let mut line = Vec::new();
+    let read = Read::take(&mut *reader, *budget as u64).read_until(b'\n', &mut line)?;
+    if read == 0 && *budget > 0 {
+        return Ok(None);
+    }
+    *budget -= read;
+    if line.pop() != Some(b'\n') {
+        return Err(if *budget == 0 {
+            ParseError::HeaderTooLarge
+        } else {
+            ParseError::UnexpectedEof
+        });
+    }
+    if line.last() == Some(&b'\r') {
+        line.pop();
+    }
+    if line.contains(&b'\r') {
+        return Err(ParseError::InvalidLineEnding);
+    }
+    Ok(Some(line))
+}
+
+fn process_req_line(s: &str) -> Result<(Method, Resource, Version), ParseError> {
+    // 요청 행을 단일 공백으로 구분된 세 덩어리로 파싱한다
+    let mut words = s.split(' ');
     // 요청 행의 첫 번째 부분에서 HTTP 메서드를 추출한다
-    let method = words.next().unwrap();
+    let method = words.next().filter(|m| is_token(m));
     // 요청 행의 두 번째 부분에서 리소스(URI/URL)을 추출한다
-    let resource = words.next().unwrap();
+    let resource = words.next().filter(|r| !r.is_empty());
     // 요청 행의 세 번째 부분에서 HTTP 버전을 추출한다
-    let version = words.next().unwrap();
+    let version = words.next().filter(|v| is_http_version(v));
 
-    (
-        method.into(),
-        Resource::Path(resource.to_string()),
-        version.into(),
-    )
+    match (method, resource, version, words.next()) {
+        (Some(method), Some(resource), Some(version), None) => {
+            let version: Version = version.into();
+            if version == Version::Uninitialized {
+                return Err(ParseError::UnsupportedVersion);
+            }
+            let method: Method = method.into();
+            let resource = Resource::parse(resource, &method).map_err(ParseError::InvalidTarget)?;
+            Ok((method, resource, version))
+        }
+        _ => Err(ParseError::InvalidRequestLine),
+    }
 }
 
-fn process_header_line(s: &str) -> (String, String) {
-    // 헤더 행을 구분자(';')로 나눠진 단어로 파싱한다
-    let mut header_items = s.split(":");
-    let mut key = String::from("");
-    let mut value = String::from("");
-    // 헤도의 키 부분을 추출한다
-    if let Some(k) = header_items.next() {
-        key = k.to_string();
+fn process_header_line(s: &str) -> Result<(String, String), ParseError> {
+    // 헤더 행을 첫 번째 구분자(':')에서 이름과 값으로 나눈다. 값에 들어 있는 ':'는 그대로 둔다.
+    let (key, value) = s.split_once(':').ok_or(ParseError::InvalidHeader)?;
+    // 헤더 이름은 token이어야 하며 ':' 앞에 공백을 둘 수 없다 (RFC 9112 5.1)
+    if !is_token(key) {
+        return Err(ParseError::InvalidHeader);
     }
-    // 헤더의 값 부분을 추출한다
-    if let Some(v) = header_items.next() {
-        value = v.to_string()
+    // 헤더 값 앞뒤의 공백은 값에 속하지 않는다
+    let value = value.trim_matches([' ', '\t']);
+    // 값에는 탭 말고 제어 문자(NUL 등)가 들어갈 수 없다 (RFC 9110 5.5)
+    if value.bytes().any(|b| (b < 0x20 && b != b'\t') || b == 0x7f) {
+        return Err(ParseError::InvalidHeader);
     }
 
-    (key, value)
+    Ok((key.to_string(), value.to_string()))
 }
 
-#[derive(Debug, PartialEq)]
+// Content-Length 헤더 값들을 해석한다. 같은 값이 반복된 경우만 허용하고,
+// 서로 다른 값이 섞여 있으면 본문 경계가 모호하므로 거부한다 (RFC 9112 6.3)
+pub(crate) fn parse_content_length(values: &[&str]) -> Result<usize, ParseError> {
+    let mut length = None;
+    for value in values.iter().flat_map(|v| v.split(',')) {
+        let value = value.trim();
+        if value.is_empty() || !value.bytes().all(|b| b.is_ascii_digit()) {
+            return Err(ParseError::InvalidContentLength);
+        }
+        let parsed: usize = value
+            .parse()
+            .map_err(|_| ParseError::InvalidContentLength)?;
+        if length.is_some_and(|l| l != parsed) {
+            return Err(ParseError::InvalidContentLength);
+        }
+        length = Some(parsed);
+    }
+    length.ok_or(ParseError::InvalidContentLength)
+}
+
+// RFC 9110 5.6.2의 token 문자로만 이루어졌는지 확인한다
+fn is_token(s: &str) -> bool {
+    !s.is_empty()
+        && s.bytes()
+            .all(|b| b.is_ascii_alphanumeric() || b"!#$%&'*+-.^_`|~".contains(&b))
+}
+
+// "HTTP/x.y" 형식인지 확인한다
+fn is_http_version(s: &str) -> bool {
+    match s.strip_prefix("HTTP/").map(str::as_bytes) {
+        Some([major, b'.', minor]) => major.is_ascii_digit() && minor.is_ascii_digit(),
+        _ => false,
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
 pub enum Method {
     Get,
     Post,
+    Put,
+    Delete,
+    Patch,
+    Head,
+    Options,
+    Connect,
+    Trace,
+    // 표준에 없는 확장 메서드 (예: PROPFIND)
+    Extension(String),
     Uninitialized,
 }
 
 impl From<&str> for Method {
     fn from(s: &str) -> Method {
+        // 메서드 이름은 대소문자를 구분한다 (RFC 9110 9.1)
         match s {
             "GET" => Method::Get,
             "POST" => Method::Post,
+            "PUT" => Method::Put,
+            "DELETE" => Method::Delete,
+            "PATCH" => Method::Patch,
+            "HEAD" => Method::Head,
+            "OPTIONS" => Method::Options,
+            "CONNECT" => Method::Connect,
+            "TRACE" => Method::Trace,
+            m if is_token(m) => Method::Extension(m.to_string()),
             _ => Method::Uninitialized,
         }
     }
 }
 
-#[derive(Debug, PartialEq)]
+impl Method {
+    pub fn as_str(&self) -> &str {
+        match self {
+            Method::Get => "GET",
+            Method::Post => "POST",
+            Method::Put => "PUT",
+            Method::Delete => "DELETE",
+            Method::Patch => "PATCH",
+            Method::Head => "HEAD",
+            Method::Options => "OPTIONS",
+            Method::Connect => "CONNECT",
+            Method::Trace => "TRACE",
+            Method::Extension(m) => m.as_str(),
+            Method::Uninitialized => "",
+        }
+    }
+}
+
+impl fmt::Display for Method {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
 pub enum Version {
+    V1_0,
     V1_1,
-    V2_0,
     Uninitialized,
 }
 
 impl From<&str> for Version {
     fn from(s: &str) -> Version {
-        match s {
-            "HTTP/1.1" => Version::V1_1,
+        // 부 버전이 더 높은 HTTP/1.x는 지원하는 가장 높은 부 버전인 1.1로 다룬다 (RFC 9110 2.5).
+        // HTTP/2 이상은 텍스트 요청 행으로 오지 않으므로 지원하지 않는 버전이다.
+        match s.strip_prefix("HTTP/1.").map(str::as_bytes) {
+            Some(b"0") => Version::V1_0,
+            Some([minor]) if minor.is_ascii_digit() => Version::V1_1,
             _ => Version::Uninitialized,
         }
     }
 }
 
+impl Version {
+    pub fn as_str(&self) -> &str {
+        match self {
+            Version::V1_0 => "HTTP/1.0",
+            Version::V1_1 => "HTTP/1.1",
+            Version::Uninitialized => "",
+        }
+    }
+
+    // 해당 버전에서 Connection 헤더가 없을 때 커넥션을 유지하는지 여부.
+    // HTTP/1.0은 기본이 close이고, HTTP/1.1부터는 기본이 keep-alive다.
+    pub fn keep_alive_by_default(&self) -> bool {
+        *self == Version::V1_1
+    }
+}
+
+impl fmt::Display for Version {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -129,23 +635,258 @@ mod tests {
         assert_eq!(m, Method::Get);
     }
 
+    #[test]
+    fn test_extension_method_into() {
+        let m: Method = "DELETE".into();
+        assert_eq!(m, Method::Delete);
+        let m: Method = "PROPFIND".into();
+        assert_eq!(m, Method::Extension("PROPFIND".to_string()));
+        assert_eq!("PROPFIND", m.to_string());
+        // 메서드는 대소문자를 구분한다
+        let m: Method = "get".into();
+        assert_eq!(m, Method::Extension("get".to_string()));
+        let m: Method = "G(T".into();
+        assert_eq!(m, Method::Uninitialized);
+    }
+
     #[test]
     fn test_version_into() {
         let m: Version = "HTTP/1.1".into();
         assert_eq!(m, Version::V1_1);
+        let m: Version = "HTTP/1.0".into();
+        assert_eq!(m, Version::V1_0);
+        let m: Version = "HTTP/1.2".into();
+        assert_eq!(m, Version::V1_1);
+        let m: Version = "HTTP/2.0".into();
+        assert_eq!(m, Version::Uninitialized);
+        let m: Version = "HTTP/3.0".into();
+        assert_eq!(m, Version::Uninitialized);
+    }
+
+    #[test]
+    fn test_read_request_targets() {
+        let req: HttpRequest = "OPTIONS * HTTP/1.1\r\n\r\n".to_string().try_into().unwrap();
+        assert_eq!(Resource::Asterisk, req.resource);
+        let req: HttpRequest = "CONNECT localhost:3000 HTTP/1.1\r\n\r\n"
+            .to_string()
+            .try_into()
+            .unwrap();
+        assert_eq!(Resource::Authority("localhost:3000".into()), req.resource);
+        let req: HttpRequest =
+            "GET http://localhost:3000/api/shipping/orders?order_id=2 HTTP/1.1\r\n\r\n"
+                .to_string()
+                .try_into()
+                .unwrap();
+        let uri = req.resource.uri().unwrap();
+        assert_eq!(Some("localhost:3000"), uri.authority());
+        assert_eq!(vec!["api", "shipping", "orders"], req.resource.segments());
+        assert_eq!(Some("2"), uri.query().get("order_id"));
+
+        let result: Result<HttpRequest, _> =
+            "GET /../etc/passwd HTTP/1.1\r\n\r\n".to_string().try_into();
+        assert!(matches!(
+            result,
+            Err(ParseError::InvalidTarget(UriError::PathTraversal))
+        ));
+        let result: Result<HttpRequest, _> = "GET * HTTP/1.1\r\n\r\n".to_string().try_into();
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_keep_alive_defaults() {
+        let cases = [
+            ("GET / HTTP/1.1\r\n\r\n", true),
+            ("GET / HTTP/1.1\r\nConnection: close\r\n\r\n", false),
+            ("GET / HTTP/1.0\r\n\r\n", false),
+            ("GET / HTTP/1.0\r\nConnection: Keep-Alive\r\n\r\n", true),
+            (
+                "GET / HTTP/1.0\r\nconnection: keep-alive, Upgrade\r\n\r\n",
+                true,
+            ),
+        ];
+        for (raw, expected) in cases {
+            let req: HttpRequest = raw.to_string().try_into().unwrap();
+            assert_eq!(expected, req.keep_alive(), "{}", raw);
+        }
     }
-    
+
     #[test]
     fn test_read_http() {
         let s: String = String::from("GET /greeting HTTP/1.1\r\nHost: localhost:3000\r\nUser-Agent: curl/7.64.1\r\nAccept: */*\r\n\r\n");
-        let mut headers_expected = HashMap::new();
-        headers_expected.insert("Host".into(), " localhost".into());
-        headers_expected.insert("Accept".into(), " */*".into());
-        headers_expected.insert("User-Agent".into(), " curl/7.64.1".into());
-        let req: HttpRequest = s.into();
+        let mut headers_expected = HeaderMap::new();
+        headers_expected.append("Host", "localhost:3000");
+        headers_expected.append("User-Agent", "curl/7.64.1");
+        headers_expected.append("Accept", "*/*");
+        let req: HttpRequest = s.try_into().unwrap();
         assert_eq!(Method::Get, req.method);
         assert_eq!(Version::V1_1, req.version);
-        assert_eq!(Resource::Path("/greeting".to_string()), req.resource);
+        assert_eq!("/greeting", req.resource.path());
         assert_eq!(headers_expected, req.headers);
     }
+
+    #[test]
+    fn test_read_body_by_content_length() {
+        let raw: &[u8] = b"POST /api/shipping/orders HTTP/1.1\r\nContent-Length: 4\r\n\r\n\x00\xff\r\nGET / HTTP/1.1\r\n\r\n";
+        let mut reader = raw;
+        let req = HttpRequest::from_reader(&mut reader).unwrap();
+        assert_eq!(Method::Post, req.method);
+        assert_eq!(vec![0x00, 0xff, b'\r', b'\n'], req.msg_body);
+        // 본문 뒤의 바이트는 다음 요청으로 남아 있어야 한다
+        let next = HttpRequest::from_reader(&mut reader).unwrap();
+        assert_eq!("/", next.resource.path());
+        assert!(matches!(
+            HttpRequest::from_reader(&mut reader),
+            Err(ParseError::ConnectionClosed)
+        ));
+    }
+
+    #[test]
+    fn test_read_malformed_request() {
+        let cases: [&[u8]; 6] = [
+            b"GET /\r\n\r\n",
+            b"GET  / HTTP/1.1\r\n\r\n",
+            b"GET / HTTP/1.1 extra\r\n\r\n",
+            b"GET / HTTP/1.1\r\nNoColon\r\n\r\n",
+            b"GET / HTTP/1.1\r\nContent-Length: -1\r\n\r\n",
+            b"GET / HTTP/1.1\rHost: x\r\n\r\n",
+        ];
+        for raw in cases {
+            assert!(HttpRequest::from_reader(&mut &raw[..]).is_err());
+        }
+    }
+
+    #[test]
+    fn test_read_truncated_request() {
+        let raw: &[u8] = b"POST / HTTP/1.1\r\nContent-Length: 10\r\n\r\nabc";
+        assert!(matches!(
+            HttpRequest::from_reader(&mut &raw[..]),
+            Err(ParseError::UnexpectedEof)
+        ));
+        let raw: &[u8] = b"GET / HTTP/1.1\r\nHost: x";
+        assert!(matches!(
+            HttpRequest::from_reader(&mut &raw[..]),
+            Err(ParseError::UnexpectedEof)
+        ));
+    }
+
+    #[test]
+    fn test_read_chunked_request() {
+        let raw: &[u8] = b"POST /api/shipping/orders HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\n3\r\nabc\r\n2\r\nde\r\n0\r\nX-Checksum: 1\r\n\r\nGET / HTTP/1.1\r\n\r\n";
+        let mut reader = raw;
+        let req = HttpRequest::from_reader(&mut reader).unwrap();
+        assert_eq!(b"abcde".to_vec(), req.msg_body);
+        assert_eq!(Some("1"), req.trailers.get("x-checksum"));
+        assert!(HttpRequest::from_reader(&mut reader).is_ok());
+    }
+
+    #[test]
+    fn test_reject_ambiguous_body_length() {
+        let raw: &[u8] =
+            b"POST / HTTP/1.1\r\nContent-Length: 3\r\nTransfer-Encoding: chunked\r\n\r\n0\r\n\r\n";
+        assert!(matches!(
+            HttpRequest::from_reader(&mut &raw[..]),
+            Err(ParseError::AmbiguousBodyLength)
+        ));
+        let raw: &[u8] = b"POST / HTTP/1.1\r\nTransfer-Encoding: gzip\r\n\r\n";
+        assert!(matches!(
+            HttpRequest::from_reader(&mut &raw[..]),
+            Err(ParseError::UnsupportedTransferEncoding)
+        ));
+    }
+
+    #[test]
+    fn test_read_repeated_headers() {
+        let raw = "GET / HTTP/1.1\r\nSet-Cookie: a=1\r\nX-Empty:\r\nset-cookie:b=2 \t\r\n\r\n";
+        let req: HttpRequest = raw.to_string().try_into().unwrap();
+        assert_eq!(vec!["a=1", "b=2"], req.headers.get_all("Set-Cookie"));
+        assert_eq!(Some(""), req.headers.get("x-empty"));
+
+        let raw = "GET / HTTP/1.1\r\nHost : localhost\r\n\r\n";
+        let result: Result<HttpRequest, _> = raw.to_string().try_into();
+        assert!(matches!(result, Err(ParseError::InvalidHeader)));
+    }
+
+    #[test]
+    fn test_duplicate_content_length() {
+        let raw: &[u8] = b"POST / HTTP/1.1\r\nContent-Length: 3\r\nContent-Length: 3\r\n\r\nabc";
+        let req = HttpRequest::from_reader(&mut &raw[..]).unwrap();
+        assert_eq!(b"abc".to_vec(), req.msg_body);
+
+        let raw: &[u8] = b"POST / HTTP/1.1\r\nContent-Length: 3\r\nContent-Length: 4\r\n\r\nabcd";
+        assert!(matches!(
+            HttpRequest::from_reader(&mut &raw[..]),
+            Err(ParseError::InvalidContentLength)
+        ));
+    }
+
+    #[test]
+    fn test_build_and_send_request() {
+        let req = HttpRequest::builder()
+            .method(Method::Post)
+            .target("/api/shipping/orders")
+            .header("Host", "localhost")
+            .header("Content-Length", "99")
+            .body("{}")
+            .build()
+            .unwrap();
+        let mut out = Vec::new();
+        req.send_request(&mut out).unwrap();
+        assert_eq!(
+            "POST /api/shipping/orders HTTP/1.1\r\nHost: localhost\r\nContent-Length: 2\r\n\r\n{}",
+            String::from_utf8(out.clone()).unwrap()
+        );
+        let parsed = HttpRequest::from_reader(&mut &out[..]).unwrap();
+        assert_eq!(Method::Post, parsed.method);
+        assert_eq!(b"{}".to_vec(), parsed.msg_body);
+
+        // 본문 없는 GET에는 Content-Length를 쓰지 않는다
+        let mut out = Vec::new();
+        let req = HttpRequest::builder().target("/a?b").build().unwrap();
+        req.send_request(&mut out).unwrap();
+        assert_eq!(b"GET /a?b HTTP/1.1\r\n\r\n".to_vec(), out);
+        assert!(matches!(
+            HttpRequest::builder().target("no slash").build(),
+            Err(ParseError::InvalidTarget(_))
+        ));
+    }
+
+    #[test]
+    fn test_size_limits() {
+        let limits = Limits {
+            max_request_line: 20,
+            max_header_bytes: 30,
+            max_header_count: 2,
+            max_body_bytes: 4,
+        };
+        let raw: &[u8] = b"POST / HTTP/1.1\r\nHost: a\r\nContent-Length: 4\r\n\r\nabcd";
+        let req = HttpRequest::from_reader_with_limits(&mut &raw[..], &limits).unwrap();
+        assert_eq!(b"abcd".to_vec(), req.msg_body);
+
+        let raw: &[u8] = b"POST / HTTP/1.1\r\nHost: a\r\nX-Long: 0123456789abcdef\r\n\r\n";
+        assert!(matches!(
+            HttpRequest::from_reader_with_limits(&mut &raw[..], &limits),
+            Err(ParseError::HeaderTooLarge)
+        ));
+        let raw: &[u8] = b"POST / HTTP/1.1\r\nA: 1\r\nB: 2\r\nC: 3\r\n\r\n";
+        assert!(matches!(
+            HttpRequest::from_reader_with_limits(&mut &raw[..], &limits),
+            Err(ParseError::HeaderTooLarge)
+        ));
+        let raw: &[u8] = b"GET /a-very-long-path HTTP/1.1\r\n\r\n";
+        assert!(matches!(
+            HttpRequest::from_reader_with_limits(&mut &raw[..], &limits),
+            Err(ParseError::RequestLineTooLong)
+        ));
+        let raw: &[u8] = b"POST / HTTP/1.1\r\nContent-Length: 5\r\n\r\nabcde";
+        assert!(matches!(
+            HttpRequest::from_reader_with_limits(&mut &raw[..], &limits),
+            Err(ParseError::BodyTooLarge)
+        ));
+        let raw: &[u8] =
+            b"POST / HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\n3\r\nabc\r\n2\r\nde\r\n0\r\n\r\n";
+        assert!(matches!(
+            HttpRequest::from_reader_with_limits(&mut &raw[..], &limits),
+            Err(ParseError::BodyTooLarge)
+        ));
+    }
 }
@@ -1,110 +1,325 @@
-use std::collections::HashMap;
-use std::io::{Result, Write};
-#[derive(Debug, PartialEq, Clone)]
+use super::body::Body;
+use super::chunked::{self, ChunkedWriter};
+use super::headers::HeaderMap;
+use super::httprequest::{
+    parse_content_length, read_header_block, read_line, Limits, Method, ParseError, Version,
+};
+use super::status::StatusCode;
+use std::io::{self, BufRead, Read, Result, Write};
+use std::str;
+
 // cargo test -p _http --lib
-pub struct HttpResponse<'a> {
-    version: &'a str,
-    status_code: &'a str,
-    status_text: &'a str,
-    headers: Option<HashMap<&'a str, &'a str>>,
-    body: Option<String>,
+#[derive(Debug, PartialEq)]
+pub struct HttpResponse {
+    version: Version,
+    status: StatusCode,
+    headers: HeaderMap,
+    body: Body,
 }
 
-impl<'a> Default for HttpResponse<'a> {
+impl Default for HttpResponse {
     fn default() -> Self {
         Self {
-            version: "HTTP/1.1".into(),
-            status_code: "200".into(),
-            status_text: "OK".into(),
-            headers: None,
-            body: None,
+            version: Version::V1_1,
+            status: StatusCode::Ok,
+            headers: HeaderMap::new(),
+            body: Body::Empty,
         }
     }
 }
 
-impl<'a> HttpResponse<'a> {
-    pub fn new(
-        status_code: &'a str,
-        headers: Option<HashMap<&'a str, &'a str>>,
-        body: Option<String>,
-    ) -> HttpResponse<'a> {
-        let mut response: HttpResponse<'a> = HttpResponse::default();
-        if status_code != "200" {
-            response.status_code = status_code.into();
-        };
-        response.headers = match &headers {
-            Some(_h) => headers,
-            None => {
-                let mut h = HashMap::new();
-                h.insert("Content-Type", "text/html");
-                Some(h)
+// 본문의 길이를 알리는 방식
+#[derive(Clone, Copy)]
+enum Framing {
+    // Content-Length 헤더로 길이를 알린다
+    Length(u64),
+    // Transfer-Encoding: chunked로 보낸다
+    Chunked,
+    // 본문이 없는 상태 코드라 길이 헤더를 쓰지 않는다
+    NoBody,
+}
+
+impl HttpResponse {
+    // 헤더를 주지 않으면 Content-Type: text/html을 기본으로 넣는다
+    pub fn new(status: StatusCode, headers: Option<HeaderMap>, body: Option<String>) -> Self {
+        let headers = headers.unwrap_or_else(|| {
+            let mut h = HeaderMap::new();
+            h.insert("Content-Type", "text/html");
+            h
+        });
+        HttpResponse {
+            status,
+            headers,
+            body: body.map(Body::from).unwrap_or_default(),
+            ..HttpResponse::default()
+        }
+    }
+
+    pub fn builder() -> HttpResponseBuilder {
+        HttpResponseBuilder::new()
+    }
+
+    pub fn version(&self) -> Version {
+        self.version
+    }
+
+    pub fn status(&self) -> StatusCode {
+        self.status
+    }
+
+    pub fn headers(&self) -> &HeaderMap {
+        &self.headers
+    }
+
+    pub fn headers_mut(&mut self) -> &mut HeaderMap {
+        &mut self.headers
+    }
+
+    pub fn body(&self) -> &Body {
+        &self.body
+    }
+
+    pub fn set_body(&mut self, body: impl Into<Body>) {
+        self.body = body.into();
+    }
+
+    // 본문을 꺼내고 그 자리를 빈 본문으로 바꾼다
+    pub fn take_body(&mut self) -> Body {
+        std::mem::take(&mut self.body)
+    }
+
+    // 헤더를 먼저 쓰고 본문을 조금씩 옮겨 쓴다. 보낸 본문 바이트 수를 돌려준다.
+    // 길이를 모르는 본문은 chunked 전송 코딩으로 보낸다.
+    pub fn send_response(self, write_stream: &mut impl Write) -> Result<u64> {
+        let framing = self.framing();
+        self.write_head(write_stream, framing)?;
+        let written = match framing {
+            Framing::Length(_) => self.body.write_to(write_stream)?,
+            Framing::Chunked => {
+                let mut writer = ChunkedWriter::new(&mut *write_stream);
+                let written = self.body.write_to(&mut writer)?;
+                writer.finish(&HeaderMap::new())?;
+                written
             }
+            Framing::NoBody => 0,
         };
-        response.status_text = match response.status_code {
-            "200" => "OK".into(),
-            "400" => "Bad Request".into(),
-            "404" => "Not Found".into(),
-            "500" => "Internal Server Error".into(),
-            _ => "Not Found".into(),
-        };
-        response.body = body;
-        response
+        write_stream.flush()?;
+        Ok(written)
     }
-    
-    pub fn send_response(&self, write_stream: &mut impl Write) -> Result<()> {
-        let res = self.clone();
-        let response_string: String = String::from(res);
-        let _ = write!(write_stream, "{}", response_string);
-        Ok(())
+
+    // HEAD 요청에 대한 응답: 본문을 보낼 때와 같은 헤더를 쓰되 본문은 보내지 않는다
+    pub fn send_head(&self, write_stream: &mut impl Write) -> Result<()> {
+        self.write_head(write_stream, self.framing())?;
+        write_stream.flush()
     }
-}
 
-impl<'a> HttpResponse<'a> {
-    fn version(&self) -> &str {
-        self.version
+    // 본문을 Content-Length 대신 chunked 전송 코딩으로 보낸다.
+    // 반복자가 돌려주는 조각 하나하나가 청크 하나가 되며, self의 본문은 무시된다.
+    pub fn send_chunked_response<I>(&self, write_stream: &mut impl Write, chunks: I) -> Result<()>
+    where
+        I: IntoIterator,
+        I::Item: AsRef<[u8]>,
+    {
+        self.write_head(write_stream, Framing::Chunked)?;
+        let mut writer = ChunkedWriter::new(write_stream);
+        for chunk in chunks {
+            writer.write_all(chunk.as_ref())?;
+            writer.flush()?;
+        }
+        writer.finish(&HeaderMap::new())?;
+        Ok(())
     }
 
-    fn status_code(&self) -> &str {
-        self.status_code
+    // 리더의 내용을 끝까지 읽어 chunked 전송 코딩으로 보낸다.
+    pub fn send_chunked_reader(
+        &self,
+        write_stream: &mut impl Write,
+        reader: &mut impl Read,
+    ) -> Result<()> {
+        self.write_head(write_stream, Framing::Chunked)?;
+        let mut writer = ChunkedWriter::new(write_stream);
+        io::copy(reader, &mut writer)?;
+        writer.finish(&HeaderMap::new())?;
+        Ok(())
     }
 
-    fn status_text(&self) -> &str {
-        self.status_text
+    fn framing(&self) -> Framing {
+        if !self.status.allows_body() {
+            return Framing::NoBody;
+        }
+        match self.body.len() {
+            Some(len) => Framing::Length(len),
+            None => Framing::Chunked,
+        }
     }
 
-    fn headers(&self) -> String {
-        let map: HashMap<&str, &str> = self.headers.clone().unwrap();
-        let mut header_string: String = "".into();
-        for (k, v) in map.iter() {
-            header_string = format!("{}{}:{}\r\n", header_string, k, v);
+    // {상태} - version, status code, status text \r\n
+    // {헤더} - headers (넣은 순서대로), Content-Length 또는 Transfer-Encoding \r\n
+    // {empty} - \r\n
+    fn write_head(&self, write_stream: &mut impl Write, framing: Framing) -> Result<()> {
+        let mut head = format!("{} {}\r\n", self.version, self.status);
+        for (k, v) in self.headers.iter() {
+            // 본문 길이 헤더는 실제 본문에 맞춰 아래에서 직접 쓴다
+            if k.eq_ignore_ascii_case("Content-Length")
+                || k.eq_ignore_ascii_case("Transfer-Encoding")
+            {
+                continue;
+            }
+            head.push_str(&format!("{}: {}\r\n", k, v));
+        }
+        match framing {
+            Framing::Length(len) => head.push_str(&format!("Content-Length: {}\r\n", len)),
+            Framing::Chunked => head.push_str("Transfer-Encoding: chunked\r\n"),
+            Framing::NoBody => {}
         }
-        header_string
+        head.push_str("\r\n");
+        write_stream.write_all(head.as_bytes())
     }
+}
+
+impl HttpResponse {
+    // 리더에서 응답 하나를 기본 크기 제한으로 읽어 파싱한다
+    pub fn from_reader<R: BufRead>(
+        reader: &mut R,
+        request_method: &Method,
+    ) -> std::result::Result<HttpResponse, ParseError> {
+        HttpResponse::from_reader_with_limits(reader, request_method, &Limits::default())
+    }
+
+    // 리더에서 응답 하나를 읽어 파싱한다. 본문 길이는 RFC 9112 6.3의 순서로 정한다:
+    // HEAD 요청의 응답과 1xx, 204, 304 응답은 본문이 없고, 그 밖에는 chunked, Content-Length,
+    // 둘 다 없으면 커넥션이 닫힐 때까지가 본문이다. 상태 행의 길이는 max_request_line으로 제한한다.
+    pub fn from_reader_with_limits<R: BufRead>(
+        reader: &mut R,
+        request_method: &Method,
+        limits: &Limits,
+    ) -> std::result::Result<HttpResponse, ParseError> {
+        let mut line_budget = limits.max_request_line;
+        let status_line = match read_line(reader, &mut line_budget) {
+            Ok(Some(line)) => line,
+            Ok(None) => return Err(ParseError::ConnectionClosed),
+            Err(ParseError::HeaderTooLarge) => return Err(ParseError::InvalidStatusLine),
+            Err(e) => return Err(e),
+        };
+        let (version, status) = str::from_utf8(&status_line)
+            .ok()
+            .and_then(process_status_line)
+            .ok_or(ParseError::InvalidStatusLine)?;
+        let headers = read_header_block(reader, limits)?;
 
-    pub fn body(&self) -> &str {
-        match &self.body {
-            Some(b) => b.as_str(),
-            None => "",
+        let mut body = Vec::new();
+        if *request_method != Method::Head && status.allows_body() {
+            let transfer_encoding = headers.get_all("Transfer-Encoding");
+            let content_length = headers.get_all("Content-Length");
+            if let Some(coding) = transfer_encoding.last() {
+                if transfer_encoding.len() != 1 || !coding.eq_ignore_ascii_case("chunked") {
+                    return Err(ParseError::UnsupportedTransferEncoding);
+                }
+                chunked::read_chunked_body(reader, &mut body, limits)?;
+            } else if !content_length.is_empty() {
+                let length = parse_content_length(&content_length)?;
+                if length > limits.max_body_bytes {
+                    return Err(ParseError::BodyTooLarge);
+                }
+                body = vec![0; length];
+                reader.read_exact(&mut body)?;
+            } else {
+                let limit = limits.max_body_bytes as u64 + 1;
+                Read::take(&mut *reader, limit).read_to_end(&mut body)?;
+                if body.len() > limits.max_body_bytes {
+                    return Err(ParseError::BodyTooLarge);
+                }
+            }
         }
+
+        Ok(HttpResponse {
+            version,
+            status,
+            headers,
+            body: if body.is_empty() {
+                Body::Empty
+            } else {
+                Body::from(body)
+            },
+        })
+    }
+}
+
+// "HTTP/1.1 404 Not Found" 형식의 상태 행. 사유 문구는 비어 있을 수 있고 무시한다.
+fn process_status_line(line: &str) -> Option<(Version, StatusCode)> {
+    let mut parts = line.splitn(3, ' ');
+    let version = Version::from(parts.next()?);
+    let code = parts.next()?;
+    if version == Version::Uninitialized || code.len() != 3 {
+        return None;
+    }
+    let status = StatusCode::from_code(code.parse().ok()?)?;
+    Some((version, status))
+}
+
+impl From<HttpResponse> for Vec<u8> {
+    fn from(res: HttpResponse) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        // Vec<u8>에 쓰는 것은 실패하지 않는다. 파일이나 리더 본문을 읽다 실패하면 panic한다.
+        res.send_response(&mut bytes).unwrap();
+        bytes
     }
 }
 
-impl<'a> From<HttpResponse<'a>> for String {
+impl From<HttpResponse> for String {
     fn from(res: HttpResponse) -> String {
-        let res1 = res.clone();
-        // {상태} - version, status code, status text \r\n
-        // {헤더} - headers, Content-Lenght, body.lenght \r\n
-        // {empty} - \r\n
-        // {선택적인 메시지 바디} - body
-        format!(
-            "{} {} {}\r\n{}Content-Length: {}\r\n\r\n{}",
-            &res1.version(),
-            &res1.status_code(),
-            &res1.status_text(),
-            &res1.headers(),
-            &res.body.unwrap().len(),
-            &res1.body()
-        )
+        let bytes: Vec<u8> = res.into();
+        String::from_utf8_lossy(&bytes).into_owned()
+    }
+}
+
+// HttpResponse를 단계적으로 만든다.
+//
+// let response = HttpResponse::builder()
+//     .status(StatusCode::Created)
+//     .header("Content-Type", "application/json")
+//     .body(r#"{"order_id":3}"#)
+//     .build();
+#[derive(Debug, Default)]
+pub struct HttpResponseBuilder {
+    response: HttpResponse,
+}
+
+impl HttpResponseBuilder {
+    pub fn new() -> Self {
+        HttpResponseBuilder {
+            response: HttpResponse::default(),
+        }
+    }
+
+    pub fn version(mut self, version: Version) -> Self {
+        self.response.version = version;
+        self
+    }
+
+    pub fn status(mut self, status: StatusCode) -> Self {
+        self.response.status = status;
+        self
+    }
+
+    // 같은 이름의 헤더가 있어도 덧붙인다 (예: Set-Cookie 여러 개)
+    pub fn header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.response.headers.append(name, value);
+        self
+    }
+
+    pub fn headers(mut self, headers: HeaderMap) -> Self {
+        self.response.headers = headers;
+        self
+    }
+
+    pub fn body(mut self, body: impl Into<Body>) -> Self {
+        self.response.body = body.into();
+        self
+    }
+
+    pub fn build(self) -> HttpResponse {
+        self.response
     }
 }
 
@@ -115,20 +330,19 @@ mod tests {
     #[test]
     fn test_response_struct_creation_200() {
         let response_actual = HttpResponse::new(
-            "200",
+            StatusCode::Ok,
             None,
             Some("Item was shipped on 21st Dec 2020".into()),
         );
         let response_expected = HttpResponse {
-            version: "HTTP/1.1",
-            status_code: "200",
-            status_text: "OK",
+            version: Version::V1_1,
+            status: StatusCode::Ok,
             headers: {
-                let mut h = HashMap::new();
+                let mut h = HeaderMap::new();
                 h.insert("Content-Type", "text/html");
-                Some(h)
+                h
             },
-            body: Some("Item was shipped on 21st Dec 2020".into()),
+            body: "Item was shipped on 21st Dec 2020".into(),
         };
 
         assert_eq!(response_actual, response_expected);
@@ -137,21 +351,20 @@ mod tests {
     #[test]
     fn test_response_struct_creation_404() {
         let response_actual = HttpResponse::new(
-            "404",
+            StatusCode::NotFound,
             None,
             Some("Item was shipped on 21st Dec 2020".into()),
         );
 
         let response_expected = HttpResponse {
-            version: "HTTP/1.1",
-            status_code: "404",
-            status_text: "Not Found",
+            version: Version::V1_1,
+            status: StatusCode::NotFound,
             headers: {
-                let mut h = HashMap::new();
+                let mut h = HeaderMap::new();
                 h.insert("Content-Type", "text/html");
-                Some(h)
+                h
             },
-            body: Some("Item was shipped on 21st Dec 2020".into()),
+            body: "Item was shipped on 21st Dec 2020".into(),
         };
 
         assert_eq!(response_actual, response_expected);
@@ -160,19 +373,191 @@ mod tests {
     #[test]
     fn test_http_response_creation() {
         let response_expected = HttpResponse {
-            version: "HTTP/1.1",
-            status_code: "404",
-            status_text: "Not Found",
+            version: Version::V1_1,
+            status: StatusCode::NotFound,
             headers: {
-                let mut h = HashMap::new();
+                let mut h = HeaderMap::new();
                 h.insert("Content-Type", "text/html");
-                Some(h)
+                h
             },
-            body: Some("Item was shipped on 21st Dec 2020".into()),
+            body: "Item was shipped on 21st Dec 2020".into(),
         };
         let http_string: String = response_expected.into();
-        let response_actual = "HTTP/1.1 404 Not Found\r\nContent-Type:text/html\r\nConte-Type:text/html
-        \r\nContent-Length: 33\r\n\r\nItem was shipped on 21st Dec 2020";
+        let response_actual = "HTTP/1.1 404 Not Found\r\nContent-Type: text/html\r\nContent-Length: 33\r\n\r\nItem was shipped on 21st Dec 2020";
         assert_eq!(http_string, response_actual);
     }
+
+    #[test]
+    fn test_builder() {
+        let response = HttpResponse::builder()
+            .status(StatusCode::Created)
+            .header("Content-Type", "application/json")
+            .header("Set-Cookie", "a=1")
+            .header("Set-Cookie", "b=2")
+            .body(vec![b'{', b'}'])
+            .build();
+        assert_eq!(StatusCode::Created, response.status());
+        assert_eq!(Some(&b"{}"[..]), response.body().as_bytes());
+        let bytes: Vec<u8> = response.into();
+        assert_eq!(
+            b"HTTP/1.1 201 Created\r\nContent-Type: application/json\r\nSet-Cookie: a=1\r\nSet-Cookie: b=2\r\nContent-Length: 2\r\n\r\n{}".to_vec(),
+            bytes
+        );
+    }
+
+    #[test]
+    fn test_serialise_binary_body_and_bodyless_status() {
+        let response = HttpResponse::builder()
+            .version(Version::V1_0)
+            .header("Content-Length", "999")
+            .body(vec![0x00, 0xff])
+            .build();
+        let bytes: Vec<u8> = response.into();
+        assert_eq!(
+            b"HTTP/1.0 200 OK\r\nContent-Length: 2\r\n\r\n\x00\xff".to_vec(),
+            bytes
+        );
+
+        let response = HttpResponse::builder()
+            .status(StatusCode::NotModified)
+            .header("ETag", "\"abc\"")
+            .body("ignored")
+            .build();
+        let http_string: String = response.into();
+        assert_eq!(
+            "HTTP/1.1 304 Not Modified\r\nETag: \"abc\"\r\n\r\n",
+            http_string
+        );
+    }
+
+    #[test]
+    fn test_send_chunked_response() {
+        let response = HttpResponse::new(StatusCode::Ok, None, None);
+        let mut out = Vec::new();
+        response
+            .send_chunked_response(&mut out, ["Item was ", "", "shipped"])
+            .unwrap();
+        assert_eq!(
+            "HTTP/1.1 200 OK\r\nContent-Type: text/html\r\nTransfer-Encoding: chunked\r\n\r\n9\r\nItem was \r\n7\r\nshipped\r\n0\r\n\r\n",
+            String::from_utf8(out).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_send_chunked_reader() {
+        let response = HttpResponse::new(StatusCode::Ok, None, None);
+        let mut out = Vec::new();
+        let mut reader: &[u8] = b"Item was shipped";
+        response.send_chunked_reader(&mut out, &mut reader).unwrap();
+        assert!(String::from_utf8(out)
+            .unwrap()
+            .ends_with("\r\n\r\n10\r\nItem was shipped\r\n0\r\n\r\n"));
+    }
+
+    #[test]
+    fn test_send_head() {
+        let response = HttpResponse::new(StatusCode::Ok, None, Some("Item was shipped".into()));
+        let mut out = Vec::new();
+        response.send_head(&mut out).unwrap();
+        assert_eq!(
+            "HTTP/1.1 200 OK\r\nContent-Type: text/html\r\nContent-Length: 16\r\n\r\n",
+            String::from_utf8(out).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_repeated_response_headers() {
+        let mut headers = HeaderMap::new();
+        headers.append("Content-Type", "text/html");
+        headers.append("Set-Cookie", "a=1");
+        headers.append("Set-Cookie", "b=2");
+        let response = HttpResponse::new(StatusCode::Ok, Some(headers), Some("ok".into()));
+        let http_string: String = response.into();
+        assert_eq!(
+            "HTTP/1.1 200 OK\r\nContent-Type: text/html\r\nSet-Cookie: a=1\r\nSet-Cookie: b=2\r\nContent-Length: 2\r\n\r\nok",
+            http_string
+        );
+    }
+
+    #[test]
+    fn test_send_reader_body_without_length_as_chunks() {
+        let response = HttpResponse::builder()
+            .body(Body::from_reader(&b"Item was shipped"[..], None))
+            .build();
+        let mut out = Vec::new();
+        assert_eq!(16, response.send_response(&mut out).unwrap());
+        assert_eq!(
+            "HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n10\r\nItem was shipped\r\n0\r\n\r\n",
+            String::from_utf8(out).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_read_response() {
+        let mut reader: &[u8] = b"HTTP/1.1 200 OK\r\nContent-Length: 4\r\n\r\nItemHTTP/1.1 201 Created\r\nTransfer-Encoding: chunked\r\n\r\n4\r\nItem\r\n0\r\n\r\nHTTP/1.0 404 \r\n\r\nnot found";
+        let response = HttpResponse::from_reader(&mut reader, &Method::Get).unwrap();
+        assert_eq!(StatusCode::Ok, response.status());
+        assert_eq!(Some(&b"Item"[..]), response.body().as_bytes());
+        let response = HttpResponse::from_reader(&mut reader, &Method::Post).unwrap();
+        assert_eq!(StatusCode::Created, response.status());
+        assert_eq!(Some(&b"Item"[..]), response.body().as_bytes());
+        // 길이를 알리지 않은 본문은 스트림 끝까지 읽는다
+        let response = HttpResponse::from_reader(&mut reader, &Method::Get).unwrap();
+        assert_eq!(Version::V1_0, response.version());
+        assert_eq!(Some(&b"not found"[..]), response.body().as_bytes());
+        assert!(matches!(
+            HttpResponse::from_reader(&mut reader, &Method::Get),
+            Err(ParseError::ConnectionClosed)
+        ));
+
+        // HEAD 요청과 304 응답에는 Content-Length가 있어도 본문이 없다
+        let mut reader: &[u8] = b"HTTP/1.1 200 OK\r\nContent-Length: 4\r\n\r\nHTTP/1.1 304 Not Modified\r\nContent-Length: 4\r\n\r\n";
+        let response = HttpResponse::from_reader(&mut reader, &Method::Head).unwrap();
+        assert_eq!(Some(4), response.headers().content_length());
+        assert!(response.body().is_empty());
+        let response = HttpResponse::from_reader(&mut reader, &Method::Get).unwrap();
+        assert_eq!(StatusCode::NotModified, response.status());
+        assert!(reader.is_empty());
+
+        for raw in [
+            "HTTP/1.1 OK\r\n\r\n",
+            "HTTP/9 200 OK\r\n\r\n",
+            "200 OK\r\n\r\n",
+        ] {
+            assert!(matches!(
+                HttpResponse::from_reader(&mut raw.as_bytes(), &Method::Get),
+                Err(ParseError::InvalidStatusLine)
+            ));
+        }
+    }
+
+    #[test]
+    fn test_response_round_trip() {
+        let response = HttpResponse::builder()
+            .status(StatusCode::Created)
+            .header("Location", "/api/shipping/orders/3")
+            .body(Body::from_reader(&b"{\"order_id\":3}"[..], None))
+            .build();
+        let bytes: Vec<u8> = response.into();
+        let parsed = HttpResponse::from_reader(&mut &bytes[..], &Method::Post).unwrap();
+        assert_eq!(StatusCode::Created, parsed.status());
+        assert_eq!(
+            Some("/api/shipping/orders/3"),
+            parsed.headers().get("Location")
+        );
+        assert_eq!(Some(&b"{\"order_id\":3}"[..]), parsed.body().as_bytes());
+    }
+
+    #[test]
+    fn test_send_reader_body_with_length() {
+        let response = HttpResponse::builder()
+            .body(Body::from_reader(&b"Item was shipped"[..], Some(4)))
+            .build();
+        let mut out = Vec::new();
+        assert_eq!(4, response.send_response(&mut out).unwrap());
+        assert_eq!(
+            "HTTP/1.1 200 OK\r\nContent-Length: 4\r\n\r\nItem",
+            String::from_utf8(out).unwrap()
+        );
+    }
 }
@@ -1,2 +1,10 @@
+pub mod body;
+pub mod chunked;
+pub mod client;
+pub mod date;
+pub mod headers;
 pub mod httprequest;
 pub mod httpresponse;
+pub mod range;
+pub mod status;
+pub mod uri;
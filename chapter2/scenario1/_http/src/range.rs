@@ -0,0 +1,114 @@
+use std::ops::Range;
+
+// Range 헤더의 bytes 범위 하나 (RFC 9110 14.1.1)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ByteRangeSpec {
+    // first-last: 두 위치 모두 포함한다
+    FromTo(u64, u64),
+    // first-: 끝까지
+    From(u64),
+    // -suffix: 마지막 suffix 바이트
+    Suffix(u64),
+}
+
+impl ByteRangeSpec {
+    // 길이가 len인 표현에서 실제로 보낼 구간. 만족할 수 없는 범위면 None이다.
+    pub fn resolve(&self, len: u64) -> Option<Range<u64>> {
+        match *self {
+            ByteRangeSpec::FromTo(first, last) if first < len => {
+                Some(first..last.saturating_add(1).min(len))
+            }
+            ByteRangeSpec::From(first) if first < len => Some(first..len),
+            ByteRangeSpec::Suffix(suffix) if suffix > 0 && len > 0 => {
+                Some(len.saturating_sub(suffix)..len)
+            }
+            _ => None,
+        }
+    }
+}
+
+// "bytes=0-499, -500" 형식의 Range 헤더 값을 읽는다.
+// bytes 단위가 아니거나 문법이 틀리면 None이며, 이때는 Range 헤더를 무시해야 한다.
+pub fn parse_range_header(value: &str) -> Option<Vec<ByteRangeSpec>> {
+    let (unit, ranges) = value.split_once('=')?;
+    if !unit.trim().eq_ignore_ascii_case("bytes") {
+        return None;
+    }
+
+    let mut specs = Vec::new();
+    // 목록의 빈 원소는 건너뛴다 (RFC 9110 5.6.1)
+    for range in ranges.split(',').map(str::trim).filter(|r| !r.is_empty()) {
+        let (first, last) = range.split_once('-')?;
+        let spec = match (first, last) {
+            ("", suffix) => ByteRangeSpec::Suffix(parse_position(suffix)?),
+            (first, "") => ByteRangeSpec::From(parse_position(first)?),
+            (first, last) => {
+                let (first, last) = (parse_position(first)?, parse_position(last)?);
+                if last < first {
+                    return None;
+                }
+                ByteRangeSpec::FromTo(first, last)
+            }
+        };
+        specs.push(spec);
+    }
+    if specs.is_empty() {
+        return None;
+    }
+    Some(specs)
+}
+
+fn parse_position(value: &str) -> Option<u64> {
+    if value.is_empty() || !value.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    value.parse().ok()
+}
+
+// 206 응답의 Content-Range 값: "bytes 0-499/1234"
+pub fn content_range(range: &Range<u64>, len: u64) -> String {
+    format!("bytes {}-{}/{}", range.start, range.end - 1, len)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_range_header() {
+        assert_eq!(
+            Some(vec![
+                ByteRangeSpec::FromTo(0, 499),
+                ByteRangeSpec::From(9500),
+                ByteRangeSpec::Suffix(500),
+            ]),
+            parse_range_header("bytes=0-499, 9500-,,-500")
+        );
+        for invalid in [
+            "bytes=",
+            "items=0-1",
+            "bytes=5-1",
+            "bytes=a-b",
+            "bytes=-",
+            "bytes=+1-2",
+        ] {
+            assert_eq!(None, parse_range_header(invalid), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn test_resolve_against_length() {
+        assert_eq!(Some(0..500), ByteRangeSpec::FromTo(0, 499).resolve(10000));
+        // 끝 위치가 길이를 넘으면 잘라 낸다
+        assert_eq!(
+            Some(9000..10000),
+            ByteRangeSpec::FromTo(9000, 20000).resolve(10000)
+        );
+        assert_eq!(Some(9500..10000), ByteRangeSpec::From(9500).resolve(10000));
+        assert_eq!(Some(0..100), ByteRangeSpec::Suffix(500).resolve(100));
+        assert_eq!(None, ByteRangeSpec::From(100).resolve(100));
+        assert_eq!(None, ByteRangeSpec::Suffix(0).resolve(100));
+        assert_eq!(None, ByteRangeSpec::Suffix(10).resolve(0));
+        assert_eq!("bytes 0-499/1234", content_range(&(0..500), 1234));
+    }
+}
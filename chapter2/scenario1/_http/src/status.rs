@@ -0,0 +1,209 @@
+use std::fmt;
+
+// IANA HTTP Status Code Registry에 등록된 상태 코드와 표준 사유 구문(RFC 9110 15) 표.
+// 표 한 곳에서 열거형, 숫자 변환, 사유 구문을 모두 만든다.
+// 표에 없는 코드는 Other로 숫자를 그대로 들고 있으며, 분류는 첫 자리로 정한다.
+macro_rules! status_codes {
+    ($(($code:expr, $variant:ident, $phrase:expr);)+) => {
+        #[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+        pub enum StatusCode {
+            $($variant,)+
+            // 등록되지 않은 코드 (예: 299, 520). from_code로만 만든다.
+            Other(u16),
+        }
+
+        impl StatusCode {
+            pub fn as_u16(&self) -> u16 {
+                match self {
+                    $(StatusCode::$variant => $code,)+
+                    StatusCode::Other(code) => *code,
+                }
+            }
+
+            pub fn reason_phrase(&self) -> &'static str {
+                match self {
+                    $(StatusCode::$variant => $phrase,)+
+                    StatusCode::Other(_) => "",
+                }
+            }
+
+            // 등록되지 않은 코드면 None을 돌려준다
+            pub fn from_u16(code: u16) -> Option<StatusCode> {
+                match code {
+                    $($code => Some(StatusCode::$variant),)+
+                    _ => None,
+                }
+            }
+        }
+    };
+}
+
+status_codes! {
+    (100, Continue, "Continue");
+    (101, SwitchingProtocols, "Switching Protocols");
+    (102, Processing, "Processing");
+    (103, EarlyHints, "Early Hints");
+
+    (200, Ok, "OK");
+    (201, Created, "Created");
+    (202, Accepted, "Accepted");
+    (203, NonAuthoritativeInformation, "Non-Authoritative Information");
+    (204, NoContent, "No Content");
+    (205, ResetContent, "Reset Content");
+    (206, PartialContent, "Partial Content");
+    (207, MultiStatus, "Multi-Status");
+    (208, AlreadyReported, "Already Reported");
+    (226, ImUsed, "IM Used");
+
+    (300, MultipleChoices, "Multiple Choices");
+    (301, MovedPermanently, "Moved Permanently");
+    (302, Found, "Found");
+    (303, SeeOther, "See Other");
+    (304, NotModified, "Not Modified");
+    (305, UseProxy, "Use Proxy");
+    (307, TemporaryRedirect, "Temporary Redirect");
+    (308, PermanentRedirect, "Permanent Redirect");
+
+    (400, BadRequest, "Bad Request");
+    (401, Unauthorized, "Unauthorized");
+    (402, PaymentRequired, "Payment Required");
+    (403, Forbidden, "Forbidden");
+    (404, NotFound, "Not Found");
+    (405, MethodNotAllowed, "Method Not Allowed");
+    (406, NotAcceptable, "Not Acceptable");
+    (407, ProxyAuthenticationRequired, "Proxy Authentication Required");
+    (408, RequestTimeout, "Request Timeout");
+    (409, Conflict, "Conflict");
+    (410, Gone, "Gone");
+    (411, LengthRequired, "Length Required");
+    (412, PreconditionFailed, "Precondition Failed");
+    (413, ContentTooLarge, "Content Too Large");
+    (414, UriTooLong, "URI Too Long");
+    (415, UnsupportedMediaType, "Unsupported Media Type");
+    (416, RangeNotSatisfiable, "Range Not Satisfiable");
+    (417, ExpectationFailed, "Expectation Failed");
+    (418, ImATeapot, "I'm a teapot");
+    (421, MisdirectedRequest, "Misdirected Request");
+    (422, UnprocessableContent, "Unprocessable Content");
+    (423, Locked, "Locked");
+    (424, FailedDependency, "Failed Dependency");
+    (425, TooEarly, "Too Early");
+    (426, UpgradeRequired, "Upgrade Required");
+    (428, PreconditionRequired, "Precondition Required");
+    (429, TooManyRequests, "Too Many Requests");
+    (431, RequestHeaderFieldsTooLarge, "Request Header Fields Too Large");
+    (451, UnavailableForLegalReasons, "Unavailable For Legal Reasons");
+
+    (500, InternalServerError, "Internal Server Error");
+    (501, NotImplemented, "Not Implemented");
+    (502, BadGateway, "Bad Gateway");
+    (503, ServiceUnavailable, "Service Unavailable");
+    (504, GatewayTimeout, "Gateway Timeout");
+    (505, HttpVersionNotSupported, "HTTP Version Not Supported");
+    (506, VariantAlsoNegotiates, "Variant Also Negotiates");
+    (507, InsufficientStorage, "Insufficient Storage");
+    (508, LoopDetected, "Loop Detected");
+    (510, NotExtended, "Not Extended");
+    (511, NetworkAuthenticationRequired, "Network Authentication Required");
+}
+
+impl StatusCode {
+    // 받은 응답의 상태 코드. 등록되지 않은 코드도 같은 분류의 x00처럼 다룰 수 있게 Other로 받아들인다
+    // (RFC 9110 15). 100~599 밖의 코드는 None이다.
+    pub fn from_code(code: u16) -> Option<StatusCode> {
+        if !(100..600).contains(&code) {
+            return None;
+        }
+        Some(StatusCode::from_u16(code).unwrap_or(StatusCode::Other(code)))
+    }
+
+    pub fn is_informational(&self) -> bool {
+        (100..200).contains(&self.as_u16())
+    }
+
+    pub fn is_success(&self) -> bool {
+        (200..300).contains(&self.as_u16())
+    }
+
+    pub fn is_redirection(&self) -> bool {
+        (300..400).contains(&self.as_u16())
+    }
+
+    pub fn is_client_error(&self) -> bool {
+        (400..500).contains(&self.as_u16())
+    }
+
+    pub fn is_server_error(&self) -> bool {
+        (500..600).contains(&self.as_u16())
+    }
+
+    // 1xx, 204, 304 응답은 본문을 가질 수 없다 (RFC 9110 6.4.1)
+    pub fn allows_body(&self) -> bool {
+        !(self.is_informational()
+            || *self == StatusCode::NoContent
+            || *self == StatusCode::NotModified)
+    }
+}
+
+impl TryFrom<u16> for StatusCode {
+    type Error = u16;
+
+    fn try_from(code: u16) -> Result<Self, Self::Error> {
+        StatusCode::from_u16(code).ok_or(code)
+    }
+}
+
+impl fmt::Display for StatusCode {
+    // 상태 행에 쓰는 형식: "404 Not Found"
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}", self.as_u16(), self.reason_phrase())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_status_code_round_trip() {
+        for code in 100..600 {
+            if let Some(status) = StatusCode::from_u16(code) {
+                assert_eq!(code, status.as_u16());
+                assert!(!status.reason_phrase().is_empty());
+            }
+        }
+        assert_eq!(Some(StatusCode::ImATeapot), StatusCode::from_u16(418));
+        assert_eq!(None, StatusCode::from_u16(299));
+        assert_eq!(Err(600), StatusCode::try_from(600));
+    }
+
+    #[test]
+    fn test_unregistered_status_codes() {
+        assert_eq!(Some(StatusCode::NotFound), StatusCode::from_code(404));
+        let status = StatusCode::from_code(299).unwrap();
+        assert_eq!(StatusCode::Other(299), status);
+        assert_eq!(299, status.as_u16());
+        assert!(status.is_success());
+        assert_eq!("299 ", status.to_string());
+        assert!(StatusCode::from_code(451).unwrap().is_client_error());
+        assert!(StatusCode::from_code(520).unwrap().is_server_error());
+        assert_eq!(None, StatusCode::from_code(99));
+        assert_eq!(None, StatusCode::from_code(600));
+    }
+
+    #[test]
+    fn test_status_code_display_and_classes() {
+        assert_eq!("404 Not Found", StatusCode::NotFound.to_string());
+        assert_eq!(
+            "505 HTTP Version Not Supported",
+            StatusCode::HttpVersionNotSupported.to_string()
+        );
+        assert!(StatusCode::SwitchingProtocols.is_informational());
+        assert!(StatusCode::PartialContent.is_success());
+        assert!(StatusCode::PermanentRedirect.is_redirection());
+        assert!(StatusCode::RequestHeaderFieldsTooLarge.is_client_error());
+        assert!(StatusCode::NetworkAuthenticationRequired.is_server_error());
+        assert!(!StatusCode::NotModified.allows_body());
+        assert!(StatusCode::NotFound.allows_body());
+    }
+}
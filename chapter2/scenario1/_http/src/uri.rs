@@ -0,0 +1,301 @@
+use std::fmt;
+use std::str;
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum UriError {
+    // 요청 대상이 비었거나 허용되지 않는 문자가 들어 있다
+    Malformed,
+    // '%' 뒤에 16진수 두 자리가 오지 않았거나 디코딩 결과가 UTF-8이 아니다
+    InvalidPercentEncoding,
+    // 경로에 ".." 세그먼트가 들어 있다
+    PathTraversal,
+}
+
+impl fmt::Display for UriError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            UriError::Malformed => write!(f, "malformed request target"),
+            UriError::InvalidPercentEncoding => write!(f, "invalid percent-encoding"),
+            UriError::PathTraversal => write!(f, "path traversal in request target"),
+        }
+    }
+}
+
+// 쿼리 문자열을 파싱한 결과. 같은 이름의 파라미터가 여러 번 올 수 있으므로 순서대로 모두 보관한다.
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct Query {
+    params: Vec<(String, String)>,
+}
+
+impl Query {
+    pub fn parse(s: &str) -> Query {
+        let params = s
+            .split('&')
+            .filter(|p| !p.is_empty())
+            .map(|p| {
+                let (k, v) = p.split_once('=').unwrap_or((p, ""));
+                (decode_query_component(k), decode_query_component(v))
+            })
+            .collect();
+        Query { params }
+    }
+
+    // 이름이 같은 파라미터 중 첫 번째 값을 돌려준다
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.params
+            .iter()
+            .find(|(k, _)| k == name)
+            .map(|(_, v)| v.as_str())
+    }
+
+    // 이름이 같은 파라미터의 값을 모두 돌려준다
+    pub fn get_all(&self, name: &str) -> Vec<&str> {
+        self.params
+            .iter()
+            .filter(|(k, _)| k == name)
+            .map(|(_, v)| v.as_str())
+            .collect()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.params.iter().map(|(k, v)| (k.as_str(), v.as_str()))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.params.is_empty()
+    }
+}
+
+// origin-form("/a/b?x=1") 또는 absolute-form("http://host/a/b?x=1") 요청 대상을 파싱한 결과
+#[derive(Debug, PartialEq, Clone)]
+pub struct Uri {
+    raw: String,
+    scheme: Option<String>,
+    authority: Option<String>,
+    path: String,
+    segments: Vec<String>,
+    query: Query,
+}
+
+impl Uri {
+    pub fn parse(target: &str) -> Result<Uri, UriError> {
+        if target.is_empty() || !target.bytes().all(|b| b.is_ascii_graphic()) {
+            return Err(UriError::Malformed);
+        }
+        // 프래그먼트는 서버로 보내지 않는 부분이므로 떼어 낸다
+        let without_fragment = target.split('#').next().unwrap_or("");
+
+        // absolute-form이면 스킴과 authority를 먼저 분리한다
+        let (scheme, authority, rest) = if without_fragment.starts_with('/') {
+            (None, None, without_fragment)
+        } else {
+            let (scheme, rest) = without_fragment
+                .split_once("://")
+                .ok_or(UriError::Malformed)?;
+            if !is_scheme(scheme) {
+                return Err(UriError::Malformed);
+            }
+            let end = rest.find(['/', '?']).unwrap_or(rest.len());
+            let (authority, rest) = rest.split_at(end);
+            if authority.is_empty() {
+                return Err(UriError::Malformed);
+            }
+            (
+                Some(scheme.to_ascii_lowercase()),
+                Some(authority.to_string()),
+                rest,
+            )
+        };
+
+        let (raw_path, raw_query) = rest.split_once('?').unwrap_or((rest, ""));
+        let raw_path = if raw_path.is_empty() { "/" } else { raw_path };
+
+        // 경로를 세그먼트로 나눈 뒤 각각 퍼센트 디코딩한다.
+        // 디코딩된 "%2F"는 구분자가 아니라 세그먼트의 일부로 남는다.
+        let mut segments = Vec::new();
+        if raw_path != "/" {
+            for raw_segment in raw_path[1..].split('/') {
+                let segment = percent_decode(raw_segment)?;
+                match segment.as_str() {
+                    "." => continue,
+                    ".." => return Err(UriError::PathTraversal),
+                    _ => segments.push(segment),
+                }
+            }
+        }
+        let path = format!("/{}", segments.join("/"));
+
+        Ok(Uri {
+            raw: target.to_string(),
+            scheme,
+            authority,
+            path,
+            segments,
+            query: Query::parse(raw_query),
+        })
+    }
+
+    // 클라이언트가 보낸 그대로의 요청 대상
+    pub fn as_str(&self) -> &str {
+        &self.raw
+    }
+
+    pub fn scheme(&self) -> Option<&str> {
+        self.scheme.as_deref()
+    }
+
+    pub fn authority(&self) -> Option<&str> {
+        self.authority.as_deref()
+    }
+
+    // 디코딩된 경로. 항상 '/'로 시작한다.
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    // 디코딩된 경로 세그먼트. "/"는 빈 목록, "/a/b/"는 ["a", "b", ""]가 된다.
+    pub fn segments(&self) -> &[String] {
+        &self.segments
+    }
+
+    pub fn query(&self) -> &Query {
+        &self.query
+    }
+
+    // 스킴과 authority, 프래그먼트를 뗀 경로와 쿼리. 인코딩은 받은 그대로 둔다.
+    pub fn origin_form(&self) -> String {
+        let target = self.raw.split('#').next().unwrap_or("");
+        let rest = match (&self.scheme, &self.authority) {
+            (Some(scheme), Some(authority)) => &target[scheme.len() + 3 + authority.len()..],
+            _ => target,
+        };
+        match rest.starts_with('/') {
+            true => rest.to_string(),
+            false => format!("/{}", rest),
+        }
+    }
+}
+
+impl fmt::Display for Uri {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.raw)
+    }
+}
+
+fn is_scheme(s: &str) -> bool {
+    let mut bytes = s.bytes();
+    matches!(bytes.next(), Some(b) if b.is_ascii_alphabetic())
+        && bytes.all(|b| b.is_ascii_alphanumeric() || b"+-.".contains(&b))
+}
+
+fn percent_decode_bytes(s: &str) -> Result<Vec<u8>, UriError> {
+    let bytes = s.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' {
+            let hex = bytes
+                .get(i + 1..i + 3)
+                .and_then(|h| str::from_utf8(h).ok())
+                .filter(|h| h.bytes().all(|b| b.is_ascii_hexdigit()))
+                .ok_or(UriError::InvalidPercentEncoding)?;
+            decoded.push(u8::from_str_radix(hex, 16).unwrap());
+            i += 3;
+        } else {
+            decoded.push(bytes[i]);
+            i += 1;
+        }
+    }
+    Ok(decoded)
+}
+
+pub fn percent_decode(s: &str) -> Result<String, UriError> {
+    String::from_utf8(percent_decode_bytes(s)?).map_err(|_| UriError::InvalidPercentEncoding)
+}
+
+// 경로 세그먼트 하나를 인코딩한다. unreserved 문자(RFC 3986 2.3) 밖은 모두 %XX로 바꾼다.
+pub fn percent_encode(segment: &str) -> String {
+    let mut encoded = String::with_capacity(segment.len());
+    for b in segment.bytes() {
+        if b.is_ascii_alphanumeric() || b"-._~".contains(&b) {
+            encoded.push(b as char);
+        } else {
+            encoded.push_str(&format!("%{:02X}", b));
+        }
+    }
+    encoded
+}
+
+// 쿼리 구성 요소는 form 인코딩 관례에 따라 '+'를 공백으로 읽고, 잘못된 인코딩은 그대로 둔다
+fn decode_query_component(s: &str) -> String {
+    let s = s.replace('+', " ");
+    match percent_decode_bytes(&s) {
+        Ok(bytes) => String::from_utf8_lossy(&bytes).into_owned(),
+        Err(_) => s,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_origin_form() {
+        let uri =
+            Uri::parse("/api/shipping/orders?order_status=Pending&tag=a&tag=b+c#top").unwrap();
+        assert_eq!(None, uri.scheme());
+        assert_eq!("/api/shipping/orders", uri.path());
+        assert_eq!(vec!["api", "shipping", "orders"], uri.segments());
+        assert_eq!(Some("Pending"), uri.query().get("order_status"));
+        assert_eq!(vec!["a", "b c"], uri.query().get_all("tag"));
+        assert_eq!(None, uri.query().get("top"));
+    }
+
+    #[test]
+    fn test_parse_root_and_trailing_slash() {
+        let uri = Uri::parse("/").unwrap();
+        assert_eq!("/", uri.path());
+        assert!(uri.segments().is_empty());
+        assert!(uri.query().is_empty());
+
+        let uri = Uri::parse("/docs/./guide/").unwrap();
+        assert_eq!("/docs/guide/", uri.path());
+        assert_eq!(vec!["docs", "guide", ""], uri.segments());
+    }
+
+    #[test]
+    fn test_parse_absolute_form() {
+        let uri = Uri::parse("HTTP://localhost:3000?x=1").unwrap();
+        assert_eq!(Some("http"), uri.scheme());
+        assert_eq!(Some("localhost:3000"), uri.authority());
+        assert_eq!("/", uri.path());
+        assert_eq!(Some("1"), uri.query().get("x"));
+        assert_eq!("/?x=1", uri.origin_form());
+        let uri = Uri::parse("http://a.test/orders/1?x=%20#top").unwrap();
+        assert_eq!("/orders/1?x=%20", uri.origin_form());
+    }
+
+    #[test]
+    fn test_percent_decoding() {
+        let uri = Uri::parse("/files/a%20b/c%2Fd").unwrap();
+        assert_eq!(vec!["files", "a b", "c/d"], uri.segments());
+        assert_eq!(Err(UriError::InvalidPercentEncoding), Uri::parse("/a%2"));
+        assert_eq!(Err(UriError::InvalidPercentEncoding), Uri::parse("/a%zz"));
+        assert_eq!(Err(UriError::InvalidPercentEncoding), Uri::parse("/a%ff"));
+
+        assert_eq!("a%20b%2Fc~%EC%A3%BC", percent_encode("a b/c~주"));
+        assert_eq!(
+            Ok("a b/c~주".to_string()),
+            percent_decode(&percent_encode("a b/c~주"))
+        );
+    }
+
+    #[test]
+    fn test_reject_traversal_and_malformed() {
+        assert_eq!(Err(UriError::PathTraversal), Uri::parse("/../etc/passwd"));
+        assert_eq!(Err(UriError::PathTraversal), Uri::parse("/a/%2e%2E/b"));
+        assert_eq!(Err(UriError::Malformed), Uri::parse("index.html"));
+        assert_eq!(Err(UriError::Malformed), Uri::parse("http:///a"));
+        assert_eq!(Err(UriError::Malformed), Uri::parse(""));
+    }
+}
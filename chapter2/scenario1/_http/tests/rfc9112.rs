@@ -0,0 +1,322 @@
+// RFC 9112의 경계 사례 모음. 요청 밀반입(smuggling)에 쓰이는 모호한 요청은 거부하고,
+// 받아들이는 요청은 언제나 같은 방식으로 해석해야 한다.
+use http::httprequest::{HttpRequest, Limits, ParseError, Version};
+use http::status::StatusCode;
+
+fn parse(raw: &[u8]) -> Result<HttpRequest, ParseError> {
+    HttpRequest::from_reader(&mut &raw[..])
+}
+
+// 거부해야 하는 요청과 기대하는 에러
+const REJECTED: &[(&str, &[u8], &str)] = &[
+    (
+        "obs-fold continuation line",
+        b"GET / HTTP/1.1\r\nX-Long: a\r\n b\r\n\r\n",
+        "InvalidHeader",
+    ),
+    (
+        "obs-fold with tab",
+        b"GET / HTTP/1.1\r\nX-Long: a\r\n\tb\r\n\r\n",
+        "InvalidHeader",
+    ),
+    (
+        "whitespace before first header",
+        b"GET / HTTP/1.1\r\n Host: a\r\n\r\n",
+        "InvalidHeader",
+    ),
+    (
+        "whitespace before colon",
+        b"POST / HTTP/1.1\r\nTransfer-Encoding : chunked\r\n\r\n0\r\n\r\n",
+        "InvalidHeader",
+    ),
+    (
+        "header without colon",
+        b"GET / HTTP/1.1\r\nHost\r\n\r\n",
+        "InvalidHeader",
+    ),
+    (
+        "NUL in header value",
+        b"GET / HTTP/1.1\r\nX-A: a\0b\r\n\r\n",
+        "InvalidHeader",
+    ),
+    (
+        "bare CR in header",
+        b"GET / HTTP/1.1\r\nX-A: a\rContent-Length: 5\r\n\r\n",
+        "InvalidLineEnding",
+    ),
+    (
+        "bare CR in request line",
+        b"GET /\r HTTP/1.1\r\n\r\n",
+        "InvalidLineEnding",
+    ),
+    (
+        "differing duplicate Content-Length",
+        b"POST / HTTP/1.1\r\nContent-Length: 3\r\nContent-Length: 5\r\n\r\nabcde",
+        "InvalidContentLength",
+    ),
+    (
+        "differing Content-Length list",
+        b"POST / HTTP/1.1\r\nContent-Length: 3, 5\r\n\r\nabcde",
+        "InvalidContentLength",
+    ),
+    (
+        "signed Content-Length",
+        b"POST / HTTP/1.1\r\nContent-Length: +3\r\n\r\nabc",
+        "InvalidContentLength",
+    ),
+    (
+        "empty Content-Length",
+        b"POST / HTTP/1.1\r\nContent-Length:\r\n\r\n",
+        "InvalidContentLength",
+    ),
+    (
+        "overflowing Content-Length",
+        b"POST / HTTP/1.1\r\nContent-Length: 99999999999999999999999\r\n\r\n",
+        "InvalidContentLength",
+    ),
+    (
+        "CL.TE",
+        b"POST / HTTP/1.1\r\nContent-Length: 6\r\nTransfer-Encoding: chunked\r\n\r\n0\r\n\r\nX",
+        "AmbiguousBodyLength",
+    ),
+    (
+        "TE.CL",
+        b"POST / HTTP/1.1\r\nTransfer-Encoding: chunked\r\nContent-Length: 3\r\n\r\n8\r\nSMUGGLED\r\n0\r\n\r\n",
+        "AmbiguousBodyLength",
+    ),
+    (
+        "obfuscated coding",
+        b"POST / HTTP/1.1\r\nTransfer-Encoding: xchunked\r\n\r\n0\r\n\r\n",
+        "UnsupportedTransferEncoding",
+    ),
+    (
+        "chunked not last",
+        b"POST / HTTP/1.1\r\nTransfer-Encoding: chunked, identity\r\n\r\n0\r\n\r\n",
+        "UnsupportedTransferEncoding",
+    ),
+    (
+        "repeated Transfer-Encoding",
+        b"POST / HTTP/1.1\r\nTransfer-Encoding: chunked\r\nTransfer-Encoding: chunked\r\n\r\n0\r\n\r\n",
+        "UnsupportedTransferEncoding",
+    ),
+    (
+        "Transfer-Encoding in HTTP/1.0",
+        b"POST / HTTP/1.0\r\nTransfer-Encoding: chunked\r\n\r\n0\r\n\r\n",
+        "UnsupportedTransferEncoding",
+    ),
+    (
+        "negative chunk size",
+        b"POST / HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\n-1\r\n\r\n",
+        "InvalidChunk",
+    ),
+    (
+        "0x chunk size",
+        b"POST / HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\n0x5\r\nabcde\r\n0\r\n\r\n",
+        "InvalidChunk",
+    ),
+    (
+        "overflowing chunk size",
+        b"POST / HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\n10000000000000000\r\n",
+        "InvalidChunk",
+    ),
+    (
+        "chunk data longer than size",
+        b"POST / HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\n3\r\nabcde\r\n0\r\n\r\n",
+        "InvalidChunk",
+    ),
+    (
+        "two spaces in request line",
+        b"GET  / HTTP/1.1\r\n\r\n",
+        "InvalidRequestLine",
+    ),
+    (
+        "tab in request line",
+        b"GET\t/ HTTP/1.1\r\n\r\n",
+        "InvalidRequestLine",
+    ),
+    (
+        "missing version",
+        b"GET /\r\n\r\n",
+        "InvalidRequestLine",
+    ),
+    (
+        "lowercase version",
+        b"GET / http/1.1\r\n\r\n",
+        "InvalidRequestLine",
+    ),
+    (
+        "HTTP/2.0 request line",
+        b"GET / HTTP/2.0\r\nHost: a\r\n\r\n",
+        "UnsupportedVersion",
+    ),
+    (
+        "HTTP/3.0 request line",
+        b"GET / HTTP/3.0\r\nHost: a\r\n\r\n",
+        "UnsupportedVersion",
+    ),
+    (
+        "HTTP/0.9 request line",
+        b"GET / HTTP/0.9\r\n\r\n",
+        "UnsupportedVersion",
+    ),
+    (
+        "space in target",
+        b"GET /a b HTTP/1.1\r\n\r\n",
+        "InvalidRequestLine",
+    ),
+    (
+        "truncated body",
+        b"POST / HTTP/1.1\r\nContent-Length: 10\r\n\r\nabc",
+        "UnexpectedEof",
+    ),
+    (
+        "truncated headers",
+        b"GET / HTTP/1.1\r\nHost: a\r\n",
+        "UnexpectedEof",
+    ),
+];
+
+#[test]
+fn test_rejected_requests() {
+    for (name, raw, expected) in REJECTED {
+        match parse(raw) {
+            Ok(req) => panic!("{}: accepted as {} {}", name, req.method, req.resource),
+            Err(e) => assert!(
+                format!("{:?}", e).starts_with(expected),
+                "{}: expected {}, got {:?}",
+                name,
+                expected,
+                e
+            ),
+        }
+    }
+}
+
+#[test]
+fn test_rejection_status() {
+    let status = |raw: &[u8]| parse(raw).unwrap_err().status();
+    // 모르는 전송 코딩은 501, 틀린 형식은 400
+    assert_eq!(
+        StatusCode::NotImplemented,
+        status(b"POST / HTTP/1.1\r\nTransfer-Encoding: gzip\r\n\r\n")
+    );
+    assert_eq!(
+        StatusCode::NotImplemented,
+        status(b"POST / HTTP/1.1\r\nTransfer-Encoding: chunked, identity\r\n\r\n0\r\n\r\n")
+    );
+    assert_eq!(
+        StatusCode::BadRequest,
+        status(b"POST / HTTP/1.1\r\nContent-Length: 3, 5\r\n\r\nabcde")
+    );
+    assert_eq!(
+        StatusCode::HttpVersionNotSupported,
+        status(b"GET / HTTP/2.0\r\nHost: a\r\n\r\n")
+    );
+    let limits = Limits {
+        max_request_line: 20,
+        ..Limits::default()
+    };
+    let err = HttpRequest::from_reader_with_limits(
+        &mut &b"GET /aaaaaaaaaaaaaaaaaaaa HTTP/1.1\r\n\r\n"[..],
+        &limits,
+    )
+    .unwrap_err();
+    assert_eq!(StatusCode::UriTooLong, err.status());
+}
+
+#[test]
+fn test_higher_minor_version_is_http_1_1() {
+    // HTTP/1.2는 1.1로 다루므로 Connection 헤더가 없으면 커넥션을 유지한다
+    let req = parse(b"GET / HTTP/1.2\r\nHost: a\r\n\r\n").unwrap();
+    assert_eq!(Version::V1_1, req.version);
+    assert!(req.keep_alive());
+    let req = parse(b"GET / HTTP/1.9\r\nHost: a\r\nConnection: close\r\n\r\n").unwrap();
+    assert_eq!(Version::V1_1, req.version);
+    assert!(!req.keep_alive());
+}
+
+#[test]
+fn test_empty_header_values() {
+    let req = parse(b"GET / HTTP/1.1\r\nX-Empty:\r\nX-Spaces:   \r\nHost: a\r\n\r\n").unwrap();
+    assert_eq!(Some(""), req.headers.get("X-Empty"));
+    assert_eq!(Some(""), req.headers.get("X-Spaces"));
+    assert_eq!(Some("a"), req.headers.get("Host"));
+}
+
+#[test]
+fn test_bare_lf_line_endings() {
+    // LF만으로 끝나는 행도 행 끝으로 받는다 (RFC 9112 2.2)
+    let req = parse(b"POST /a HTTP/1.1\nContent-Length: 2\nX-A: b\r\n\nok").unwrap();
+    assert_eq!("/a", req.resource.path());
+    assert_eq!(Some("b"), req.headers.get("X-A"));
+    assert_eq!(b"ok".to_vec(), req.msg_body);
+}
+
+#[test]
+fn test_repeated_equal_content_length() {
+    for raw in [
+        &b"POST / HTTP/1.1\r\nContent-Length: 3\r\nContent-Length: 3\r\n\r\nabc"[..],
+        &b"POST / HTTP/1.1\r\nContent-Length: 3, 3\r\n\r\nabc"[..],
+    ] {
+        assert_eq!(b"abc".to_vec(), parse(raw).unwrap().msg_body);
+    }
+}
+
+#[test]
+fn test_leading_empty_lines_and_case_insensitive_chunked() {
+    let req = parse(
+        b"\r\n\r\nPOST / HTTP/1.1\r\nTransfer-Encoding: ChUnKeD\r\n\r\n3;ext=1\r\nabc\r\n0\r\nX-Sum: 1\r\n\r\n",
+    )
+    .unwrap();
+    assert_eq!(b"abc".to_vec(), req.msg_body);
+    assert_eq!(Some("1"), req.trailers.get("X-Sum"));
+}
+
+#[test]
+fn test_pipelined_body_boundaries() {
+    // 본문 길이만큼만 읽으므로 뒤의 바이트는 항상 다음 요청으로 해석된다
+    let raw = b"POST / HTTP/1.1\r\nContent-Length: 0\r\n\r\nGET /admin HTTP/1.1\r\n\r\nPOST / HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\n0\r\n\r\nGET /next HTTP/1.1\r\n\r\n";
+    let mut reader = &raw[..];
+    let paths: Vec<String> = (0..4)
+        .map(|_| {
+            HttpRequest::from_reader(&mut reader)
+                .unwrap()
+                .resource
+                .path()
+                .to_string()
+        })
+        .collect();
+    assert_eq!(vec!["/", "/admin", "/", "/next"], paths);
+    assert!(matches!(
+        HttpRequest::from_reader(&mut reader),
+        Err(ParseError::ConnectionClosed)
+    ));
+}
+
+#[test]
+fn test_limits_apply_to_every_part() {
+    let limits = Limits {
+        max_request_line: 20,
+        max_header_bytes: 32,
+        max_header_count: 2,
+        max_body_bytes: 4,
+    };
+    let parse = |raw: &[u8]| HttpRequest::from_reader_with_limits(&mut &raw[..], &limits);
+    assert!(matches!(
+        parse(b"GET /aaaaaaaaaaaaaaaa HTTP/1.1\r\n\r\n"),
+        Err(ParseError::RequestLineTooLong)
+    ));
+    assert!(matches!(
+        parse(b"GET / HTTP/1.1\r\nA: 1\r\nB: 2\r\nC: 3\r\n\r\n"),
+        Err(ParseError::HeaderTooLarge)
+    ));
+    assert!(matches!(
+        parse(b"POST / HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\n3\r\nabc\r\n3\r\nabc\r\n0\r\n\r\n"),
+        Err(ParseError::BodyTooLarge)
+    ));
+    // 트레일러에도 헤더 개수 제한이 따로 걸린다
+    assert!(matches!(
+        parse(b"POST / HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\n0\r\nA: 1\r\nB: 2\r\nC: 3\r\n\r\n"),
+        Err(ParseError::HeaderTooLarge)
+    ));
+}
@@ -0,0 +1,272 @@
+use http::date::{fmt_clf_date, fmt_rfc3339};
+use http::httprequest::HttpRequest;
+use serde::Deserialize;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime};
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogFormat {
+    // 원격 주소, 시각, 요청 행, 상태 코드, 보낸 바이트 수
+    Common,
+    // Common에 Referer와 User-Agent를 더한 형식
+    Combined,
+    // 같은 항목과 처리 시간을 담은 JSON 한 줄
+    Json,
+}
+
+// 응답 하나에 대한 접근 로그 항목.
+// 요청을 파싱하지 못해 에러로 응답한 경우에는 요청이 없다.
+pub struct AccessEntry<'a> {
+    pub remote_addr: Option<SocketAddr>,
+    pub request: Option<&'a HttpRequest>,
+    pub status: u16,
+    // 보낸 본문 바이트 수. 헤더는 세지 않는다.
+    pub bytes: u64,
+    pub duration: Duration,
+    pub time: SystemTime,
+}
+
+// 응답을 보낼 때마다 한 줄씩 남기는 접근 로그.
+// 여러 worker가 함께 쓰므로 출력 대상을 잠그고 한 줄을 통째로 쓴다.
+pub struct AccessLog {
+    format: LogFormat,
+    sink: Mutex<Sink>,
+}
+
+enum Sink {
+    Stdout,
+    File(RotatingFile),
+}
+
+impl AccessLog {
+    pub fn stdout(format: LogFormat) -> Self {
+        AccessLog {
+            format,
+            sink: Mutex::new(Sink::Stdout),
+        }
+    }
+
+    // 파일에 쓴다. 파일이 max_bytes를 넘으면 이름 뒤에 .1, .2, ...를 붙여 밀어내고,
+    // 가장 오래된 것부터 지워 max_files개까지만 남긴다.
+    pub fn file(
+        format: LogFormat,
+        path: &Path,
+        max_bytes: u64,
+        max_files: usize,
+    ) -> io::Result<Self> {
+        Ok(AccessLog {
+            format,
+            sink: Mutex::new(Sink::File(RotatingFile::open(path, max_bytes, max_files)?)),
+        })
+    }
+
+    pub fn log(&self, entry: &AccessEntry<'_>) {
+        let line = self.format_line(entry);
+        let mut sink = self.sink.lock().unwrap_or_else(|e| e.into_inner());
+        let result = match &mut *sink {
+            Sink::Stdout => writeln!(io::stdout().lock(), "{}", line),
+            Sink::File(file) => file.write_line(&line),
+        };
+        // 로그를 못 쓴다고 요청 처리를 멈추지는 않는다
+        if let Err(e) = result {
+            eprintln!("Failed to write access log: {}", e);
+        }
+    }
+
+    fn format_line(&self, entry: &AccessEntry<'_>) -> String {
+        let remote = entry
+            .remote_addr
+            .map(|addr| addr.ip().to_string())
+            .unwrap_or_else(|| "-".to_string());
+        let header = |name| entry.request.and_then(|req| req.headers.get(name));
+        match self.format {
+            LogFormat::Common | LogFormat::Combined => {
+                let request_line = match entry.request {
+                    Some(req) => {
+                        escape(&format!("{} {} {}", req.method, req.resource, req.version))
+                    }
+                    None => "-".to_string(),
+                };
+                let bytes = match entry.bytes {
+                    0 => "-".to_string(),
+                    bytes => bytes.to_string(),
+                };
+                let mut line = format!(
+                    "{} - - [{}] \"{}\" {} {}",
+                    remote,
+                    fmt_clf_date(entry.time),
+                    request_line,
+                    entry.status,
+                    bytes
+                );
+                if self.format == LogFormat::Combined {
+                    let quoted = |value: Option<&str>| escape(value.unwrap_or("-"));
+                    line.push_str(&format!(
+                        " \"{}\" \"{}\"",
+                        quoted(header("Referer")),
+                        quoted(header("User-Agent"))
+                    ));
+                }
+                line
+            }
+            LogFormat::Json => serde_json::json!({
+                "time": fmt_rfc3339(entry.time),
+                "remote_addr": entry.remote_addr.map(|addr| addr.ip().to_string()),
+                "method": entry.request.map(|req| req.method.to_string()),
+                "target": entry.request.map(|req| req.resource.to_string()),
+                "status": entry.status,
+                "bytes": entry.bytes,
+                "duration_ms": entry.duration.as_secs_f64() * 1000.0,
+                "referer": header("Referer"),
+                "user_agent": header("User-Agent"),
+            })
+            .to_string(),
+        }
+    }
+}
+
+// 따옴표 안에 들어가는 값에서 따옴표, 역슬래시, 제어 문자를 \xhh로 바꿔
+// 클라이언트가 보낸 값이 로그 행을 깨거나 가짜 행을 만들지 못하게 한다.
+fn escape(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '"' | '\\' => escaped.push_str(&format!("\\x{:02x}", c as u32)),
+            c if c.is_control() => escaped.push_str(&format!("\\x{:02x}", c as u32)),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+struct RotatingFile {
+    path: PathBuf,
+    max_bytes: u64,
+    max_files: usize,
+    file: File,
+    size: u64,
+}
+
+impl RotatingFile {
+    fn open(path: &Path, max_bytes: u64, max_files: usize) -> io::Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        let size = file.metadata()?.len();
+        Ok(RotatingFile {
+            path: path.to_path_buf(),
+            max_bytes,
+            max_files,
+            file,
+            size,
+        })
+    }
+
+    fn write_line(&mut self, line: &str) -> io::Result<()> {
+        let len = line.len() as u64 + 1;
+        if self.size > 0 && self.size + len > self.max_bytes {
+            self.rotate()?;
+        }
+        writeln!(self.file, "{}", line)?;
+        self.size += len;
+        Ok(())
+    }
+
+    fn rotate(&mut self) -> io::Result<()> {
+        let numbered = |n: usize| {
+            let mut name = self.path.clone().into_os_string();
+            name.push(format!(".{}", n));
+            PathBuf::from(name)
+        };
+        if self.max_files == 0 {
+            fs::remove_file(&self.path)?;
+        } else {
+            // access.log.(n-1) -> access.log.n, ..., access.log -> access.log.1
+            for n in (1..self.max_files).rev() {
+                if numbered(n).exists() {
+                    fs::rename(numbered(n), numbered(n + 1))?;
+                }
+            }
+            fs::rename(&self.path, numbered(1))?;
+        }
+        self.file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?;
+        self.size = 0;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+    use std::time::UNIX_EPOCH;
+
+    fn entry(req: Option<&HttpRequest>, bytes: u64) -> AccessEntry<'_> {
+        AccessEntry {
+            remote_addr: Some("192.0.2.7:51234".parse().unwrap()),
+            request: req,
+            status: 200,
+            bytes,
+            duration: Duration::from_micros(2500),
+            time: UNIX_EPOCH + Duration::from_secs(784111777),
+        }
+    }
+
+    #[test]
+    fn test_formats() {
+        let req = HttpRequest::try_from(
+            "GET /index.html?q=1 HTTP/1.1\r\nReferer: http://a.test/\r\nUser-Agent: curl \"8\"\r\n\r\n"
+                .to_string(),
+        )
+        .unwrap();
+        assert_eq!(
+            "192.0.2.7 - - [06/Nov/1994:08:49:37 +0000] \"GET /index.html?q=1 HTTP/1.1\" 200 1234",
+            AccessLog::stdout(LogFormat::Common).format_line(&entry(Some(&req), 1234))
+        );
+        assert_eq!(
+            "192.0.2.7 - - [06/Nov/1994:08:49:37 +0000] \"GET /index.html?q=1 HTTP/1.1\" 200 - \"http://a.test/\" \"curl \\x228\\x22\"",
+            AccessLog::stdout(LogFormat::Combined).format_line(&entry(Some(&req), 0))
+        );
+        // 파싱하지 못한 요청은 요청 행과 헤더 자리를 비운다
+        assert_eq!(
+            "192.0.2.7 - - [06/Nov/1994:08:49:37 +0000] \"-\" 200 5 \"-\" \"-\"",
+            AccessLog::stdout(LogFormat::Combined).format_line(&entry(None, 5))
+        );
+
+        let line = AccessLog::stdout(LogFormat::Json).format_line(&entry(Some(&req), 10));
+        let json: serde_json::Value = serde_json::from_str(&line).unwrap();
+        assert_eq!("1994-11-06T08:49:37Z", json["time"]);
+        assert_eq!("192.0.2.7", json["remote_addr"]);
+        assert_eq!("/index.html?q=1", json["target"]);
+        assert_eq!(10, json["bytes"]);
+        assert_eq!(2.5, json["duration_ms"]);
+        assert_eq!("curl \"8\"", json["user_agent"]);
+        let line = AccessLog::stdout(LogFormat::Json).format_line(&entry(None, 0));
+        let json: serde_json::Value = serde_json::from_str(&line).unwrap();
+        assert!(json["method"].is_null());
+    }
+
+    #[test]
+    fn test_rotating_file() {
+        let dir = env::temp_dir().join(format!("access-log-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("access.log");
+        let log = AccessLog::file(LogFormat::Common, &path, 150, 2).unwrap();
+        for _ in 0..7 {
+            log.log(&entry(None, 1));
+        }
+        // 한 줄은 53바이트이므로 파일마다 두 줄씩 들어가고, 오래된 파일은 두 개까지만 남는다
+        let lines = |path: &Path| fs::read_to_string(path).unwrap().lines().count();
+        assert_eq!(1, lines(&path));
+        assert_eq!(2, lines(&dir.join("access.log.1")));
+        assert_eq!(2, lines(&dir.join("access.log.2")));
+        assert!(!dir.join("access.log.3").exists());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
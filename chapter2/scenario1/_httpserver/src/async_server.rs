@@ -0,0 +1,191 @@
+use super::connection_limit::IpConnectionLimiter;
+use super::server::{
+    handle_connection, rejection, ConnectionOptions, Endpoint, Listener, REJECT_WRITE_TIMEOUT,
+};
+use http::status::StatusCode;
+use std::io;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use tokio::io::AsyncWriteExt;
+use tokio::net::{TcpListener, TcpStream};
+use tokio::signal::unix::{signal, SignalKind};
+use tokio::sync::{watch, Semaphore};
+use tokio::task::JoinSet;
+
+// tokio 런타임에서 커넥션을 비동기로 받는다.
+// 요청 처리 코드는 블로킹 I/O를 쓰므로 커넥션마다 tokio의 블로킹 스레드 풀에서 돌린다.
+// 블로킹 스레드 수는 workers로, 동시에 맡는 커넥션 수는 workers + queue_capacity로 제한한다.
+pub fn run(
+    listeners: &[Listener],
+    options: ConnectionOptions,
+    workers: usize,
+    queue_capacity: usize,
+    shutdown: Arc<AtomicBool>,
+) -> io::Result<()> {
+    let runtime = tokio::runtime::Builder::new_multi_thread()
+        .max_blocking_threads(workers)
+        .enable_all()
+        .build()?;
+
+    runtime.block_on(async move {
+        let total_permits = workers + queue_capacity;
+        let permits = Arc::new(Semaphore::new(total_permits));
+        let limiter = options.limiter();
+        let (stop, stopped) = watch::channel(false);
+        let mut accept_loops = JoinSet::new();
+        for listener in listeners {
+            let socket = TcpListener::bind(&listener.addr).await?;
+            println!("Listening on {}", listener.describe());
+            accept_loops.spawn(accept_loop(
+                socket,
+                Arc::clone(&listener.endpoint),
+                options.clone(),
+                Arc::clone(&limiter),
+                Arc::clone(&permits),
+                Arc::clone(&shutdown),
+                stopped.clone(),
+            ));
+        }
+        println!("Running with {} async workers", workers);
+
+        let mut terminate = signal(SignalKind::terminate())?;
+        tokio::select! {
+            _ = tokio::signal::ctrl_c() => {}
+            _ = terminate.recv() => {}
+        }
+
+        shutdown.store(true, Ordering::SeqCst);
+        let _ = stop.send(true);
+        println!("Shutting down, waiting for in-flight requests");
+        while accept_loops.join_next().await.is_some() {}
+        // 처리 중인 커넥션이 모두 허용량을 돌려줄 때까지 기다린다
+        let _ = permits.acquire_many(total_permits as u32).await;
+        // worker에서 넘겨받은 웹소켓과 이벤트 스트림 커넥션도 닫힐 때까지 기다린다
+        let _ = tokio::task::spawn_blocking(move || options.wait_for_streams()).await;
+        Ok(())
+    })
+}
+
+// 리스너 하나에서 종료 신호가 올 때까지 커넥션을 받는다
+async fn accept_loop(
+    socket: TcpListener,
+    endpoint: Arc<Endpoint>,
+    options: ConnectionOptions,
+    limiter: Arc<IpConnectionLimiter>,
+    permits: Arc<Semaphore>,
+    shutdown: Arc<AtomicBool>,
+    mut stopped: watch::Receiver<bool>,
+) {
+    loop {
+        let accepted = tokio::select! {
+            accepted = socket.accept() => accepted,
+            _ = stopped.changed() => return,
+        };
+        let (stream, peer) = match accepted {
+            Ok(accepted) => accepted,
+            Err(e) => {
+                println!("Failed to accept connection: {}", e);
+                continue;
+            }
+        };
+        let endpoint = Arc::clone(&endpoint);
+        // 같은 IP가 이미 커넥션을 너무 많이 열었으면 429로 거절한다
+        let Some(slot) = limiter.try_acquire(peer.ip()) else {
+            tokio::spawn(reject(stream, endpoint, StatusCode::TooManyRequests));
+            continue;
+        };
+        // 허용량이 남아 있지 않으면 503으로 거절한다
+        let permit = match Arc::clone(&permits).try_acquire_owned() {
+            Ok(permit) => permit,
+            Err(_) => {
+                tokio::spawn(reject(stream, endpoint, StatusCode::ServiceUnavailable));
+                continue;
+            }
+        };
+        let stream = match stream.into_std().and_then(|stream| {
+            stream.set_nonblocking(false)?;
+            Ok(stream)
+        }) {
+            Ok(stream) => stream,
+            Err(e) => {
+                println!("Failed to accept connection: {}", e);
+                continue;
+            }
+        };
+        let shutdown = Arc::clone(&shutdown);
+        let options = options.clone();
+        tokio::task::spawn_blocking(move || {
+            handle_connection(stream, &endpoint, &options, &shutdown, Some(slot));
+            drop(permit);
+        });
+    }
+}
+
+// 거절 응답은 블로킹 스레드 풀을 거치지 않고 런타임에서 바로 보낸다.
+// 블로킹 스레드는 모두 바쁜 커넥션이 쓰고 있을 수 있으므로, 거기서 보내면 거절도 그 뒤에 줄을 선다.
+async fn reject(mut stream: TcpStream, endpoint: Arc<Endpoint>, status: StatusCode) {
+    let Some(response) = rejection(&endpoint, status) else {
+        return;
+    };
+    match tokio::time::timeout(REJECT_WRITE_TIMEOUT, stream.write_all(&response)).await {
+        Ok(Ok(())) => {}
+        Ok(Err(e)) => println!("Failed to reject connection: {}", e),
+        Err(_) => println!("Failed to reject connection: write timed out"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::router::Router;
+    use std::io::Read;
+    use std::sync::mpsc;
+    use std::thread;
+
+    #[test]
+    fn test_rejection_does_not_wait_for_blocking_threads() {
+        let runtime = tokio::runtime::Builder::new_multi_thread()
+            .max_blocking_threads(1)
+            .enable_all()
+            .build()
+            .unwrap();
+        runtime.block_on(async {
+            let socket = TcpListener::bind("127.0.0.1:0").await.unwrap();
+            let addr = socket.local_addr().unwrap();
+            // 하나뿐인 블로킹 스레드를 붙잡아 둔다
+            let (release, busy) = mpsc::channel::<()>();
+            let blocked = tokio::task::spawn_blocking(move || busy.recv());
+            let options = ConnectionOptions::default();
+            let (stop, stopped) = watch::channel(false);
+            let accepting = tokio::spawn(accept_loop(
+                socket,
+                Arc::new(Endpoint::new(Arc::new(Router::new()), None)),
+                options.clone(),
+                options.limiter(),
+                // 허용량이 없으므로 모든 커넥션을 거절한다
+                Arc::new(Semaphore::new(0)),
+                Arc::new(AtomicBool::new(false)),
+                stopped,
+            ));
+
+            let response = thread::spawn(move || {
+                let mut client = std::net::TcpStream::connect(addr).unwrap();
+                client
+                    .set_read_timeout(Some(std::time::Duration::from_secs(2)))
+                    .unwrap();
+                let mut response = String::new();
+                client.read_to_string(&mut response).map(|_| response)
+            })
+            .join()
+            .unwrap()
+            .unwrap();
+            assert!(response.starts_with("HTTP/1.1 503 Service Unavailable\r\n"));
+            assert!(response.contains("Retry-After: 1\r\n"));
+
+            release.send(()).unwrap();
+            blocked.await.unwrap().unwrap();
+            stop.send(true).unwrap();
+            accepting.await.unwrap();
+        });
+    }
+}
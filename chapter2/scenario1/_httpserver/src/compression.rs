@@ -0,0 +1,348 @@
+use super::middleware::{Middleware, Next};
+use flate2::read::{GzEncoder, ZlibEncoder};
+use http::{
+    body::Body,
+    httprequest::{HttpRequest, Method, Version},
+    httpresponse::HttpResponse,
+    status::StatusCode,
+};
+use std::io::{self, Read};
+
+// 이보다 작은 본문은 압축해도 이득이 거의 없으므로 그대로 보낸다
+const DEFAULT_MIN_SIZE: u64 = 1024;
+// 압축할 Content-Type. 끝이 '/'이면 그 타입 전체(예: text/*)를 뜻한다.
+const DEFAULT_CONTENT_TYPES: &[&str] = &[
+    "text/",
+    "application/json",
+    "application/javascript",
+    "application/xml",
+    "application/manifest+json",
+    "application/wasm",
+    "image/svg+xml",
+];
+// 같은 q 값이면 앞에 있는 코딩을 고른다
+const SERVER_PREFERENCE: [Coding; 3] = [Coding::Brotli, Coding::Gzip, Coding::Deflate];
+const BROTLI_QUALITY: u32 = 5;
+const BROTLI_WINDOW: u32 = 22;
+
+// 지원하는 콘텐츠 코딩 (RFC 9110 8.4.1)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Coding {
+    Brotli,
+    Gzip,
+    Deflate,
+}
+
+impl Coding {
+    // Content-Encoding 헤더에 쓰는 이름
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Coding::Brotli => "br",
+            Coding::Gzip => "gzip",
+            Coding::Deflate => "deflate",
+        }
+    }
+
+    // 미리 압축해 둔 파일의 확장자
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Coding::Brotli => "br",
+            Coding::Gzip => "gz",
+            Coding::Deflate => "zz",
+        }
+    }
+
+    fn matches(&self, name: &str) -> bool {
+        name.eq_ignore_ascii_case(self.as_str())
+            || (*self == Coding::Gzip && name.eq_ignore_ascii_case("x-gzip"))
+    }
+
+    fn encode(&self, reader: Box<dyn Read + Send>) -> Box<dyn Read + Send> {
+        match self {
+            Coding::Brotli => Box::new(brotli::CompressorReader::new(
+                reader,
+                4096,
+                BROTLI_QUALITY,
+                BROTLI_WINDOW,
+            )),
+            Coding::Gzip => Box::new(GzEncoder::new(reader, flate2::Compression::default())),
+            Coding::Deflate => Box::new(ZlibEncoder::new(reader, flate2::Compression::default())),
+        }
+    }
+}
+
+// Accept-Encoding의 q 값에 따라 available 중 가장 선호하는 코딩을 고른다.
+// 헤더가 없거나 받아 줄 코딩이 없으면 None이며, 이때는 압축하지 않는다.
+pub fn negotiate(accept_encoding: Option<&str>, available: &[Coding]) -> Option<Coding> {
+    let accept_encoding = accept_encoding?;
+    let mut preferences: Vec<(&str, f32)> = Vec::new();
+    for item in accept_encoding.split(',').map(str::trim) {
+        let mut params = item.split(';').map(str::trim);
+        let name = params.next().unwrap_or("");
+        if name.is_empty() {
+            continue;
+        }
+        let q = params
+            .find_map(|p| p.strip_prefix("q=").or_else(|| p.strip_prefix("Q=")))
+            .map_or(Some(1.0), |q| {
+                q.parse::<f32>().ok().filter(|q| (0.0..=1.0).contains(q))
+            });
+        // q 값이 잘못된 항목은 무시한다
+        if let Some(q) = q {
+            preferences.push((name, q));
+        }
+    }
+
+    let quality = |coding: &Coding| {
+        preferences
+            .iter()
+            .find(|(name, _)| coding.matches(name))
+            .or_else(|| preferences.iter().find(|(name, _)| *name == "*"))
+            .map_or(0.0, |(_, q)| *q)
+    };
+    let mut best: Option<(Coding, f32)> = None;
+    for coding in SERVER_PREFERENCE.iter().filter(|c| available.contains(c)) {
+        let q = quality(coding);
+        if q > 0.0 && best.is_none_or(|(_, best_q)| q > best_q) {
+            best = Some((*coding, q));
+        }
+    }
+    best.map(|(coding, _)| coding)
+}
+
+// 클라이언트가 받아 주는 코딩으로 응답 본문을 압축한다.
+// 메모리에 있는 본문은 한 번에 압축해 길이를 알리고, 파일 같은 본문은 읽으면서 압축해 chunked로 보낸다.
+pub struct Compression {
+    min_size: u64,
+    content_types: Vec<String>,
+}
+
+impl Default for Compression {
+    fn default() -> Self {
+        Compression {
+            min_size: DEFAULT_MIN_SIZE,
+            content_types: DEFAULT_CONTENT_TYPES
+                .iter()
+                .map(|t| t.to_string())
+                .collect(),
+        }
+    }
+}
+
+impl Compression {
+    pub fn new() -> Self {
+        Compression::default()
+    }
+
+    pub fn min_size(mut self, min_size: u64) -> Self {
+        self.min_size = min_size;
+        self
+    }
+
+    pub fn content_types(mut self, content_types: Vec<String>) -> Self {
+        self.content_types = content_types;
+        self
+    }
+
+    fn is_compressible(&self, content_type: &str) -> bool {
+        let mime = content_type.split(';').next().unwrap_or("").trim();
+        self.content_types.iter().any(|allowed| {
+            if allowed.ends_with('/') {
+                mime.starts_with(allowed.as_str())
+            } else {
+                mime.eq_ignore_ascii_case(allowed)
+            }
+        })
+    }
+}
+
+impl Middleware for Compression {
+    fn handle(&self, req: &HttpRequest, next: Next<'_>) -> HttpResponse {
+        let mut resp = next.run(req);
+        let compressible = resp
+            .headers()
+            .content_type()
+            .is_some_and(|t| self.is_compressible(t));
+        if !compressible {
+            return resp;
+        }
+        // 압축 여부가 Accept-Encoding에 따라 달라지므로 캐시에 알린다
+        add_vary(&mut resp, "Accept-Encoding");
+
+        // 부분 응답이나 이미 인코딩된 본문(미리 압축한 파일 등)은 건드리지 않는다
+        if resp.status() != StatusCode::Ok
+            || resp.headers().contains("Content-Encoding")
+            || !(req.method == Method::Get || req.method == Method::Head)
+            || resp.body().len().is_none_or(|len| len < self.min_size)
+        {
+            return resp;
+        }
+        let Some(coding) = negotiate(req.headers.get("Accept-Encoding"), &SERVER_PREFERENCE) else {
+            return resp;
+        };
+
+        // 파일 같은 본문은 압축한 길이를 미리 알 수 없어 chunked로 보내야 하는데,
+        // HTTP/1.0 클라이언트는 chunked를 읽지 못하므로 압축하지 않고 원래 길이로 보낸다
+        let in_memory = resp.body().as_bytes().is_some();
+        if !in_memory && req.version == Version::V1_0 {
+            return resp;
+        }
+        let body = resp.take_body();
+        let encoded = coding.encode(body.into_reader());
+        let body = if in_memory {
+            match read_all(encoded) {
+                Ok(bytes) => Body::from(bytes),
+                Err(e) => {
+                    println!("Failed to compress response: {}", e);
+                    return HttpResponse::new(StatusCode::InternalServerError, None, None);
+                }
+            }
+        } else {
+            Body::from_reader(encoded, None)
+        };
+        resp.set_body(body);
+        resp.headers_mut()
+            .insert("Content-Encoding", coding.as_str());
+        // 압축한 표현은 바이트가 달라지므로 강한 ETag를 약하게 바꾼다
+        if let Some(etag) = resp.headers().get("ETag").map(String::from) {
+            if !etag.starts_with("W/") {
+                resp.headers_mut().insert("ETag", format!("W/{}", etag));
+            }
+        }
+        // 압축하면 바이트 위치가 달라지므로 범위 요청을 받지 않는다
+        resp.headers_mut().remove("Accept-Ranges");
+        resp
+    }
+}
+
+// Vary 헤더에 field가 없으면 덧붙인다
+pub fn add_vary(resp: &mut HttpResponse, field: &str) {
+    let varies = resp
+        .headers()
+        .get_all("Vary")
+        .iter()
+        .flat_map(|v| v.split(','))
+        .any(|v| v.trim().eq_ignore_ascii_case(field) || v.trim() == "*");
+    if !varies {
+        resp.headers_mut().append("Vary", field);
+    }
+}
+
+fn read_all(mut reader: impl Read) -> io::Result<Vec<u8>> {
+    let mut bytes = Vec::new();
+    reader.read_to_end(&mut bytes)?;
+    Ok(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::router::{Params, Router};
+    use flate2::read::{GzDecoder, ZlibDecoder};
+    use http::headers::HeaderMap;
+
+    fn request(accept_encoding: Option<&str>) -> HttpRequest {
+        let mut raw = String::from("GET / HTTP/1.1\r\nHost: localhost\r\n");
+        if let Some(value) = accept_encoding {
+            raw.push_str(&format!("Accept-Encoding: {}\r\n", value));
+        }
+        raw.push_str("\r\n");
+        HttpRequest::try_from(raw).unwrap()
+    }
+
+    fn text(_req: &HttpRequest, _params: &Params) -> HttpResponse {
+        let mut headers = HeaderMap::new();
+        headers.insert("Content-Type", "text/html; charset=utf-8");
+        headers.insert("ETag", "\"abc\"");
+        HttpResponse::new(StatusCode::Ok, Some(headers), Some("Hello! ".repeat(400)))
+    }
+
+    fn png(_req: &HttpRequest, _params: &Params) -> HttpResponse {
+        let mut headers = HeaderMap::new();
+        headers.insert("Content-Type", "image/png");
+        HttpResponse::new(StatusCode::Ok, Some(headers), Some("x".repeat(4000)))
+    }
+
+    #[test]
+    fn test_negotiate_q_values() {
+        let all = &SERVER_PREFERENCE;
+        assert_eq!(None, negotiate(None, all));
+        assert_eq!(None, negotiate(Some(""), all));
+        assert_eq!(
+            Some(Coding::Brotli),
+            negotiate(Some("gzip, deflate, br"), all)
+        );
+        assert_eq!(Some(Coding::Gzip), negotiate(Some("br;q=0.5, gzip"), all));
+        assert_eq!(
+            Some(Coding::Deflate),
+            negotiate(Some("deflate, *;q=0.1"), all)
+        );
+        assert_eq!(Some(Coding::Gzip), negotiate(Some("x-gzip"), all));
+        assert_eq!(Some(Coding::Gzip), negotiate(Some("br;q=0, *"), all));
+        assert_eq!(None, negotiate(Some("identity, gzip;q=0"), &[Coding::Gzip]));
+        assert_eq!(None, negotiate(Some("gzip;q=2"), all));
+        assert_eq!(
+            Some(Coding::Gzip),
+            negotiate(Some("br, gzip"), &[Coding::Gzip])
+        );
+    }
+
+    #[test]
+    fn test_compresses_negotiated_coding() {
+        let router = Router::new().wrap(Compression::new()).get("/", text);
+        let expected = "Hello! ".repeat(400).into_bytes();
+
+        let mut resp = router.dispatch(&request(Some("gzip")));
+        assert_eq!(Some("gzip"), resp.headers().get("Content-Encoding"));
+        assert_eq!(Some("Accept-Encoding"), resp.headers().get("Vary"));
+        assert_eq!(Some("W/\"abc\""), resp.headers().get("ETag"));
+        let compressed = resp.take_body().into_bytes().unwrap();
+        assert!(compressed.len() < expected.len());
+        let mut decoded = Vec::new();
+        GzDecoder::new(&compressed[..])
+            .read_to_end(&mut decoded)
+            .unwrap();
+        assert_eq!(expected, decoded);
+
+        let mut resp = router.dispatch(&request(Some("deflate")));
+        let mut decoded = Vec::new();
+        ZlibDecoder::new(&resp.take_body().into_bytes().unwrap()[..])
+            .read_to_end(&mut decoded)
+            .unwrap();
+        assert_eq!(expected, decoded);
+
+        let mut resp = router.dispatch(&request(Some("br")));
+        let mut decoded = Vec::new();
+        brotli::Decompressor::new(&resp.take_body().into_bytes().unwrap()[..], 4096)
+            .read_to_end(&mut decoded)
+            .unwrap();
+        assert_eq!(expected, decoded);
+    }
+
+    #[test]
+    fn test_skips_small_and_binary_bodies() {
+        let router = Router::new()
+            .wrap(Compression::new())
+            .get("/", text)
+            .get("/image", png);
+
+        let resp = router.dispatch(&request(None));
+        assert_eq!(None, resp.headers().get("Content-Encoding"));
+        assert_eq!(Some("Accept-Encoding"), resp.headers().get("Vary"));
+
+        let resp = router.dispatch(
+            &HttpRequest::try_from(
+                "GET /image HTTP/1.1\r\nAccept-Encoding: gzip\r\n\r\n".to_string(),
+            )
+            .unwrap(),
+        );
+        assert_eq!(None, resp.headers().get("Content-Encoding"));
+        assert_eq!(None, resp.headers().get("Vary"));
+
+        let router = Router::new()
+            .wrap(Compression::new().min_size(1 << 20))
+            .get("/", text);
+        let resp = router.dispatch(&request(Some("gzip")));
+        assert_eq!(None, resp.headers().get("Content-Encoding"));
+    }
+}
@@ -0,0 +1,639 @@
+use super::access_log::LogFormat;
+use super::server::WorkerModel;
+use super::tls::TlsCertificate;
+use serde::de::{value::StrDeserializer, DeserializeOwned, IntoDeserializer};
+use serde::Deserialize;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+pub const USAGE: &str = "\
+Usage: httpserver [OPTIONS]
+
+Options:
+  -c, --config <FILE>             TOML configuration file
+      --listen <ADDR>             plain HTTP address, repeatable (default localhost:3000)
+      --document-root <DIR>       directory of static files
+      --data-dir <DIR>            directory of orders.json
+      --workers <N>               connections handled at once
+      --queue <N>                 connections waiting for a worker
+      --worker-model <MODEL>      threads or async
+      --keep-alive-timeout <SECS> idle time allowed between requests
+      --read-timeout <SECS>       time allowed to receive a whole request
+      --write-timeout <SECS>      time allowed to write a response
+      --max-request-line <N>      request line bytes
+      --max-header-bytes <N>      header bytes
+      --max-header-count <N>      header fields
+      --max-body-bytes <N>        request body bytes
+      --max-connections-per-ip <N>
+                                  concurrent connections from one client (0 = unlimited)
+      --max-streams <N>           concurrent WebSocket and event stream connections (0 = unlimited)
+      --log-format <FORMAT>       access log format: common, combined or json
+      --log-file <FILE>           write the access log to a rotating file instead of stdout
+      --tls-listen <ADDR>         HTTPS address, repeatable
+      --tls-cert <FILE>           default certificate chain (PEM)
+      --tls-key <FILE>            private key of the default certificate (PEM)
+      --https-redirect            redirect plain HTTP addresses to HTTPS
+  -h, --help                      print this help
+";
+
+#[derive(Debug)]
+pub enum ConfigError {
+    Read(PathBuf, io::Error),
+    Parse(PathBuf, toml::de::Error),
+    // 명령행 인자가 잘못되었다
+    Usage(String),
+    // 값은 읽었지만 함께 쓸 수 없거나 범위를 벗어난다
+    Invalid(String),
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::Read(path, e) => write!(f, "{}: {}", path.display(), e),
+            ConfigError::Parse(path, e) => write!(f, "{}: {}", path.display(), e),
+            ConfigError::Usage(message) => write!(f, "{} (see --help)", message),
+            ConfigError::Invalid(message) => write!(f, "invalid configuration: {}", message),
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+// 명령행이 요청한 동작
+pub enum Command {
+    Run(Box<Config>),
+    Help,
+}
+
+// 서버 설정. 설정 파일의 값을 명령행 인자가 덮어쓴다.
+// 파일에 없는 항목은 기본값을 쓴다.
+#[derive(Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub listen: Vec<String>,
+    pub document_root: PathBuf,
+    pub data_dir: PathBuf,
+    pub workers: WorkersConfig,
+    pub timeouts: TimeoutsConfig,
+    pub limits: LimitsConfig,
+    pub log: LogConfig,
+    pub static_files: StaticFilesConfig,
+    pub compression: CompressionConfig,
+    pub cors: CorsConfig,
+    pub tls: TlsConfig,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            listen: vec!["localhost:3000".to_string()],
+            document_root: PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("public"),
+            data_dir: PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("data"),
+            workers: WorkersConfig::default(),
+            timeouts: TimeoutsConfig::default(),
+            limits: LimitsConfig::default(),
+            log: LogConfig::default(),
+            static_files: StaticFilesConfig::default(),
+            compression: CompressionConfig::default(),
+            cors: CorsConfig::default(),
+            tls: TlsConfig::default(),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct WorkersConfig {
+    pub threads: usize,
+    pub queue: usize,
+    pub model: WorkerModel,
+}
+
+impl Default for WorkersConfig {
+    fn default() -> Self {
+        WorkersConfig {
+            threads: 8,
+            queue: 64,
+            model: WorkerModel::Threads,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TimeoutsConfig {
+    pub keep_alive_secs: u64,
+    pub read_secs: u64,
+    pub write_secs: u64,
+}
+
+impl Default for TimeoutsConfig {
+    fn default() -> Self {
+        TimeoutsConfig {
+            keep_alive_secs: 5,
+            read_secs: 10,
+            write_secs: 30,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LimitsConfig {
+    pub max_request_line: usize,
+    pub max_header_bytes: usize,
+    pub max_header_count: usize,
+    pub max_body_bytes: usize,
+    pub max_connections_per_ip: usize,
+    pub max_streams: usize,
+}
+
+impl Default for LimitsConfig {
+    fn default() -> Self {
+        let limits = http::httprequest::Limits::default();
+        LimitsConfig {
+            max_request_line: limits.max_request_line,
+            max_header_bytes: limits.max_header_bytes,
+            max_header_count: limits.max_header_count,
+            max_body_bytes: limits.max_body_bytes,
+            max_connections_per_ip: 0,
+            max_streams: 256,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LogConfig {
+    pub format: LogFormat,
+    // 없으면 표준 출력에 쓴다
+    pub file: Option<PathBuf>,
+    // 파일이 이 크기를 넘으면 새 파일로 바꾸고, 지난 파일은 max_files개까지 남긴다
+    pub max_file_bytes: u64,
+    pub max_files: usize,
+}
+
+impl Default for LogConfig {
+    fn default() -> Self {
+        LogConfig {
+            format: LogFormat::Combined,
+            file: None,
+            max_file_bytes: 10 * 1024 * 1024,
+            max_files: 5,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct StaticFilesConfig {
+    pub index_files: Vec<String>,
+    pub autoindex: bool,
+    pub precompressed: bool,
+    // mime.types 형식의 파일로 기본 표를 덮어쓴다
+    pub mime_types: Option<PathBuf>,
+    pub mime_sniff: bool,
+}
+
+impl Default for StaticFilesConfig {
+    fn default() -> Self {
+        StaticFilesConfig {
+            index_files: vec!["index.html".to_string()],
+            autoindex: false,
+            precompressed: false,
+            mime_types: None,
+            mime_sniff: true,
+        }
+    }
+}
+
+// 비워 두면 Compression의 기본값을 쓴다
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CompressionConfig {
+    pub min_size: Option<u64>,
+    pub content_types: Option<Vec<String>>,
+}
+
+// /api 아래 경로에 적용한다. allowed_origins가 비어 있으면 CORS 헤더를 보내지 않고,
+// 메서드와 요청 헤더를 비워 두면 Cors의 기본값을 쓴다.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CorsConfig {
+    // 정확한 오리진이나 *를 넣은 패턴(https://*.example.com). "*" 하나면 모든 오리진
+    pub allowed_origins: Vec<String>,
+    pub allowed_methods: Option<Vec<String>>,
+    pub allowed_headers: Option<Vec<String>>,
+    pub exposed_headers: Vec<String>,
+    pub allow_credentials: bool,
+    pub max_age_secs: Option<u64>,
+}
+
+impl CorsConfig {
+    pub fn enabled(&self) -> bool {
+        !self.allowed_origins.is_empty()
+    }
+}
+
+// listen이 비어 있으면 TLS를 쓰지 않는다
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TlsConfig {
+    pub listen: Vec<String>,
+    // SNI가 없거나 맞는 이름이 없을 때 쓰는 인증서
+    pub cert: Option<PathBuf>,
+    pub key: Option<PathBuf>,
+    // 평문 주소로 온 요청을 처리하지 않고 HTTPS로 보낸다
+    pub redirect_http: bool,
+    pub sni: Vec<SniConfig>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SniConfig {
+    pub names: Vec<String>,
+    pub cert: PathBuf,
+    pub key: PathBuf,
+}
+
+impl TlsConfig {
+    pub fn enabled(&self) -> bool {
+        !self.listen.is_empty()
+    }
+
+    // 기본 인증서를 맨 앞에 두어 SNI 선택기가 기본값으로 쓰게 한다
+    pub fn certificates(&self) -> Vec<TlsCertificate> {
+        let default = self
+            .cert
+            .iter()
+            .zip(&self.key)
+            .map(|(cert, key)| TlsCertificate {
+                names: Vec::new(),
+                cert_path: cert.clone(),
+                key_path: key.clone(),
+            });
+        let by_name = self.sni.iter().map(|sni| TlsCertificate {
+            names: sni.names.clone(),
+            cert_path: sni.cert.clone(),
+            key_path: sni.key.clone(),
+        });
+        default.chain(by_name).collect()
+    }
+
+    // 리다이렉트가 가리킬 HTTPS 포트. 첫 번째 TLS 주소의 포트를 쓴다.
+    pub fn redirect_port(&self) -> Option<u16> {
+        self.listen.first().and_then(|addr| port_of(addr))
+    }
+}
+
+impl Config {
+    // TOML 설정 파일을 읽는다. 파일 안의 상대 경로는 파일이 있는 디렉터리를 기준으로 한다.
+    pub fn load(path: &Path) -> Result<Config, ConfigError> {
+        let contents =
+            fs::read_to_string(path).map_err(|e| ConfigError::Read(path.to_path_buf(), e))?;
+        let mut config: Config =
+            toml::from_str(&contents).map_err(|e| ConfigError::Parse(path.to_path_buf(), e))?;
+        if let Some(base) = path.parent() {
+            config.resolve_paths(base);
+        }
+        Ok(config)
+    }
+
+    fn resolve_paths(&mut self, base: &Path) {
+        let resolve = |path: &mut PathBuf| {
+            if path.is_relative() {
+                *path = base.join(&*path);
+            }
+        };
+        resolve(&mut self.document_root);
+        resolve(&mut self.data_dir);
+        self.log.file.iter_mut().for_each(resolve);
+        self.static_files.mime_types.iter_mut().for_each(resolve);
+        self.tls.cert.iter_mut().for_each(resolve);
+        self.tls.key.iter_mut().for_each(resolve);
+        for sni in &mut self.tls.sni {
+            resolve(&mut sni.cert);
+            resolve(&mut sni.key);
+        }
+    }
+
+    // 함께 쓸 수 없는 값이나 범위를 벗어난 값을 서버를 띄우기 전에 찾는다
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        let invalid = |message: String| Err(ConfigError::Invalid(message));
+        if self.listen.is_empty() && !self.tls.enabled() {
+            return invalid("no listen address".to_string());
+        }
+        for addr in self.listen.iter().chain(&self.tls.listen) {
+            if port_of(addr).is_none() {
+                return invalid(format!("listen address {:?} must be host:port", addr));
+            }
+        }
+        if self.workers.threads == 0 {
+            return invalid("workers.threads must be at least 1".to_string());
+        }
+        let timeouts = &self.timeouts;
+        if timeouts.keep_alive_secs == 0 || timeouts.read_secs == 0 || timeouts.write_secs == 0 {
+            return invalid("timeouts must be at least 1 second".to_string());
+        }
+        if self.limits.max_request_line == 0 || self.limits.max_header_bytes == 0 {
+            return invalid(
+                "limits.max_request_line and limits.max_header_bytes must be positive".to_string(),
+            );
+        }
+        if self.log.file.is_some() && self.log.max_file_bytes == 0 {
+            return invalid("log.max_file_bytes must be positive".to_string());
+        }
+        if !self.data_dir.is_dir() {
+            return invalid(format!(
+                "data_dir {} is not a directory",
+                self.data_dir.display()
+            ));
+        }
+        if self.tls.enabled() && (self.tls.cert.is_none() || self.tls.key.is_none()) {
+            return invalid("tls.cert and tls.key are required for tls.listen".to_string());
+        }
+        if !self.tls.enabled() && (self.tls.redirect_http || !self.tls.sni.is_empty()) {
+            return invalid("tls.redirect_http and tls.sni need tls.listen".to_string());
+        }
+        if self.tls.redirect_http && self.listen.is_empty() {
+            return invalid("tls.redirect_http needs a plain listen address".to_string());
+        }
+        if self.tls.sni.iter().any(|sni| sni.names.is_empty()) {
+            return invalid("every tls.sni entry needs at least one name".to_string());
+        }
+        // 오리진은 scheme://host[:port] 꼴이다
+        let origins = &self.cors.allowed_origins;
+        if let Some(origin) = origins.iter().find(|o| *o != "*" && !o.contains("://")) {
+            return invalid(format!(
+                "cors.allowed_origins entry {:?} has no scheme",
+                origin
+            ));
+        }
+        // 아무 사이트나 사용자의 쿠키를 실어 API를 부를 수 있게 되므로 막는다
+        if self.cors.allow_credentials && origins.iter().any(|o| o == "*") {
+            return invalid("cors.allow_credentials cannot be used with \"*\" origins".to_string());
+        }
+        Ok(())
+    }
+}
+
+// 명령행 인자(프로그램 이름 제외)를 읽는다. --config로 준 파일을 먼저 읽고 나머지 인자로 덮어쓴다.
+pub fn parse_args(args: impl IntoIterator<Item = String>) -> Result<Command, ConfigError> {
+    let mut options = Vec::new();
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        let (flag, inline_value) = match arg.split_once('=') {
+            Some((flag, value)) if flag.starts_with("--") => (flag.to_string(), Some(value)),
+            _ => (arg.clone(), None),
+        };
+        match flag.as_str() {
+            "-h" | "--help" => return Ok(Command::Help),
+            "--https-redirect" => options.push((flag, String::new())),
+            "-c"
+            | "--config"
+            | "--listen"
+            | "--document-root"
+            | "--data-dir"
+            | "--workers"
+            | "--queue"
+            | "--worker-model"
+            | "--keep-alive-timeout"
+            | "--read-timeout"
+            | "--write-timeout"
+            | "--max-request-line"
+            | "--max-header-bytes"
+            | "--max-header-count"
+            | "--max-body-bytes"
+            | "--max-connections-per-ip"
+            | "--max-streams"
+            | "--log-format"
+            | "--log-file"
+            | "--tls-listen"
+            | "--tls-cert"
+            | "--tls-key" => {
+                let value = match inline_value {
+                    Some(value) => value.to_string(),
+                    None => args
+                        .next()
+                        .ok_or_else(|| ConfigError::Usage(format!("{} needs a value", flag)))?,
+                };
+                options.push((flag, value));
+            }
+            _ => return Err(ConfigError::Usage(format!("unknown option {}", arg))),
+        }
+    }
+
+    let mut config = match options
+        .iter()
+        .rfind(|(flag, _)| flag == "-c" || flag == "--config")
+    {
+        Some((_, path)) => Config::load(Path::new(path))?,
+        None => Config::default(),
+    };
+    // 여러 번 줄 수 있는 주소는 명령행에 하나라도 있으면 파일의 목록을 대신한다
+    let mut listen = Vec::new();
+    let mut tls_listen = Vec::new();
+    for (flag, value) in options {
+        match flag.as_str() {
+            "--listen" => listen.push(value),
+            "--document-root" => config.document_root = PathBuf::from(value),
+            "--data-dir" => config.data_dir = PathBuf::from(value),
+            "--workers" => config.workers.threads = parse_value(&flag, &value)?,
+            "--queue" => config.workers.queue = parse_value(&flag, &value)?,
+            "--worker-model" => config.workers.model = parse_enum(&flag, &value)?,
+            "--keep-alive-timeout" => config.timeouts.keep_alive_secs = parse_value(&flag, &value)?,
+            "--read-timeout" => config.timeouts.read_secs = parse_value(&flag, &value)?,
+            "--write-timeout" => config.timeouts.write_secs = parse_value(&flag, &value)?,
+            "--max-request-line" => config.limits.max_request_line = parse_value(&flag, &value)?,
+            "--max-header-bytes" => config.limits.max_header_bytes = parse_value(&flag, &value)?,
+            "--max-header-count" => config.limits.max_header_count = parse_value(&flag, &value)?,
+            "--max-body-bytes" => config.limits.max_body_bytes = parse_value(&flag, &value)?,
+            "--max-connections-per-ip" => {
+                config.limits.max_connections_per_ip = parse_value(&flag, &value)?
+            }
+            "--max-streams" => config.limits.max_streams = parse_value(&flag, &value)?,
+            "--log-format" => config.log.format = parse_enum(&flag, &value)?,
+            "--log-file" => config.log.file = Some(PathBuf::from(value)),
+            "--tls-listen" => tls_listen.push(value),
+            "--tls-cert" => config.tls.cert = Some(PathBuf::from(value)),
+            "--tls-key" => config.tls.key = Some(PathBuf::from(value)),
+            "--https-redirect" => config.tls.redirect_http = true,
+            _ => {}
+        }
+    }
+    if !listen.is_empty() {
+        config.listen = listen;
+    }
+    if !tls_listen.is_empty() {
+        config.tls.listen = tls_listen;
+    }
+    Ok(Command::Run(Box::new(config)))
+}
+
+fn parse_value<T: FromStr>(flag: &str, value: &str) -> Result<T, ConfigError> {
+    value
+        .parse()
+        .map_err(|_| ConfigError::Usage(format!("invalid value {:?} for {}", value, flag)))
+}
+
+// 설정 파일과 같은 이름(예: "async", "json")으로 열거형 값을 읽는다
+fn parse_enum<T: DeserializeOwned>(flag: &str, value: &str) -> Result<T, ConfigError> {
+    let deserializer: StrDeserializer<serde::de::value::Error> = value.into_deserializer();
+    T::deserialize(deserializer)
+        .map_err(|_| ConfigError::Usage(format!("invalid value {:?} for {}", value, flag)))
+}
+
+fn port_of(addr: &str) -> Option<u16> {
+    let (host, port) = addr.rsplit_once(':')?;
+    if host.is_empty() {
+        return None;
+    }
+    port.parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+
+    fn args(line: &str) -> Vec<String> {
+        line.split_whitespace().map(String::from).collect()
+    }
+
+    fn run_config(line: &str) -> Config {
+        match parse_args(args(line)) {
+            Ok(Command::Run(config)) => *config,
+            Ok(Command::Help) => panic!("unexpected --help"),
+            Err(e) => panic!("{}", e),
+        }
+    }
+
+    #[test]
+    fn test_load_file_with_cli_overrides() {
+        let dir = env::temp_dir().join(format!("config-test-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("server.toml");
+        fs::write(
+            &path,
+            r#"
+listen = ["0.0.0.0:8080", "[::]:8080"]
+document_root = "site"
+
+[workers]
+threads = 2
+model = "async"
+
+[log]
+format = "json"
+file = "logs/access.log"
+
+[tls]
+listen = ["0.0.0.0:8443"]
+cert = "certs/default.pem"
+key = "/etc/keys/default.pem"
+redirect_http = true
+
+[[tls.sni]]
+names = ["api.example.com"]
+cert = "certs/api.pem"
+key = "certs/api.key"
+
+[cors]
+allowed_origins = ["https://app.example.com", "http://localhost:*"]
+exposed_headers = ["Location"]
+max_age_secs = 600
+"#,
+        )
+        .unwrap();
+
+        let config = run_config(&format!(
+            "--config {} --workers=4 --max-body-bytes 10 --max-streams 16",
+            path.display()
+        ));
+        assert_eq!(vec!["0.0.0.0:8080", "[::]:8080"], config.listen);
+        assert_eq!(dir.join("site"), config.document_root);
+        assert_eq!(4, config.workers.threads);
+        assert_eq!(64, config.workers.queue);
+        assert_eq!(WorkerModel::Async, config.workers.model);
+        assert_eq!(LogFormat::Json, config.log.format);
+        assert_eq!(Some(dir.join("logs/access.log")), config.log.file);
+        assert_eq!(10, config.limits.max_body_bytes);
+        assert_eq!(16, config.limits.max_streams);
+        assert_eq!(Some(8443), config.tls.redirect_port());
+        let certificates = config.tls.certificates();
+        assert_eq!(2, certificates.len());
+        assert_eq!(dir.join("certs/default.pem"), certificates[0].cert_path);
+        assert_eq!(
+            PathBuf::from("/etc/keys/default.pem"),
+            certificates[0].key_path
+        );
+        assert_eq!(vec!["api.example.com"], certificates[1].names);
+        assert!(config.cors.enabled());
+        assert_eq!(None, config.cors.allowed_methods);
+        assert_eq!(Some(600), config.cors.max_age_secs);
+
+        fs::write(&path, "[workers]\nthread = 2\n").unwrap();
+        let err = Config::load(&path).unwrap_err();
+        assert!(matches!(err, ConfigError::Parse(..)));
+        assert!(err.to_string().contains("thread"));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_cli_errors() {
+        assert!(matches!(parse_args(args("--help")), Ok(Command::Help)));
+        for line in [
+            "--workers",
+            "--workers many",
+            "--worker-model fibers",
+            "--log-format text",
+            "--verbose",
+        ] {
+            assert!(
+                matches!(parse_args(args(line)), Err(ConfigError::Usage(_))),
+                "{}",
+                line
+            );
+        }
+        assert!(matches!(
+            parse_args(args("--config /nonexistent/server.toml")),
+            Err(ConfigError::Read(..))
+        ));
+    }
+
+    #[test]
+    fn test_validation() {
+        let config = run_config("--listen 127.0.0.1:0 --tls-listen localhost:3443");
+        assert_eq!(vec!["127.0.0.1:0"], config.listen);
+        let err = config.validate().unwrap_err();
+        assert!(err.to_string().contains("tls.cert"));
+
+        assert!(run_config("").validate().is_ok());
+        for line in [
+            "--listen localhost",
+            "--workers 0",
+            "--keep-alive-timeout 0",
+            "--read-timeout 0",
+            "--max-request-line 0",
+            "--https-redirect",
+            "--data-dir /nonexistent",
+        ] {
+            assert!(
+                matches!(run_config(line).validate(), Err(ConfigError::Invalid(_))),
+                "{}",
+                line
+            );
+        }
+
+        let mut config = run_config("");
+        config.cors.allowed_origins = vec!["app.example.com".to_string()];
+        assert!(matches!(config.validate(), Err(ConfigError::Invalid(_))));
+        config.cors.allowed_origins = vec!["*".to_string()];
+        assert!(config.validate().is_ok());
+        config.cors.allow_credentials = true;
+        assert!(matches!(config.validate(), Err(ConfigError::Invalid(_))));
+    }
+}
@@ -0,0 +1,146 @@
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::sync::{Arc, Condvar, Mutex};
+
+// 클라이언트 IP마다 동시에 열어 둘 수 있는 커넥션 수를 센다.
+// 한 클라이언트가 커넥션을 잔뜩 열어 worker를 모두 차지하지 못하게 한다.
+pub struct IpConnectionLimiter {
+    max_per_ip: usize,
+    counts: Mutex<HashMap<IpAddr, usize>>,
+}
+
+// 커넥션 하나가 차지한 자리. 커넥션을 다 처리하고 버리면 자리를 돌려준다.
+pub struct IpSlot {
+    limiter: Arc<IpConnectionLimiter>,
+    ip: IpAddr,
+}
+
+impl IpConnectionLimiter {
+    // max_per_ip가 0이면 제한하지 않는다
+    pub fn new(max_per_ip: usize) -> Arc<Self> {
+        Arc::new(IpConnectionLimiter {
+            max_per_ip,
+            counts: Mutex::new(HashMap::new()),
+        })
+    }
+
+    pub fn try_acquire(self: &Arc<Self>, ip: IpAddr) -> Option<IpSlot> {
+        let mut counts = self.counts.lock().unwrap();
+        let count = counts.entry(ip).or_insert(0);
+        if self.max_per_ip > 0 && *count >= self.max_per_ip {
+            return None;
+        }
+        *count += 1;
+        Some(IpSlot {
+            limiter: Arc::clone(self),
+            ip,
+        })
+    }
+}
+
+impl Drop for IpSlot {
+    fn drop(&mut self) {
+        let mut counts = self.limiter.counts.lock().unwrap();
+        if let Some(count) = counts.get_mut(&self.ip) {
+            *count -= 1;
+            // 커넥션이 없는 IP는 지워서 표가 계속 커지지 않게 한다
+            if *count == 0 {
+                counts.remove(&self.ip);
+            }
+        }
+    }
+}
+
+// 응답 헤더를 보낸 뒤 커넥션을 넘겨받은 스트림(웹소켓, 이벤트 스트림)의 수를 센다.
+// 이런 커넥션은 worker를 돌려주고 자기 스레드에서 오래 열려 있으므로, 스레드가 한없이 늘지 않게 상한을 둔다.
+pub struct StreamLimiter {
+    max_streams: usize,
+    open: Mutex<usize>,
+    closed: Condvar,
+}
+
+// 스트림 하나가 차지한 자리. 스트림을 닫고 버리면 자리를 돌려준다.
+pub struct StreamSlot {
+    limiter: Arc<StreamLimiter>,
+}
+
+impl StreamLimiter {
+    // max_streams가 0이면 제한하지 않는다
+    pub fn new(max_streams: usize) -> Arc<Self> {
+        Arc::new(StreamLimiter {
+            max_streams,
+            open: Mutex::new(0),
+            closed: Condvar::new(),
+        })
+    }
+
+    pub fn try_acquire(self: &Arc<Self>) -> Option<StreamSlot> {
+        let mut open = self.open.lock().unwrap();
+        if self.max_streams > 0 && *open >= self.max_streams {
+            return None;
+        }
+        *open += 1;
+        Some(StreamSlot {
+            limiter: Arc::clone(self),
+        })
+    }
+
+    // 열린 스트림이 모두 닫힐 때까지 기다린다. 종료할 때 쓴다.
+    pub fn wait_idle(&self) {
+        let mut open = self.open.lock().unwrap();
+        while *open > 0 {
+            open = self.closed.wait(open).unwrap();
+        }
+    }
+}
+
+impl Drop for StreamSlot {
+    fn drop(&mut self) {
+        let mut open = self.limiter.open.lock().unwrap();
+        *open -= 1;
+        if *open == 0 {
+            self.limiter.closed.notify_all();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_slots_are_counted_per_ip() {
+        let limiter = IpConnectionLimiter::new(2);
+        let a: IpAddr = "10.0.0.1".parse().unwrap();
+        let b: IpAddr = "10.0.0.2".parse().unwrap();
+        let first = limiter.try_acquire(a).unwrap();
+        let _second = limiter.try_acquire(a).unwrap();
+        assert!(limiter.try_acquire(a).is_none());
+        assert!(limiter.try_acquire(b).is_some());
+
+        drop(first);
+        assert!(limiter.try_acquire(a).is_some());
+
+        let unlimited = IpConnectionLimiter::new(0);
+        let slots: Vec<_> = (0..10).map(|_| unlimited.try_acquire(a)).collect();
+        assert!(slots.iter().all(Option::is_some));
+        drop(slots);
+        assert!(unlimited.counts.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_stream_slots_are_capped_and_drained() {
+        let limiter = StreamLimiter::new(2);
+        let first = limiter.try_acquire().unwrap();
+        let second = limiter.try_acquire().unwrap();
+        assert!(limiter.try_acquire().is_none());
+        drop(first);
+        let third = limiter.try_acquire().unwrap();
+
+        // 남은 스트림이 다른 스레드에서 닫히면 wait_idle이 돌아온다
+        let closer = std::thread::spawn(move || drop((second, third)));
+        limiter.wait_idle();
+        closer.join().unwrap();
+        assert_eq!(0, *limiter.open.lock().unwrap());
+    }
+}
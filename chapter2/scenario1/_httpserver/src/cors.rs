@@ -0,0 +1,369 @@
+use super::compression::add_vary;
+use super::middleware::{Middleware, Next};
+use http::{
+    httprequest::{HttpRequest, Method},
+    httpresponse::HttpResponse,
+    status::StatusCode,
+};
+use std::time::Duration;
+
+const DEFAULT_METHODS: &[&str] = &["GET", "HEAD", "POST", "PUT", "PATCH", "DELETE"];
+// JSON 본문을 보내는 요청은 Content-Type 때문에 preflight를 거친다
+const DEFAULT_HEADERS: &[&str] = &["Content-Type"];
+
+// 다른 오리진에서 온 브라우저 스크립트가 응답을 읽을 수 있게 CORS 헤더를 붙인다 (Fetch Standard 3.2).
+// prefix 아래 경로에만 적용하며, preflight 요청(OPTIONS + Access-Control-Request-Method)에는
+// 라우터까지 가지 않고 바로 답한다. 허용하지 않은 오리진의 요청도 처리는 하지만 CORS 헤더를 붙이지 않으므로
+// 브라우저가 스크립트에 응답을 넘기지 않는다.
+pub struct Cors {
+    prefix: Vec<String>,
+    origins: Vec<String>,
+    methods: Vec<String>,
+    headers: Vec<String>,
+    expose_headers: Vec<String>,
+    credentials: bool,
+    max_age: Option<Duration>,
+}
+
+impl Default for Cors {
+    fn default() -> Self {
+        Cors {
+            prefix: Vec::new(),
+            origins: Vec::new(),
+            methods: DEFAULT_METHODS.iter().map(|m| m.to_string()).collect(),
+            headers: DEFAULT_HEADERS.iter().map(|h| h.to_string()).collect(),
+            expose_headers: Vec::new(),
+            credentials: false,
+            max_age: None,
+        }
+    }
+}
+
+impl Cors {
+    pub fn new() -> Self {
+        Cors::default()
+    }
+
+    // 이 경로와 그 아래 경로에만 적용한다 (예: "/api"는 /api, /api/a에 맞고 /apis에는 맞지 않는다)
+    pub fn prefix(mut self, prefix: &str) -> Self {
+        self.prefix = prefix
+            .split('/')
+            .filter(|s| !s.is_empty())
+            .map(String::from)
+            .collect();
+        self
+    }
+
+    // 허용할 오리진. 정확한 오리진(https://app.example.com)이나 *를 넣은 패턴(https://*.example.com,
+    // http://localhost:*)을 쓰며, "*" 하나면 모든 오리진을 허용한다.
+    pub fn allow_origins(mut self, origins: Vec<String>) -> Self {
+        self.origins = origins;
+        self
+    }
+
+    pub fn allow_methods(mut self, methods: Vec<String>) -> Self {
+        self.methods = methods;
+        self
+    }
+
+    // preflight에서 허용할 요청 헤더. "*"를 넣으면 요청한 헤더를 모두 허용한다.
+    pub fn allow_headers(mut self, headers: Vec<String>) -> Self {
+        self.headers = headers;
+        self
+    }
+
+    // 스크립트가 읽을 수 있게 할 응답 헤더 (예: 새 주문의 Location)
+    pub fn expose_headers(mut self, headers: Vec<String>) -> Self {
+        self.expose_headers = headers;
+        self
+    }
+
+    // 쿠키나 인증 헤더를 실은 요청의 응답도 읽게 한다. 이때는 "*" 대신 요청한 오리진을 돌려준다.
+    pub fn allow_credentials(mut self, credentials: bool) -> Self {
+        self.credentials = credentials;
+        self
+    }
+
+    // 브라우저가 preflight 결과를 캐시할 시간
+    pub fn max_age(mut self, max_age: Duration) -> Self {
+        self.max_age = Some(max_age);
+        self
+    }
+
+    fn any_origin(&self) -> bool {
+        self.origins.iter().any(|o| o == "*")
+    }
+
+    fn allows_origin(&self, origin: &str) -> bool {
+        let origin = origin.to_ascii_lowercase();
+        self.origins.iter().any(|pattern| {
+            pattern == "*" || glob(pattern.to_ascii_lowercase().as_bytes(), origin.as_bytes())
+        })
+    }
+
+    // 요청한 메서드와 헤더를 모두 허용하면 204로, 아니면 403으로 답한다
+    fn preflight(&self, req: &HttpRequest) -> HttpResponse {
+        let method = req
+            .headers
+            .get("Access-Control-Request-Method")
+            .unwrap_or("");
+        let requested: Vec<&str> = req
+            .headers
+            .get_all("Access-Control-Request-Headers")
+            .into_iter()
+            .flat_map(|v| v.split(','))
+            .map(str::trim)
+            .filter(|h| !h.is_empty())
+            .collect();
+        let any_header = self.headers.iter().any(|h| h == "*");
+        let allowed = self.methods.iter().any(|m| m == method)
+            && (any_header
+                || requested
+                    .iter()
+                    .all(|h| self.headers.iter().any(|a| a.eq_ignore_ascii_case(h))));
+        if !allowed {
+            return HttpResponse::new(StatusCode::Forbidden, None, None);
+        }
+
+        let mut resp = HttpResponse::new(StatusCode::NoContent, None, None);
+        let headers = resp.headers_mut();
+        headers.insert("Access-Control-Allow-Methods", self.methods.join(", "));
+        let allow_headers = if any_header {
+            requested.join(", ")
+        } else {
+            self.headers.join(", ")
+        };
+        if !allow_headers.is_empty() {
+            headers.insert("Access-Control-Allow-Headers", allow_headers);
+        }
+        if let Some(max_age) = self.max_age {
+            headers.insert("Access-Control-Max-Age", max_age.as_secs().to_string());
+        }
+        resp
+    }
+}
+
+impl Middleware for Cors {
+    fn handle(&self, req: &HttpRequest, next: Next<'_>) -> HttpResponse {
+        if !req.resource.segments().starts_with(&self.prefix) {
+            return next.run(req);
+        }
+        let origin = req.headers.get("Origin");
+        let preflight = req.method == Method::Options
+            && origin.is_some()
+            && req.headers.contains("Access-Control-Request-Method");
+        let mut resp = match origin {
+            Some(origin) if preflight && self.allows_origin(origin) => self.preflight(req),
+            // 허용하지 않은 오리진의 preflight는 라우터의 OPTIONS 응답을 받는다
+            _ => next.run(req),
+        };
+
+        if let Some(origin) = origin.filter(|o| self.allows_origin(o)) {
+            let headers = resp.headers_mut();
+            if self.any_origin() && !self.credentials {
+                headers.insert("Access-Control-Allow-Origin", "*");
+            } else {
+                headers.insert("Access-Control-Allow-Origin", origin);
+            }
+            if self.credentials {
+                headers.insert("Access-Control-Allow-Credentials", "true");
+            }
+            if !preflight && !self.expose_headers.is_empty() {
+                headers.insert(
+                    "Access-Control-Expose-Headers",
+                    self.expose_headers.join(", "),
+                );
+            }
+        }
+        // 오리진마다 응답 헤더가 달라지면 캐시가 다른 오리진의 응답을 내주지 않게 한다
+        if !self.any_origin() || self.credentials {
+            add_vary(&mut resp, "Origin");
+        }
+        resp
+    }
+}
+
+// 패턴의 *는 '/'가 없는 아무 글자열에 맞는다
+fn glob(pattern: &[u8], text: &[u8]) -> bool {
+    match pattern.split_first() {
+        None => text.is_empty(),
+        Some((b'*', rest)) => (0..=text.len())
+            .take_while(|&i| i == 0 || text[i - 1] != b'/')
+            .any(|i| glob(rest, &text[i..])),
+        Some((c, rest)) => text.first() == Some(c) && glob(rest, &text[1..]),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::router::{Params, Router};
+
+    fn request(method: &str, target: &str, headers: &str) -> HttpRequest {
+        HttpRequest::try_from(format!(
+            "{} {} HTTP/1.1\r\nHost: localhost\r\n{}\r\n",
+            method, target, headers
+        ))
+        .unwrap()
+    }
+
+    fn ok(_req: &HttpRequest, _params: &Params) -> HttpResponse {
+        HttpResponse::new(StatusCode::Ok, None, Some("ok".into()))
+    }
+
+    fn api_router(cors: Cors) -> Router {
+        Router::new()
+            .wrap(cors.prefix("/api"))
+            .get("/api/orders", ok)
+            .post("/api/orders", ok)
+            .get("/public", ok)
+    }
+
+    #[test]
+    fn test_origin_patterns() {
+        let cors = Cors::new().allow_origins(vec![
+            "https://app.example.com".to_string(),
+            "https://*.example.org".to_string(),
+            "http://localhost:*".to_string(),
+        ]);
+        for origin in [
+            "https://app.example.com",
+            "HTTPS://APP.EXAMPLE.COM",
+            "https://a.example.org",
+            "https://a.b.example.org",
+            "http://localhost:5173",
+        ] {
+            assert!(cors.allows_origin(origin), "{}", origin);
+        }
+        for origin in [
+            "http://app.example.com",
+            "https://app.example.com.evil.com",
+            "https://example.org",
+            "https://evil.com/.example.org",
+            "http://localhost.evil.com",
+            "null",
+        ] {
+            assert!(!cors.allows_origin(origin), "{}", origin);
+        }
+        assert!(Cors::new()
+            .allow_origins(vec!["*".to_string()])
+            .allows_origin("null"));
+    }
+
+    #[test]
+    fn test_preflight() {
+        let router = api_router(
+            Cors::new()
+                .allow_origins(vec!["https://app.example.com".to_string()])
+                .allow_credentials(true)
+                .max_age(Duration::from_secs(600)),
+        );
+        let preflight = |origin: &str, method: &str, headers: &str| {
+            router.dispatch(&request(
+                "OPTIONS",
+                "/api/orders",
+                &format!(
+                    "Origin: {}\r\nAccess-Control-Request-Method: {}\r\n{}",
+                    origin, method, headers
+                ),
+            ))
+        };
+
+        let resp = preflight(
+            "https://app.example.com",
+            "PUT",
+            "Access-Control-Request-Headers: content-type\r\n",
+        );
+        assert_eq!(StatusCode::NoContent, resp.status());
+        let headers = resp.headers();
+        assert_eq!(
+            Some("https://app.example.com"),
+            headers.get("Access-Control-Allow-Origin")
+        );
+        assert_eq!(
+            Some("true"),
+            headers.get("Access-Control-Allow-Credentials")
+        );
+        assert_eq!(
+            Some("GET, HEAD, POST, PUT, PATCH, DELETE"),
+            headers.get("Access-Control-Allow-Methods")
+        );
+        assert_eq!(
+            Some("Content-Type"),
+            headers.get("Access-Control-Allow-Headers")
+        );
+        assert_eq!(Some("600"), headers.get("Access-Control-Max-Age"));
+        assert_eq!(Some("Origin"), headers.get("Vary"));
+
+        // 허용하지 않은 메서드나 헤더는 거절한다
+        assert_eq!(
+            StatusCode::Forbidden,
+            preflight("https://app.example.com", "TRACE", "").status()
+        );
+        assert_eq!(
+            StatusCode::Forbidden,
+            preflight(
+                "https://app.example.com",
+                "POST",
+                "Access-Control-Request-Headers: content-type, x-secret\r\n"
+            )
+            .status()
+        );
+
+        // 허용하지 않은 오리진과 /api 밖의 경로는 라우터가 OPTIONS에 답한다
+        let resp = preflight("https://evil.com", "PUT", "");
+        assert_eq!(StatusCode::Ok, resp.status());
+        assert_eq!(None, resp.headers().get("Access-Control-Allow-Origin"));
+        assert!(resp.headers().contains("Allow"));
+        let resp = router.dispatch(&request(
+            "OPTIONS",
+            "/public",
+            "Origin: https://app.example.com\r\nAccess-Control-Request-Method: GET\r\n",
+        ));
+        assert_eq!(StatusCode::Ok, resp.status());
+        assert_eq!(None, resp.headers().get("Access-Control-Allow-Origin"));
+    }
+
+    #[test]
+    fn test_actual_requests() {
+        let router = api_router(
+            Cors::new()
+                .allow_origins(vec!["https://*.example.com".to_string()])
+                .expose_headers(vec!["Location".to_string()]),
+        );
+        let resp = router.dispatch(&request(
+            "POST",
+            "/api/orders",
+            "Origin: https://app.example.com\r\nContent-Length: 0\r\n",
+        ));
+        assert_eq!(StatusCode::Ok, resp.status());
+        let headers = resp.headers();
+        assert_eq!(
+            Some("https://app.example.com"),
+            headers.get("Access-Control-Allow-Origin")
+        );
+        assert_eq!(None, headers.get("Access-Control-Allow-Credentials"));
+        assert_eq!(
+            Some("Location"),
+            headers.get("Access-Control-Expose-Headers")
+        );
+
+        // 오리진이 없거나 허용하지 않은 요청도 처리하되 Vary만 남긴다
+        for extra in ["", "Origin: https://evil.com\r\n"] {
+            let resp = router.dispatch(&request("GET", "/api/orders", extra));
+            assert_eq!(StatusCode::Ok, resp.status());
+            assert_eq!(None, resp.headers().get("Access-Control-Allow-Origin"));
+            assert_eq!(Some("Origin"), resp.headers().get("Vary"));
+        }
+
+        // 모든 오리진을 허용하고 자격 증명을 받지 않으면 *로 답하며 캐시를 나눌 필요가 없다
+        let router = api_router(Cors::new().allow_origins(vec!["*".to_string()]));
+        let resp = router.dispatch(&request(
+            "GET",
+            "/api/orders",
+            "Origin: https://app.example.com\r\n",
+        ));
+        assert_eq!(Some("*"), resp.headers().get("Access-Control-Allow-Origin"));
+        assert_eq!(None, resp.headers().get("Vary"));
+    }
+}
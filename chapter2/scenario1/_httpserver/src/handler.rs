@@ -1,90 +1,667 @@
-use http::{httprequest::HttpRequest, httpresponse::HttpResponse};
-use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
-use std::env;
-use std::fs;
+use super::compression::{add_vary, negotiate, Coding};
+use super::mime::MimeRegistry;
+use super::order_store::{Changes, OrderEvent, OrderState, OrderStatus, OrderStore, StoreError};
+use super::router::Params;
+use super::sse::{Event, EventStream, EventStreamHandler};
+use super::static_file::{self, Resolved, StaticRoot};
+use super::websocket::{Message, WebSocket, WebSocketHandler, CLOSE_INTERNAL_ERROR};
+use http::{
+    headers::HeaderMap,
+    httprequest::{HttpRequest, Method},
+    httpresponse::HttpResponse,
+    status::StatusCode,
+};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::fs::{self, File};
+use std::io;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
 
-pub trait Handler {
-    fn handle(req: &HttpRequest) -> HttpResponse;
-    fn load_file(file_name: &str) -> Option<String> {
-        let default_path = format!("{}/public", env!("CARGO_MANIFEST_DIR"));
-        let public_path = env::var("PUBLIC_PATH").unwrap_or(default_path);
-        let full_path = format!("{}/{}", public_path, file_name);
+// 요청 하나를 처리해 응답을 만든다. 핸들러는 인스턴스로 등록되므로 설정을 필드로 들고 있을 수 있다.
+// 여러 worker 스레드가 같은 핸들러를 함께 쓰므로 Send + Sync여야 한다.
+pub trait Handler: Send + Sync {
+    fn handle(&self, req: &HttpRequest, params: &Params) -> HttpResponse;
+}
 
-        let contents = fs::read_to_string(full_path);
-        contents.ok()
+// 간단한 핸들러는 함수나 클로저로 등록할 수 있다
+impl<F> Handler for F
+where
+    F: Fn(&HttpRequest, &Params) -> HttpResponse + Send + Sync,
+{
+    fn handle(&self, req: &HttpRequest, params: &Params) -> HttpResponse {
+        self(req, params)
     }
 }
 
-#[derive(Serialize, Deserialize)]
-pub struct OrderStatus {
-    order_id: i32, 
-    order_data: String,
-    order_status: String,
+fn load_file(public_path: &str, file_name: &str) -> Option<String> {
+    let contents = fs::read_to_string(format!("{}/{}", public_path, file_name));
+    contents.ok()
 }
 
-pub struct StaticPageHandler;
-impl Handler for StaticPageHandler {
-    fn handle(req: &HttpRequest) -> HttpResponse {
-        // 요청 받은 정적 페이지의 경로를 얻는다
-        let http::httprequest::Resource::Path(s) = &req.resource;
-
-        // URI를 파싱한다
-        let route: Vec<&str> = s.split("/").collect();
-        match route[1] {
-            "" => HttpResponse::new("200", None, Self::load_file("index.html")),
-            "health" => HttpResponse::new("200", None, Self::load_file("health.html")),
-            path => match Self::load_file(path) {
-                Some(content) => {
-                    let mut map: HashMap<&str, &str> = HashMap::new();
-                    if path.ends_with(".css") {
-                        map.insert("Content-Type", "text/css");
-                    } else if path.ends_with(".js") {
-                        map.insert("Content-Type", "application/javascript");
-                    } else {
-                        map.insert("Content-Type", "text/html");
-                    }
-                    HttpResponse::new("200", Some(map), Some(content))
-                },
-                None => HttpResponse::new("404", None, Self::load_file("404.html"))
+// 공개 디렉터리의 파일을 내보낸다. 디렉터리를 요청하면 인덱스 파일을 찾고,
+// 없으면 설정에 따라 목록을 만들어 보여 주거나 거절한다.
+pub struct StaticPageHandler {
+    root: StaticRoot,
+    mime: MimeRegistry,
+    index_files: Vec<String>,
+    autoindex: bool,
+    precompressed: bool,
+}
+impl StaticPageHandler {
+    pub fn new(root: StaticRoot, mime: MimeRegistry) -> Self {
+        StaticPageHandler {
+            root,
+            mime,
+            index_files: vec!["index.html".to_string()],
+            autoindex: false,
+            precompressed: false,
+        }
+    }
+
+    // 디렉터리를 요청했을 때 차례로 찾아볼 파일 이름
+    pub fn index_files(mut self, index_files: Vec<String>) -> Self {
+        self.index_files = index_files;
+        self
+    }
+
+    // 인덱스 파일이 없는 디렉터리의 목록을 만들어 보여 줄지 정한다
+    pub fn autoindex(mut self, autoindex: bool) -> Self {
+        self.autoindex = autoindex;
+        self
+    }
+
+    // 파일 옆에 미리 압축해 둔 .br/.gz 파일이 있으면 클라이언트가 받아 주는 것을 대신 보낸다
+    pub fn precompressed(mut self, precompressed: bool) -> Self {
+        self.precompressed = precompressed;
+        self
+    }
+
+    // 파일을 메모리로 읽지 않고 파일 핸들을 본문으로 넘겨 응답을 보낼 때 조금씩 읽게 한다
+    fn serve_path(&self, req: &HttpRequest, path: &Path) -> HttpResponse {
+        let mut file = match File::open(path) {
+            Ok(file) => file,
+            Err(_) => return self.not_found(),
+        };
+        let file_name = path.file_name().unwrap_or_default().to_string_lossy();
+        let content_type = self.mime.content_type(&file_name, &mut file);
+        if !self.precompressed {
+            return static_file::serve_file(req, file, &content_type);
+        }
+
+        let accept_encoding = req.headers.get("Accept-Encoding");
+        let encoded = [Coding::Brotli, Coding::Gzip]
+            .into_iter()
+            .filter_map(|coding| Some((coding, self.root.sibling(path, coding.extension())?)))
+            .collect::<Vec<_>>();
+        let available: Vec<Coding> = encoded.iter().map(|(coding, _)| *coding).collect();
+        let chosen = negotiate(accept_encoding, &available)
+            .and_then(|coding| encoded.into_iter().find(|(c, _)| *c == coding))
+            .and_then(|(coding, path)| Some((coding, File::open(path).ok()?)));
+        let mut resp = match chosen {
+            Some((coding, encoded_file)) => {
+                let mut resp = static_file::serve_file(req, encoded_file, &content_type);
+                if resp.status() != StatusCode::RangeNotSatisfiable {
+                    resp.headers_mut()
+                        .insert("Content-Encoding", coding.as_str());
+                }
+                resp
             }
+            None => static_file::serve_file(req, file, &content_type),
+        };
+        // 같은 URL이 Accept-Encoding에 따라 다른 표현을 돌려주므로 캐시에 알린다
+        if !available.is_empty() {
+            add_vary(&mut resp, "Accept-Encoding");
+        }
+        resp
+    }
+
+    fn serve_directory(&self, req: &HttpRequest, path: &str, dir: &Path) -> HttpResponse {
+        // 상대 링크가 디렉터리 안을 가리키도록 '/'로 끝나는 URL로 보낸다
+        if !path.is_empty() && !path.ends_with('/') {
+            let target = req.resource.to_string();
+            let location = match target.split_once('?') {
+                Some((path, query)) => format!("{}/?{}", path, query),
+                None => format!("{}/", target),
+            };
+            let mut headers = HeaderMap::new();
+            headers.insert("Location", location);
+            return HttpResponse::new(StatusCode::MovedPermanently, Some(headers), None);
+        }
+
+        for index in &self.index_files {
+            if let Resolved::File(index_path) = self.root.resolve(&format!("{}{}", path, index)) {
+                return self.serve_path(req, &index_path);
+            }
+        }
+        if !self.autoindex {
+            return forbidden();
+        }
+        match static_file::directory_listing(dir, req.resource.path()) {
+            Ok(html) => {
+                let mut headers = HeaderMap::new();
+                headers.insert("Content-Type", "text/html; charset=utf-8");
+                HttpResponse::new(StatusCode::Ok, Some(headers), Some(html))
+            }
+            Err(e) => {
+                println!("Failed to list {}: {}", dir.display(), e);
+                HttpResponse::new(StatusCode::InternalServerError, None, None)
+            }
+        }
+    }
+
+    fn not_found(&self) -> HttpResponse {
+        let public_path = self.root.path().to_string_lossy();
+        HttpResponse::new(
+            StatusCode::NotFound,
+            None,
+            load_file(&public_path, "404.html"),
+        )
+    }
+}
+impl Handler for StaticPageHandler {
+    fn handle(&self, req: &HttpRequest, params: &Params) -> HttpResponse {
+        // 라우트 패턴이 캡처한 정적 페이지의 경로를 얻는다
+        let path = match params.get("path").unwrap_or("") {
+            "health" => "health.html",
+            path => path,
+        };
+        match self.root.resolve(path) {
+            Resolved::File(file_path) => self.serve_path(req, &file_path),
+            Resolved::Directory(dir) => self.serve_directory(req, path, &dir),
+            Resolved::NotFound => self.not_found(),
+            Resolved::Forbidden => forbidden(),
         }
     }
 }
 
-pub struct PageNotFoundHandler;
+fn forbidden() -> HttpResponse {
+    HttpResponse::new(StatusCode::Forbidden, None, Some("Forbidden".into()))
+}
+
+pub struct PageNotFoundHandler {
+    public_path: String,
+}
+impl PageNotFoundHandler {
+    pub fn new(public_path: impl Into<String>) -> Self {
+        PageNotFoundHandler {
+            public_path: public_path.into(),
+        }
+    }
+}
 impl Handler for PageNotFoundHandler {
-    fn handle(req: &HttpRequest) -> HttpResponse {
-        HttpResponse::new("404", None, Self::load_file("404.html"))
+    fn handle(&self, _req: &HttpRequest, _params: &Params) -> HttpResponse {
+        HttpResponse::new(
+            StatusCode::NotFound,
+            None,
+            load_file(&self.public_path, "404.html"),
+        )
     }
 }
 
-pub struct WebServiceHandler;
+// /api/shipping/orders 자원. 주문 목록과 주문 하나를 조회하고, 만들고, 고치고, 지운다.
+// 같은 저장소를 메서드별 라우트에 나눠 등록할 수 있도록 복제할 수 있다.
+#[derive(Clone)]
+pub struct WebServiceHandler {
+    store: Arc<OrderStore>,
+}
+
+// POST와 PUT의 본문. POST에서 order_id를 빼면 다음 번호를 붙인다.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct OrderInput {
+    order_id: Option<i32>,
+    order_date: String,
+    order_status: OrderState,
+}
+
+// PATCH의 본문. 준 필드만 바꾼다.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct OrderPatch {
+    order_date: Option<String>,
+    order_status: Option<OrderState>,
+}
+
 impl WebServiceHandler {
-    fn load_json() -> Vec<OrderStatus> {
-        let default_path = format!("{}/data", env!("CARGO_MANIFEST_DIR"));
-        let data_path = env::var("DATA_PATH").unwrap_or(default_path);
-        let full_path = format!("{}/{}", data_path, "orders.json");
-        let json_contents = fs::read_to_string(full_path);
-        let orders: Vec<OrderStatus> = serde_json::from_str(json_contents.unwrap().as_str()).unwrap();
-        orders
+    pub fn new(store: Arc<OrderStore>) -> Self {
+        WebServiceHandler { store }
+    }
+
+    fn list(&self, req: &HttpRequest) -> HttpResponse {
+        let filter = req
+            .resource
+            .uri()
+            .and_then(|uri| uri.query().get("order_status"));
+        let state = match filter.map(parse_state).transpose() {
+            Ok(state) => state,
+            Err(resp) => return resp,
+        };
+        match self.store.list(state) {
+            Ok(orders) => Self::json_response(StatusCode::Ok, &orders),
+            Err(e) => Self::store_error(e),
+        }
+    }
+
+    fn create(&self, req: &HttpRequest) -> HttpResponse {
+        let input: OrderInput = match parse_body(req) {
+            Ok(input) => input,
+            Err(resp) => return resp,
+        };
+        match self
+            .store
+            .create(input.order_id, input.order_date, input.order_status)
+        {
+            Ok(order) => {
+                let mut resp = Self::json_response(StatusCode::Created, &order);
+                resp.headers_mut().insert(
+                    "Location",
+                    format!("/api/shipping/orders/{}", order.order_id),
+                );
+                resp
+            }
+            Err(e) => Self::store_error(e),
+        }
+    }
+
+    fn replace(&self, req: &HttpRequest, id: i32) -> HttpResponse {
+        let input: OrderInput = match parse_body(req) {
+            Ok(input) => input,
+            Err(resp) => return resp,
+        };
+        if input.order_id.is_some_and(|order_id| order_id != id) {
+            return Self::error_response(StatusCode::BadRequest, "order_id does not match the URL");
+        }
+        self.respond(self.store.update(id, |order| {
+            order.order_date = input.order_date;
+            order.order_status = input.order_status;
+        }))
+    }
+
+    fn patch(&self, req: &HttpRequest, id: i32) -> HttpResponse {
+        let patch: OrderPatch = match parse_body(req) {
+            Ok(patch) => patch,
+            Err(resp) => return resp,
+        };
+        self.respond(self.store.update(id, |order| {
+            if let Some(order_date) = patch.order_date {
+                order.order_date = order_date;
+            }
+            if let Some(order_status) = patch.order_status {
+                order.order_status = order_status;
+            }
+        }))
+    }
+
+    fn respond(&self, result: Result<OrderStatus, StoreError>) -> HttpResponse {
+        match result {
+            Ok(order) => Self::json_response(StatusCode::Ok, &order),
+            Err(e) => Self::store_error(e),
+        }
+    }
+
+    fn json_response(status: StatusCode, value: &impl Serialize) -> HttpResponse {
+        let mut headers = HeaderMap::new();
+        headers.insert("Content-Type", "application/json");
+        let body = serde_json::to_string(value).expect("orders serialize to JSON");
+        HttpResponse::new(status, Some(headers), Some(body))
+    }
+
+    fn error_response(status: StatusCode, message: &str) -> HttpResponse {
+        Self::json_response(status, &serde_json::json!({ "error": message }))
+    }
+
+    fn store_error(e: StoreError) -> HttpResponse {
+        match e {
+            StoreError::NotFound(_) => {
+                Self::error_response(StatusCode::NotFound, "order not found")
+            }
+            StoreError::DuplicateId(_) | StoreError::IdsExhausted => {
+                Self::error_response(StatusCode::Conflict, &e.to_string())
+            }
+            StoreError::Invalid(message) => {
+                Self::error_response(StatusCode::UnprocessableContent, &message)
+            }
+            StoreError::Io(..) | StoreError::Corrupt(..) => {
+                println!("Failed to access orders: {}", e);
+                Self::error_response(StatusCode::InternalServerError, "orders unavailable")
+            }
+        }
+    }
+}
+
+// 요청 본문을 JSON으로 읽는다. JSON이 아니면 415를, 형식이 맞지 않으면 400을 돌려준다.
+fn parse_body<T: DeserializeOwned>(req: &HttpRequest) -> Result<T, HttpResponse> {
+    let is_json = req
+        .headers
+        .content_type()
+        .and_then(|content_type| content_type.split(';').next())
+        .is_some_and(|mime| mime.trim().eq_ignore_ascii_case("application/json"));
+    if !is_json {
+        return Err(WebServiceHandler::error_response(
+            StatusCode::UnsupportedMediaType,
+            "request body must be application/json",
+        ));
     }
+    serde_json::from_slice(&req.msg_body)
+        .map_err(|e| WebServiceHandler::error_response(StatusCode::BadRequest, &e.to_string()))
+}
+
+fn parse_state(value: &str) -> Result<OrderState, HttpResponse> {
+    serde_json::from_value(serde_json::Value::String(value.to_string())).map_err(|_| {
+        WebServiceHandler::error_response(
+            StatusCode::BadRequest,
+            &format!("unknown order_status {:?}", value),
+        )
+    })
 }
+
 impl Handler for WebServiceHandler {
-    fn handle(req: &HttpRequest) -> HttpResponse {
-        let http::httprequest::Resource::Path(s) = &req.resource;
-
-        // URI를 파싱한다.
-        let route: Vec<&str> = s.split("/").collect();
-        // if route if /api/shipping/orders, return json
-        match route[2] {
-            "shippling" if route.len() > 2 && route[3] == "orders" => {
-                let body = Some(serde_json::to_string(&Self::load_json()).unwrap());
-                let mut headers: HashMap<&str, &str> = HashMap::new();
-                headers.insert("Content-Type", "application/json");
-                HttpResponse::new("200", Some(headers), body)
+    // /api/shipping/orders: GET은 목록(order_status로 거를 수 있다), POST는 새 주문
+    // /api/shipping/orders/{id}: GET은 주문 하나, PUT은 통째로 바꾸기, PATCH는 일부 바꾸기, DELETE는 지우기
+    fn handle(&self, req: &HttpRequest, params: &Params) -> HttpResponse {
+        let Some(id) = params.get("id") else {
+            return match req.method {
+                Method::Post => self.create(req),
+                _ => self.list(req),
+            };
+        };
+        let Ok(id) = id.parse::<i32>() else {
+            return Self::error_response(StatusCode::BadRequest, "order id must be an integer");
+        };
+        match req.method {
+            Method::Put => self.replace(req, id),
+            Method::Patch => self.patch(req, id),
+            Method::Delete => match self.store.delete(id) {
+                Ok(_) => HttpResponse::new(StatusCode::NoContent, None, None),
+                Err(e) => Self::store_error(e),
+            },
+            _ => self.respond(self.store.get(id)),
+        }
+    }
+}
+
+// 주문 변경을 웹소켓으로 밀어 줄 때 저장소를 다시 확인하는 간격
+const ORDER_FEED_INTERVAL: Duration = Duration::from_millis(250);
+
+// 이벤트 스트림에서 변경을 한 번에 기다리는 시간. 그 사이사이 서버 종료를 확인한다.
+const ORDER_EVENTS_WAIT: Duration = Duration::from_secs(1);
+
+// 이벤트 스트림이 끊겼을 때 브라우저가 다시 연결하기 전에 기다릴 시간
+const ORDER_EVENTS_RETRY: Duration = Duration::from_secs(3);
+
+// 구독자에게 보낼 다음 내용
+enum OrderFeed {
+    // 주문 목록 전체와 거기 반영된 마지막 변경 id
+    Snapshot(u64, Vec<OrderStatus>),
+    Events(Vec<OrderEvent>),
+}
+
+impl WebServiceHandler {
+    // after 뒤의 변경을 wait까지 기다려 돌려준다. 처음 연결했거나 변경 이력에서 밀려났으면 목록 전체를 준다.
+    fn order_feed(&self, after: Option<u64>, wait: Duration) -> Result<OrderFeed, StoreError> {
+        if let Some(after) = after {
+            if let Changes::Events(events) = self.store.changes_since(after, wait)? {
+                return Ok(OrderFeed::Events(events));
             }
-            _ => HttpResponse::new("404", None, Self::load_file("404.html"))
         }
+        let (last, orders) = self.store.snapshot()?;
+        Ok(OrderFeed::Snapshot(last, orders))
     }
-}
\ No newline at end of file
+}
+
+// /api/shipping/orders/live: 연결하면 주문 목록을 {"type": "snapshot", "orders": [...]}로 보내고,
+// 그 뒤로는 저장소의 변경 이력을 {"id": 1, "type": "created", "order_id": 3, "order": {...}}처럼 보낸다.
+// 저장소가 파일의 수정 시각도 확인하므로 파일을 직접 고쳐도 알린다.
+impl WebSocketHandler for WebServiceHandler {
+    fn handle(
+        &self,
+        _req: &HttpRequest,
+        _params: &Params,
+        socket: &mut WebSocket<'_>,
+    ) -> io::Result<()> {
+        let mut last = None;
+        loop {
+            let feed = match self.order_feed(last, Duration::ZERO) {
+                Ok(feed) => feed,
+                Err(e) => {
+                    println!("Order feed failed: {}", e);
+                    return socket.close(CLOSE_INTERNAL_ERROR, "order store unavailable");
+                }
+            };
+            match feed {
+                OrderFeed::Snapshot(id, orders) => {
+                    let snapshot = serde_json::json!({ "type": "snapshot", "orders": orders });
+                    socket.send(&Message::Text(snapshot.to_string()))?;
+                    last = Some(id);
+                }
+                OrderFeed::Events(events) => {
+                    for event in events {
+                        socket.send(&Message::Text(serde_json::to_string(&event)?))?;
+                        last = Some(event.id);
+                    }
+                }
+            }
+
+            // 클라이언트가 보내는 메시지는 쓰지 않는다
+            if let Some(Message::Close(_)) = socket.read_message(ORDER_FEED_INTERVAL)? {
+                return Ok(());
+            }
+        }
+    }
+}
+
+// /api/shipping/orders/events: 웹소켓 피드와 같은 내용을 Server-Sent Events로 보낸다.
+// 주문 목록은 snapshot 이벤트, 변경은 created, updated, deleted 이벤트이고 data는 JSON이다.
+// 이벤트 id는 "세대-번호"라서, 다시 연결할 때 Last-Event-ID로 돌려주면 놓친 변경부터 이어 보낸다.
+// 서버를 다시 띄웠거나 이력에서 밀려난 id면 목록을 처음부터 다시 보낸다.
+impl EventStreamHandler for WebServiceHandler {
+    fn handle(
+        &self,
+        req: &HttpRequest,
+        _params: &Params,
+        stream: &mut EventStream<'_>,
+    ) -> io::Result<()> {
+        let generation = self.store.generation();
+        let mut last = req
+            .headers
+            .get("Last-Event-ID")
+            .and_then(|id| resume_id(id, generation));
+        stream.send(&Event::default().retry(ORDER_EVENTS_RETRY))?;
+        while stream.keep_alive()? {
+            let feed = match self.order_feed(last, ORDER_EVENTS_WAIT) {
+                Ok(feed) => feed,
+                Err(e) => {
+                    // 스트림을 끝내면 브라우저가 retry 뒤에 다시 연결한다
+                    println!("Order events failed: {}", e);
+                    return Ok(());
+                }
+            };
+            match feed {
+                OrderFeed::Snapshot(id, orders) => {
+                    let event = Event::new(serde_json::to_string(&orders)?)
+                        .event("snapshot")
+                        .id(format!("{}-{}", generation, id));
+                    stream.send(&event)?;
+                    last = Some(id);
+                }
+                OrderFeed::Events(events) => {
+                    for event in events {
+                        let sse = Event::new(serde_json::to_string(&event)?)
+                            .event(event.kind.as_str())
+                            .id(format!("{}-{}", generation, event.id));
+                        stream.send(&sse)?;
+                        last = Some(event.id);
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+// Last-Event-ID에서 이어 받을 변경 id를 꺼낸다. 다른 세대의 id면 쓸 수 없다.
+fn resume_id(id: &str, generation: u64) -> Option<u64> {
+    let (id_generation, id) = id.trim().split_once('-')?;
+    if id_generation.parse::<u64>().ok()? != generation {
+        return None;
+    }
+    id.parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::router::Router;
+    use std::env;
+
+    fn request(method: &str, target: &str, body: Option<&str>) -> HttpRequest {
+        let raw = match body {
+            Some(body) => format!(
+                "{} {} HTTP/1.1\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n{}",
+                method,
+                target,
+                body.len(),
+                body
+            ),
+            None => format!("{} {} HTTP/1.1\r\n\r\n", method, target),
+        };
+        HttpRequest::try_from(raw).unwrap()
+    }
+
+    fn body_json(resp: &HttpResponse) -> serde_json::Value {
+        serde_json::from_slice(resp.body().as_bytes().unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_order_api() {
+        let dir = env::temp_dir().join(format!("order-api-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(
+            dir.join("orders.json"),
+            r#"[{"order_id": 1, "order_date": "21 Jan 2020", "order_status": "Delivered"}]"#,
+        )
+        .unwrap();
+        let orders = WebServiceHandler::new(Arc::new(OrderStore::new(dir.join("orders.json"))));
+        let router = Router::new()
+            .get("/api/shipping/orders", orders.clone())
+            .post("/api/shipping/orders", orders.clone())
+            .get("/api/shipping/orders/{id}", orders.clone())
+            .patch("/api/shipping/orders/{id}", orders.clone())
+            .put("/api/shipping/orders/{id}", orders.clone())
+            .delete("/api/shipping/orders/{id}", orders);
+        let send = |method, target, body| router.dispatch(&request(method, target, body));
+
+        let resp = send(
+            "POST",
+            "/api/shipping/orders",
+            Some(r#"{"order_date": "2 Feb 2020", "order_status": "Pending"}"#),
+        );
+        assert_eq!(StatusCode::Created, resp.status());
+        assert_eq!(
+            Some("/api/shipping/orders/2"),
+            resp.headers().get("Location")
+        );
+
+        let resp = send("GET", "/api/shipping/orders?order_status=Pending", None);
+        assert_eq!(2, body_json(&resp)[0]["order_id"]);
+        assert_eq!(1, body_json(&resp).as_array().unwrap().len());
+
+        let resp = send(
+            "PATCH",
+            "/api/shipping/orders/2",
+            Some(r#"{"order_status": "Shipped"}"#),
+        );
+        assert_eq!(StatusCode::Ok, resp.status());
+        assert_eq!("Shipped", body_json(&resp)["order_status"]);
+        assert_eq!("2 Feb 2020", body_json(&resp)["order_date"]);
+
+        let resp = send(
+            "PUT",
+            "/api/shipping/orders/2",
+            Some(r#"{"order_date": "3 Feb 2020", "order_status": "Delivered"}"#),
+        );
+        assert_eq!("3 Feb 2020", body_json(&resp)["order_date"]);
+
+        assert_eq!(
+            StatusCode::NoContent,
+            send("DELETE", "/api/shipping/orders/2", None).status()
+        );
+        assert_eq!(
+            StatusCode::NotFound,
+            send("GET", "/api/shipping/orders/2", None).status()
+        );
+
+        // 잘못된 요청
+        for (method, target, body, status) in [
+            (
+                "GET",
+                "/api/shipping/orders?order_status=Lost",
+                None,
+                StatusCode::BadRequest,
+            ),
+            (
+                "GET",
+                "/api/shipping/orders/abc",
+                None,
+                StatusCode::BadRequest,
+            ),
+            (
+                "POST",
+                "/api/shipping/orders",
+                Some("{"),
+                StatusCode::BadRequest,
+            ),
+            (
+                "POST",
+                "/api/shipping/orders",
+                Some(r#"{"order_id": 1, "order_date": "1 Jan 2020", "order_status": "Pending"}"#),
+                StatusCode::Conflict,
+            ),
+            (
+                "POST",
+                "/api/shipping/orders",
+                Some(r#"{"order_date": "Jan 1", "order_status": "Pending"}"#),
+                StatusCode::UnprocessableContent,
+            ),
+            (
+                "PUT",
+                "/api/shipping/orders/1",
+                Some(r#"{"order_id": 5, "order_date": "1 Jan 2020", "order_status": "Pending"}"#),
+                StatusCode::BadRequest,
+            ),
+            (
+                "PATCH",
+                "/api/shipping/orders/9",
+                Some("{}"),
+                StatusCode::NotFound,
+            ),
+        ] {
+            assert_eq!(
+                status,
+                send(method, target, body).status(),
+                "{} {}",
+                method,
+                target
+            );
+        }
+        let resp = router.dispatch(
+            &HttpRequest::try_from(
+                "POST /api/shipping/orders HTTP/1.1\r\nContent-Length: 2\r\n\r\n{}".to_string(),
+            )
+            .unwrap(),
+        );
+        assert_eq!(StatusCode::UnsupportedMediaType, resp.status());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_resume_id() {
+        assert_eq!(Some(12), resume_id("77-12", 77));
+        assert_eq!(Some(0), resume_id(" 77-0 ", 77));
+        // 서버를 다시 띄우기 전의 id나 알아볼 수 없는 id는 이어 받지 않는다
+        assert_eq!(None, resume_id("76-12", 77));
+        assert_eq!(None, resume_id("12", 77));
+        assert_eq!(None, resume_id("77-x", 77));
+    }
+}
@@ -1,12 +1,181 @@
+mod access_log;
+#[cfg(feature = "async")]
+mod async_server;
+mod compression;
+mod config;
+mod connection_limit;
+mod cors;
 mod handler;
-mod server;
+mod middleware;
+mod mime;
+mod order_store;
+mod pool;
 mod router;
+mod server;
+mod sse;
+mod static_file;
+mod tls;
+mod websocket;
 
+use access_log::AccessLog;
+use compression::Compression;
+use config::{Command, Config};
+use cors::Cors;
+use handler::{PageNotFoundHandler, StaticPageHandler, WebServiceHandler};
+use http::httprequest::Limits;
+use middleware::CatchPanic;
+use mime::MimeRegistry;
+use order_store::OrderStore;
+use router::Router;
 use server::Server;
+use static_file::StaticRoot;
+use std::env;
+use std::process;
+use std::sync::Arc;
+use std::time::Duration;
 
 fn main() {
+    // 설정 파일과 명령행 인자를 읽는다. 잘못된 설정은 서버를 띄우기 전에 알리고 끝낸다.
+    let config = match config::parse_args(env::args().skip(1)) {
+        Ok(Command::Run(config)) => *config,
+        Ok(Command::Help) => {
+            print!("{}", config::USAGE);
+            return;
+        }
+        Err(e) => exit_with_error(e),
+    };
+    if let Err(e) = config.validate() {
+        exit_with_error(e);
+    }
+    match build_server(&config) {
+        // 서버를 실행한다.
+        Ok(server) => server.run(),
+        Err(e) => exit_with_error(e),
+    }
+}
+
+fn exit_with_error(e: impl std::fmt::Display) -> ! {
+    eprintln!("httpserver: {}", e);
+    process::exit(2);
+}
+
+fn build_server(config: &Config) -> Result<Server, String> {
+    // 확장자가 없는 파일은 설정에 따라 내용으로 타입을 추측한다.
+    // mime.types 형식의 파일을 주면 기본 표를 덮어쓴다.
+    let mut mime = MimeRegistry::new().sniff(config.static_files.mime_sniff);
+    if let Some(path) = &config.static_files.mime_types {
+        mime.load_overrides(path)
+            .map_err(|e| format!("failed to load MIME types: {}", e))?;
+    }
+
+    let public_path = config.document_root.to_string_lossy();
+    let static_root = StaticRoot::new(&config.document_root)
+        .map_err(|e| format!("invalid document root {}: {}", public_path, e))?;
+
+    // 응답 압축. 최소 크기(바이트)와 압축할 Content-Type 목록을 바꿀 수 있다.
+    let mut compression = Compression::new();
+    if let Some(min_size) = config.compression.min_size {
+        compression = compression.min_size(min_size);
+    }
+    if let Some(types) = &config.compression.content_types {
+        compression = compression.content_types(types.clone());
+    }
+
+    // 주문 API. 메서드마다 라우트를 따로 등록하고 저장소는 함께 쓴다.
+    // 주문 변경을 밀어 주는 웹소켓과 이벤트 스트림 경로는 {id}보다 먼저 등록해야 한다.
+    let orders = WebServiceHandler::new(Arc::new(OrderStore::new(
+        config.data_dir.join("orders.json"),
+    )));
+
+    // 다른 오리진의 브라우저가 주문 API를 부를 수 있게 한다. 허용할 오리진을 설정해야 켜진다.
+    // preflight에 라우터보다 먼저 답하도록 미들웨어로 둔다.
+    let mut router = Router::new().wrap(CatchPanic);
+    if config.cors.enabled() {
+        let mut cors = Cors::new()
+            .prefix("/api")
+            .allow_origins(config.cors.allowed_origins.clone())
+            .expose_headers(config.cors.exposed_headers.clone())
+            .allow_credentials(config.cors.allow_credentials);
+        if let Some(methods) = &config.cors.allowed_methods {
+            cors = cors.allow_methods(methods.clone());
+        }
+        if let Some(headers) = &config.cors.allowed_headers {
+            cors = cors.allow_headers(headers.clone());
+        }
+        if let Some(max_age) = config.cors.max_age_secs {
+            cors = cors.max_age(Duration::from_secs(max_age));
+        }
+        router = router.wrap(cors);
+    }
+
+    // 라우트 표. 먼저 등록한 라우트가 우선하므로 나머지 경로를 모두 받는 정적 파일 라우트를 마지막에 둔다.
+    let router = router
+        .wrap(compression)
+        .get("/api/shipping/orders", orders.clone())
+        .post("/api/shipping/orders", orders.clone())
+        .websocket("/api/shipping/orders/live", orders.clone())
+        .events("/api/shipping/orders/events", orders.clone())
+        .get("/api/shipping/orders/{id}", orders.clone())
+        .put("/api/shipping/orders/{id}", orders.clone())
+        .patch("/api/shipping/orders/{id}", orders.clone())
+        .delete("/api/shipping/orders/{id}", orders)
+        .get(
+            "/{*path}",
+            StaticPageHandler::new(static_root, mime)
+                .index_files(config.static_files.index_files.clone())
+                .autoindex(config.static_files.autoindex)
+                .precompressed(config.static_files.precompressed),
+        )
+        .fallback(PageNotFoundHandler::new(&*public_path));
+
+    // 접근 로그. 파일을 주지 않으면 표준 출력에 쓴다.
+    let access_log = match &config.log.file {
+        Some(path) => AccessLog::file(
+            config.log.format,
+            path,
+            config.log.max_file_bytes,
+            config.log.max_files,
+        )
+        .map_err(|e| format!("cannot open access log {}: {}", path.display(), e))?,
+        None => AccessLog::stdout(config.log.format),
+    };
+
     // 서버를 시작한다.
-    let server = Server::new("localhost:3000");
-    // 서버를 실행한다.
-    server.run();
+    let mut server = Server::new(router)
+        .workers(config.workers.threads, config.workers.queue)
+        .worker_model(config.workers.model)
+        .timeouts(
+            Duration::from_secs(config.timeouts.keep_alive_secs),
+            Duration::from_secs(config.timeouts.read_secs),
+            Duration::from_secs(config.timeouts.write_secs),
+        )
+        .limits(Limits {
+            max_request_line: config.limits.max_request_line,
+            max_header_bytes: config.limits.max_header_bytes,
+            max_header_count: config.limits.max_header_count,
+            max_body_bytes: config.limits.max_body_bytes,
+        })
+        .max_connections_per_ip(config.limits.max_connections_per_ip)
+        .max_streams(config.limits.max_streams)
+        .access_log(access_log);
+
+    // redirect_http를 켜면 평문 주소로 온 요청을 처리하지 않고 모두 HTTPS 주소로 보낸다.
+    let redirect_port = config
+        .tls
+        .redirect_port()
+        .filter(|_| config.tls.redirect_http);
+    for addr in &config.listen {
+        server = match redirect_port {
+            Some(port) => server.redirect_to_https(addr, port),
+            None => server.listen(addr),
+        };
+    }
+    if config.tls.enabled() {
+        let tls_config = tls::server_config(&config.tls.certificates())
+            .map_err(|e| format!("invalid TLS configuration: {}", e))?;
+        for addr in &config.tls.listen {
+            server = server.listen_tls(addr, tls_config.clone());
+        }
+    }
+    Ok(server)
 }
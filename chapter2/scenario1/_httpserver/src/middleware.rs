@@ -0,0 +1,146 @@
+use super::router::Router;
+use http::{httprequest::HttpRequest, httpresponse::HttpResponse, status::StatusCode};
+use std::panic::{self, AssertUnwindSafe};
+
+// 라우팅 전후에 끼어드는 공통 처리(로깅, 압축, CORS, 인증 등).
+// next.run(req)을 호출하면 다음 미들웨어나 라우터로 넘어가고, 호출하지 않으면 그 자리에서 응답을 끝낸다.
+pub trait Middleware: Send + Sync {
+    fn handle(&self, req: &HttpRequest, next: Next<'_>) -> HttpResponse;
+}
+
+// 아직 실행하지 않은 미들웨어들과, 그 끝에서 요청을 처리할 라우터
+pub struct Next<'a> {
+    middlewares: &'a [Box<dyn Middleware>],
+    router: &'a Router,
+}
+
+impl<'a> Next<'a> {
+    pub(crate) fn new(middlewares: &'a [Box<dyn Middleware>], router: &'a Router) -> Self {
+        Next {
+            middlewares,
+            router,
+        }
+    }
+
+    pub fn run(self, req: &HttpRequest) -> HttpResponse {
+        match self.middlewares.split_first() {
+            Some((middleware, rest)) => middleware.handle(req, Next::new(rest, self.router)),
+            None => self.router.resolve(req),
+        }
+    }
+}
+
+// 핸들러가 패닉하면 worker 스레드를 잃는 대신 500 응답으로 바꾼다
+pub struct CatchPanic;
+impl Middleware for CatchPanic {
+    fn handle(&self, req: &HttpRequest, next: Next<'_>) -> HttpResponse {
+        match panic::catch_unwind(AssertUnwindSafe(|| next.run(req))) {
+            Ok(resp) => resp,
+            Err(_) => HttpResponse::new(
+                StatusCode::InternalServerError,
+                None,
+                Some("Internal Server Error".into()),
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::router::Params;
+    use std::sync::Mutex;
+
+    fn request(target: &str) -> HttpRequest {
+        HttpRequest::try_from(format!(
+            "GET {} HTTP/1.1\r\nHost: localhost\r\n\r\n",
+            target
+        ))
+        .unwrap()
+    }
+
+    // 실행 순서를 기록하는 미들웨어
+    struct Trace {
+        name: &'static str,
+        log: &'static Mutex<Vec<String>>,
+    }
+    impl Middleware for Trace {
+        fn handle(&self, req: &HttpRequest, next: Next<'_>) -> HttpResponse {
+            self.log
+                .lock()
+                .unwrap()
+                .push(format!("{} before", self.name));
+            let mut resp = next.run(req);
+            resp.headers_mut().append("X-Trace", self.name);
+            self.log
+                .lock()
+                .unwrap()
+                .push(format!("{} after", self.name));
+            resp
+        }
+    }
+
+    // 인증 헤더가 없으면 라우터까지 가지 않고 401로 끝낸다
+    struct RequireAuth;
+    impl Middleware for RequireAuth {
+        fn handle(&self, req: &HttpRequest, next: Next<'_>) -> HttpResponse {
+            match req.headers.get("Authorization") {
+                Some(_) => next.run(req),
+                None => HttpResponse::new(StatusCode::Unauthorized, None, None),
+            }
+        }
+    }
+
+    fn ok(_req: &HttpRequest, _params: &Params) -> HttpResponse {
+        HttpResponse::new(StatusCode::Ok, None, None)
+    }
+
+    #[test]
+    fn test_middlewares_run_in_order_around_router() {
+        static LOG: Mutex<Vec<String>> = Mutex::new(Vec::new());
+        let router = Router::new()
+            .wrap(Trace {
+                name: "outer",
+                log: &LOG,
+            })
+            .wrap(Trace {
+                name: "inner",
+                log: &LOG,
+            })
+            .get("/", ok);
+
+        let resp = router.dispatch(&request("/"));
+        assert_eq!(StatusCode::Ok, resp.status());
+        assert_eq!(vec!["inner", "outer"], resp.headers().get_all("X-Trace"));
+        assert_eq!(
+            vec!["outer before", "inner before", "inner after", "outer after"],
+            *LOG.lock().unwrap()
+        );
+    }
+
+    #[test]
+    fn test_middleware_can_short_circuit() {
+        let router = Router::new().wrap(RequireAuth).get("/", ok);
+        assert_eq!(
+            StatusCode::Unauthorized,
+            router.dispatch(&request("/")).status()
+        );
+        // 라우트가 없는 경로도 미들웨어를 먼저 거친다
+        assert_eq!(
+            StatusCode::Unauthorized,
+            router.dispatch(&request("/missing")).status()
+        );
+    }
+
+    #[test]
+    fn test_panic_becomes_500() {
+        let router = Router::new().wrap(CatchPanic).get(
+            "/",
+            |_req: &HttpRequest, _params: &Params| -> HttpResponse { panic!("handler failed") },
+        );
+        assert_eq!(
+            StatusCode::InternalServerError,
+            router.dispatch(&request("/")).status()
+        );
+    }
+}
@@ -0,0 +1,266 @@
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{self, Read, Seek, SeekFrom};
+use std::path::Path;
+
+// 확장자를 모를 때 보내는 기본 미디어 타입
+const DEFAULT_TYPE: &str = "application/octet-stream";
+// 내용으로 타입을 추측할 때 읽는 최대 바이트 수
+const SNIFF_LEN: usize = 512;
+
+// 자주 쓰는 확장자와 미디어 타입. 사용자 설정으로 덮어쓰거나 늘릴 수 있다.
+const BUILTIN_TYPES: &[(&str, &str)] = &[
+    ("html", "text/html"),
+    ("htm", "text/html"),
+    ("css", "text/css"),
+    ("js", "text/javascript"),
+    ("mjs", "text/javascript"),
+    ("txt", "text/plain"),
+    ("csv", "text/csv"),
+    ("md", "text/markdown"),
+    ("xml", "application/xml"),
+    ("json", "application/json"),
+    ("map", "application/json"),
+    ("webmanifest", "application/manifest+json"),
+    ("wasm", "application/wasm"),
+    ("pdf", "application/pdf"),
+    ("zip", "application/zip"),
+    ("gz", "application/gzip"),
+    ("tar", "application/x-tar"),
+    ("svg", "image/svg+xml"),
+    ("png", "image/png"),
+    ("jpg", "image/jpeg"),
+    ("jpeg", "image/jpeg"),
+    ("gif", "image/gif"),
+    ("webp", "image/webp"),
+    ("avif", "image/avif"),
+    ("ico", "image/vnd.microsoft.icon"),
+    ("bmp", "image/bmp"),
+    ("woff", "font/woff"),
+    ("woff2", "font/woff2"),
+    ("ttf", "font/ttf"),
+    ("otf", "font/otf"),
+    ("mp3", "audio/mpeg"),
+    ("ogg", "audio/ogg"),
+    ("wav", "audio/wav"),
+    ("mp4", "video/mp4"),
+    ("webm", "video/webm"),
+];
+
+// 파일 이름의 확장자로 Content-Type을 정하는 표.
+// 확장자가 없는 파일은 선택적으로 앞부분 내용을 보고 타입을 추측한다.
+pub struct MimeRegistry {
+    by_extension: HashMap<String, String>,
+    sniff: bool,
+}
+
+impl Default for MimeRegistry {
+    fn default() -> Self {
+        MimeRegistry {
+            by_extension: BUILTIN_TYPES
+                .iter()
+                .map(|(ext, mime)| (ext.to_string(), mime.to_string()))
+                .collect(),
+            sniff: true,
+        }
+    }
+}
+
+impl MimeRegistry {
+    pub fn new() -> Self {
+        MimeRegistry::default()
+    }
+
+    pub fn sniff(mut self, sniff: bool) -> Self {
+        self.sniff = sniff;
+        self
+    }
+
+    // mime.types 형식의 파일로 표를 덮어쓴다. 각 행은 "타입 확장자..."이고 #부터는 주석이다.
+    pub fn load_overrides(&mut self, path: &Path) -> io::Result<()> {
+        let contents = fs::read_to_string(path)?;
+        for (number, line) in contents.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or("");
+            let mut fields = line.split_whitespace();
+            let Some(mime) = fields.next() else {
+                continue;
+            };
+            if !is_media_type(mime) {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!(
+                        "{}:{}: invalid media type {:?}",
+                        path.display(),
+                        number + 1,
+                        mime
+                    ),
+                ));
+            }
+            for ext in fields {
+                let ext = ext.trim_start_matches('.').to_ascii_lowercase();
+                self.by_extension.insert(ext, mime.to_string());
+            }
+        }
+        Ok(())
+    }
+
+    // 확장자에 등록된 미디어 타입. 대소문자를 구분하지 않는다.
+    pub fn lookup(&self, file_name: &str) -> Option<&str> {
+        let name = file_name.rsplit('/').next().unwrap_or(file_name);
+        let (stem, ext) = name.rsplit_once('.')?;
+        // ".bashrc" 같은 이름은 확장자가 없는 것으로 본다
+        if stem.is_empty() {
+            return None;
+        }
+        self.by_extension
+            .get(&ext.to_ascii_lowercase())
+            .map(String::as_str)
+    }
+
+    // 응답에 쓸 Content-Type. 텍스트 타입에는 charset을 붙인다.
+    // 내용을 추측하려고 파일을 읽은 경우 파일 위치를 처음으로 되돌린다.
+    pub fn content_type(&self, file_name: &str, file: &mut File) -> String {
+        let mime = match self.lookup(file_name) {
+            Some(mime) => mime.to_string(),
+            None if self.sniff => sniff_file(file).unwrap_or(DEFAULT_TYPE).to_string(),
+            None => DEFAULT_TYPE.to_string(),
+        };
+        with_charset(mime)
+    }
+}
+
+fn with_charset(mime: String) -> String {
+    if mime.starts_with("text/") && !mime.contains(';') {
+        format!("{}; charset=utf-8", mime)
+    } else {
+        mime
+    }
+}
+
+fn is_media_type(value: &str) -> bool {
+    let is_token = |s: &str| {
+        !s.is_empty()
+            && s.bytes()
+                .all(|b| b.is_ascii_alphanumeric() || b"!#$&-^_.+".contains(&b))
+    };
+    value
+        .split_once('/')
+        .is_some_and(|(kind, subtype)| is_token(kind) && is_token(subtype))
+}
+
+fn sniff_file(file: &mut File) -> Option<&'static str> {
+    let mut buf = Vec::with_capacity(SNIFF_LEN);
+    file.by_ref()
+        .take(SNIFF_LEN as u64)
+        .read_to_end(&mut buf)
+        .ok()?;
+    file.seek(SeekFrom::Start(0)).ok()?;
+    sniff(&buf)
+}
+
+// 파일 앞부분의 매직 넘버나 내용으로 타입을 추측한다
+fn sniff(bytes: &[u8]) -> Option<&'static str> {
+    const SIGNATURES: &[(&[u8], &str)] = &[
+        (b"\x89PNG\r\n\x1a\n", "image/png"),
+        (b"\xff\xd8\xff", "image/jpeg"),
+        (b"GIF87a", "image/gif"),
+        (b"GIF89a", "image/gif"),
+        (b"%PDF-", "application/pdf"),
+        (b"\0asm", "application/wasm"),
+        (b"wOFF", "font/woff"),
+        (b"wOF2", "font/woff2"),
+        (b"PK\x03\x04", "application/zip"),
+        (b"\x1f\x8b", "application/gzip"),
+    ];
+    if let Some((_, mime)) = SIGNATURES.iter().find(|(sig, _)| bytes.starts_with(sig)) {
+        return Some(mime);
+    }
+    if bytes.len() >= 12 && &bytes[..4] == b"RIFF" && &bytes[8..12] == b"WEBP" {
+        return Some("image/webp");
+    }
+
+    // 바이너리가 아니면 텍스트 내용을 본다. 잘린 UTF-8 문자는 허용한다.
+    let text = match std::str::from_utf8(bytes) {
+        Ok(text) => text,
+        Err(e) if e.error_len().is_none() => std::str::from_utf8(&bytes[..e.valid_up_to()]).ok()?,
+        Err(_) => return None,
+    };
+    if text.contains('\0') {
+        return None;
+    }
+    let start = text.trim_start().to_ascii_lowercase();
+    if start.starts_with("<!doctype html") || start.starts_with("<html") {
+        Some("text/html")
+    } else if start.starts_with("<svg") {
+        Some("image/svg+xml")
+    } else if start.starts_with("<?xml") {
+        Some("application/xml")
+    } else {
+        Some("text/plain")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+
+    #[test]
+    fn test_lookup_by_extension() {
+        let registry = MimeRegistry::new();
+        assert_eq!(Some("image/svg+xml"), registry.lookup("img/logo.svg"));
+        assert_eq!(Some("application/wasm"), registry.lookup("app.WASM"));
+        assert_eq!(Some("application/json"), registry.lookup("data.json"));
+        assert_eq!(None, registry.lookup("LICENSE"));
+        assert_eq!(None, registry.lookup(".hidden"));
+        assert_eq!(None, registry.lookup("archive.unknown"));
+    }
+
+    #[test]
+    fn test_sniff_content() {
+        assert_eq!(Some("image/png"), sniff(b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR"));
+        assert_eq!(Some("image/webp"), sniff(b"RIFF\0\0\0\0WEBPVP8 "));
+        assert_eq!(Some("text/html"), sniff(b"\n  <!DOCTYPE html><html>"));
+        assert_eq!(Some("text/plain"), sniff("Bestellung 주문".as_bytes()));
+        // 잘린 멀티바이트 문자로 끝나도 텍스트로 본다
+        assert_eq!(Some("text/plain"), sniff(&"주문".as_bytes()[..4]));
+        assert_eq!(None, sniff(b"\x00\x01\x02\xff"));
+    }
+
+    #[test]
+    fn test_overrides_and_charset() {
+        let path = env::temp_dir().join(format!("mime-types-{}", std::process::id()));
+        fs::write(
+            &path,
+            "# local types\ntext/x-rust rs\napplication/json map  json5 # source maps\n",
+        )
+        .unwrap();
+        let mut registry = MimeRegistry::new();
+        registry.load_overrides(&path).unwrap();
+        assert_eq!(Some("text/x-rust"), registry.lookup("main.rs"));
+        assert_eq!(Some("application/json"), registry.lookup("x.json5"));
+
+        fs::write(&path, "not-a-type ext\n").unwrap();
+        let err = registry.load_overrides(&path).unwrap_err();
+        assert!(err.to_string().contains(":1:"));
+
+        fs::write(&path, b"\x89PNG\r\n\x1a\nrest").unwrap();
+        let mut file = File::open(&path).unwrap();
+        assert_eq!("image/png", registry.content_type("noext", &mut file));
+        // 추측한 뒤에도 파일을 처음부터 읽을 수 있다
+        let mut contents = Vec::new();
+        file.read_to_end(&mut contents).unwrap();
+        assert_eq!(12, contents.len());
+        assert_eq!(
+            "application/octet-stream",
+            MimeRegistry::new()
+                .sniff(false)
+                .content_type("noext", &mut file)
+        );
+        assert_eq!(
+            "text/css; charset=utf-8",
+            registry.content_type("site.css", &mut file)
+        );
+        fs::remove_file(&path).unwrap();
+    }
+}
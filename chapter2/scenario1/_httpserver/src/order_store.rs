@@ -0,0 +1,588 @@
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::fmt;
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Condvar, Mutex};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+// 변경 이력에 남겨 두는 최근 변경 수. 이보다 오래된 id로 이어 받으려는 구독자는 목록을 다시 받는다.
+const MAX_CHANGES: usize = 1000;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct OrderStatus {
+    pub order_id: i32,
+    pub order_date: String,
+    pub order_status: OrderState,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum OrderState {
+    Pending,
+    Processing,
+    Shipped,
+    Delivered,
+    Cancelled,
+}
+
+impl OrderStatus {
+    // 주문 번호는 양수여야 하고, 주문 날짜는 "21 Jan 2020" 형식의 있는 날짜여야 한다
+    pub fn validate(&self) -> Result<(), String> {
+        if self.order_id <= 0 {
+            return Err("order_id must be a positive integer".to_string());
+        }
+        if !is_valid_date(&self.order_date) {
+            return Err(format!(
+                "order_date {:?} must look like \"21 Jan 2020\"",
+                self.order_date
+            ));
+        }
+        Ok(())
+    }
+}
+
+fn is_valid_date(date: &str) -> bool {
+    const MONTHS: [&str; 12] = [
+        "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+    ];
+    let parts: Vec<&str> = date.split(' ').collect();
+    let [day, month, year] = parts[..] else {
+        return false;
+    };
+    let (Ok(day), Some(month), Ok(year)) = (
+        day.parse::<u32>(),
+        MONTHS.iter().position(|m| *m == month),
+        year.parse::<u32>(),
+    ) else {
+        return false;
+    };
+    let leap = year % 4 == 0 && (year % 100 != 0 || year % 400 == 0);
+    let days_in_month = match month {
+        1 if leap => 29,
+        1 => 28,
+        3 | 5 | 8 | 10 => 30,
+        _ => 31,
+    };
+    year.to_string().len() == 4 && (1..=days_in_month).contains(&day)
+}
+
+#[derive(Debug)]
+pub enum StoreError {
+    Io(PathBuf, io::Error),
+    // 파일이 주문 목록 JSON이 아니다
+    Corrupt(PathBuf, serde_json::Error),
+    NotFound(i32),
+    DuplicateId(i32),
+    // 가장 큰 주문 번호가 i32::MAX여서 다음 번호를 붙일 수 없다
+    IdsExhausted,
+    // 저장하려는 주문이 검증을 통과하지 못했다
+    Invalid(String),
+}
+
+impl fmt::Display for StoreError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StoreError::Io(path, e) => write!(f, "{}: {}", path.display(), e),
+            StoreError::Corrupt(path, e) => write!(f, "{}: {}", path.display(), e),
+            StoreError::NotFound(id) => write!(f, "order {} not found", id),
+            StoreError::DuplicateId(id) => write!(f, "order {} already exists", id),
+            StoreError::IdsExhausted => f.write_str("no order id left to assign, give order_id"),
+            StoreError::Invalid(message) => f.write_str(message),
+        }
+    }
+}
+
+impl std::error::Error for StoreError {}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ChangeKind {
+    Created,
+    Updated,
+    Deleted,
+}
+
+impl ChangeKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ChangeKind::Created => "created",
+            ChangeKind::Updated => "updated",
+            ChangeKind::Deleted => "deleted",
+        }
+    }
+}
+
+// 주문 하나가 바뀐 일. id는 저장소가 변경마다 1씩 늘려 붙이는 번호다.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct OrderEvent {
+    pub id: u64,
+    #[serde(rename = "type")]
+    pub kind: ChangeKind,
+    pub order_id: i32,
+    // 지운 주문이면 없다
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub order: Option<OrderStatus>,
+}
+
+// changes_since의 결과
+#[derive(Debug, PartialEq)]
+pub enum Changes {
+    // 주어진 id 뒤의 변경. 기다리는 동안 바뀐 것이 없으면 비어 있다.
+    Events(Vec<OrderEvent>),
+    // 주어진 id 뒤의 변경을 이력에 다 갖고 있지 않다. 목록을 처음부터 다시 받아야 한다.
+    Expired,
+}
+
+// 최근 변경 이력
+#[derive(Default)]
+struct ChangeLog {
+    events: VecDeque<OrderEvent>,
+    last_id: u64,
+}
+
+// 주문 목록을 JSON 파일 하나에 담아 두는 저장소.
+// 읽은 목록은 메모리에 두고, 파일의 수정 시각이 바뀌면(다른 프로그램이 고친 경우) 다시 읽는다.
+// 변경은 잠금 안에서 목록을 고친 뒤 임시 파일에 쓰고 이름을 바꿔, 읽는 쪽이 반쯤 쓴 파일을 보지 않게 한다.
+// 목록이 바뀔 때마다(다시 읽어서 알게 된 경우도) 변경 이력에 남기고 기다리는 구독자를 깨운다.
+pub struct OrderStore {
+    path: PathBuf,
+    cache: Mutex<Option<Cache>>,
+    generation: u64,
+    // 잠글 때는 언제나 cache를 먼저 잠근다
+    changes: Mutex<ChangeLog>,
+    changed: Condvar,
+}
+
+struct Cache {
+    orders: Vec<OrderStatus>,
+    modified: Option<SystemTime>,
+}
+
+impl OrderStore {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        let generation = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |elapsed| elapsed.as_millis() as u64);
+        OrderStore {
+            path: path.into(),
+            cache: Mutex::new(None),
+            generation,
+            changes: Mutex::new(ChangeLog::default()),
+            changed: Condvar::new(),
+        }
+    }
+
+    // 변경 id의 세대. 서버를 다시 띄우면 id를 처음부터 다시 붙이므로,
+    // 구독자가 이전 세대의 id로 이어 받으려 하는지 알 수 있게 id와 함께 알린다.
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    // 주문 목록과 그 목록에 반영된 마지막 변경의 id. 아직 바뀐 적이 없으면 id는 0이다.
+    pub fn snapshot(&self) -> Result<(u64, Vec<OrderStatus>), StoreError> {
+        let mut cache = self.cache.lock().unwrap_or_else(|e| e.into_inner());
+        let cache = self.fresh(&mut cache)?;
+        let last_id = self
+            .changes
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .last_id;
+        Ok((last_id, cache.orders.clone()))
+    }
+
+    // after 뒤의 변경을 돌려준다. 아직 없으면 timeout까지 기다리고, 그래도 없으면 빈 목록을 돌려준다.
+    // 기다리기 전에 파일을 확인하므로 밖에서 고친 내용도 변경으로 알린다.
+    pub fn changes_since(&self, after: u64, timeout: Duration) -> Result<Changes, StoreError> {
+        self.read(|_| ())?;
+        let deadline = Instant::now() + timeout;
+        let mut log = self.changes.lock().unwrap_or_else(|e| e.into_inner());
+        loop {
+            let oldest = log.events.front().map_or(log.last_id + 1, |event| event.id);
+            if after > log.last_id || after + 1 < oldest {
+                return Ok(Changes::Expired);
+            }
+            let left = deadline.saturating_duration_since(Instant::now());
+            if after < log.last_id || left.is_zero() {
+                let events = log
+                    .events
+                    .iter()
+                    .filter(|event| event.id > after)
+                    .cloned()
+                    .collect();
+                return Ok(Changes::Events(events));
+            }
+            log = self
+                .changed
+                .wait_timeout(log, left)
+                .unwrap_or_else(|e| e.into_inner())
+                .0;
+        }
+    }
+
+    // 주문 목록. 상태를 주면 그 상태의 주문만 돌려준다.
+    pub fn list(&self, state: Option<OrderState>) -> Result<Vec<OrderStatus>, StoreError> {
+        self.read(|orders| {
+            orders
+                .iter()
+                .filter(|order| state.is_none_or(|state| order.order_status == state))
+                .cloned()
+                .collect()
+        })
+    }
+
+    pub fn get(&self, id: i32) -> Result<OrderStatus, StoreError> {
+        self.read(|orders| orders.iter().find(|order| order.order_id == id).cloned())?
+            .ok_or(StoreError::NotFound(id))
+    }
+
+    // 새 주문을 더한다. 주문 번호가 없으면 지금까지의 가장 큰 번호 다음 번호를 붙인다.
+    pub fn create(
+        &self,
+        order_id: Option<i32>,
+        order_date: String,
+        order_status: OrderState,
+    ) -> Result<OrderStatus, StoreError> {
+        self.modify(|orders| {
+            let order_id = match order_id {
+                Some(id) if orders.iter().any(|order| order.order_id == id) => {
+                    return Err(StoreError::DuplicateId(id));
+                }
+                Some(id) => id,
+                None => orders
+                    .iter()
+                    .map(|order| order.order_id)
+                    .max()
+                    .unwrap_or(0)
+                    .checked_add(1)
+                    .ok_or(StoreError::IdsExhausted)?,
+            };
+            let order = OrderStatus {
+                order_id,
+                order_date,
+                order_status,
+            };
+            order.validate().map_err(StoreError::Invalid)?;
+            orders.push(order.clone());
+            Ok(order)
+        })
+    }
+
+    // 주문 하나를 고친다. update가 바꾼 결과를 검증해 저장하고 돌려준다. 주문 번호는 바꿀 수 없다.
+    pub fn update(
+        &self,
+        id: i32,
+        update: impl FnOnce(&mut OrderStatus),
+    ) -> Result<OrderStatus, StoreError> {
+        self.modify(|orders| {
+            let order = orders
+                .iter_mut()
+                .find(|order| order.order_id == id)
+                .ok_or(StoreError::NotFound(id))?;
+            let mut updated = order.clone();
+            update(&mut updated);
+            updated.order_id = id;
+            updated.validate().map_err(StoreError::Invalid)?;
+            *order = updated.clone();
+            Ok(updated)
+        })
+    }
+
+    pub fn delete(&self, id: i32) -> Result<OrderStatus, StoreError> {
+        self.modify(|orders| {
+            let index = orders
+                .iter()
+                .position(|order| order.order_id == id)
+                .ok_or(StoreError::NotFound(id))?;
+            Ok(orders.remove(index))
+        })
+    }
+
+    fn read<T>(&self, f: impl FnOnce(&[OrderStatus]) -> T) -> Result<T, StoreError> {
+        let mut cache = self.cache.lock().unwrap_or_else(|e| e.into_inner());
+        let cache = self.fresh(&mut cache)?;
+        Ok(f(&cache.orders))
+    }
+
+    // 목록을 고치고 파일에 쓴다. f가 실패하거나 파일을 쓰지 못하면 메모리의 목록도 바꾸지 않는다.
+    fn modify<T>(
+        &self,
+        f: impl FnOnce(&mut Vec<OrderStatus>) -> Result<T, StoreError>,
+    ) -> Result<T, StoreError> {
+        let mut guard = self.cache.lock().unwrap_or_else(|e| e.into_inner());
+        let cache = self.fresh(&mut guard)?;
+        let mut orders = cache.orders.clone();
+        let result = f(&mut orders)?;
+        cache.modified = self.write(&orders)?;
+        self.record(&cache.orders, &orders);
+        cache.orders = orders;
+        Ok(result)
+    }
+
+    // 두 목록의 차이를 변경 이력에 남긴다. 새로 생기거나 바뀐 주문을 먼저, 지워진 주문을 뒤에 둔다.
+    fn record(&self, old: &[OrderStatus], new: &[OrderStatus]) {
+        let mut changes = Vec::new();
+        for order in new {
+            match old.iter().find(|o| o.order_id == order.order_id) {
+                None => changes.push((ChangeKind::Created, order.order_id, Some(order))),
+                Some(previous) if previous != order => {
+                    changes.push((ChangeKind::Updated, order.order_id, Some(order)))
+                }
+                Some(_) => {}
+            }
+        }
+        for order in old {
+            if !new.iter().any(|o| o.order_id == order.order_id) {
+                changes.push((ChangeKind::Deleted, order.order_id, None));
+            }
+        }
+        if changes.is_empty() {
+            return;
+        }
+
+        let mut log = self.changes.lock().unwrap_or_else(|e| e.into_inner());
+        for (kind, order_id, order) in changes {
+            log.last_id += 1;
+            let event = OrderEvent {
+                id: log.last_id,
+                kind,
+                order_id,
+                order: order.cloned(),
+            };
+            log.events.push_back(event);
+        }
+        while log.events.len() > MAX_CHANGES {
+            log.events.pop_front();
+        }
+        self.changed.notify_all();
+    }
+
+    // 파일이 처음 읽은 뒤로 바뀌었으면 다시 읽는다. 파일이 없으면 빈 목록이다.
+    fn fresh<'a>(&self, cache: &'a mut Option<Cache>) -> Result<&'a mut Cache, StoreError> {
+        let io_error = |e| StoreError::Io(self.path.clone(), e);
+        let modified = match fs::metadata(&self.path) {
+            Ok(metadata) => Some(metadata.modified().map_err(io_error)?),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => None,
+            Err(e) => return Err(io_error(e)),
+        };
+        if cache
+            .as_ref()
+            .is_none_or(|cache| cache.modified != modified)
+        {
+            let orders = match modified {
+                Some(_) => {
+                    let contents = fs::read_to_string(&self.path).map_err(io_error)?;
+                    serde_json::from_str(&contents)
+                        .map_err(|e| StoreError::Corrupt(self.path.clone(), e))?
+                }
+                None => Vec::new(),
+            };
+            // 처음 읽을 때가 아니면 밖에서 고친 것이므로 변경으로 알린다
+            if let Some(previous) = cache.as_ref() {
+                self.record(&previous.orders, &orders);
+            }
+            *cache = Some(Cache { orders, modified });
+        }
+        Ok(cache.as_mut().unwrap())
+    }
+
+    // 같은 디렉터리의 임시 파일에 다 쓴 뒤 이름을 바꿔 원래 파일을 한 번에 갈아 끼운다
+    fn write(&self, orders: &[OrderStatus]) -> Result<Option<SystemTime>, StoreError> {
+        let io_error = |e| StoreError::Io(self.path.clone(), e);
+        let mut tmp_path = self.path.clone().into_os_string();
+        tmp_path.push(".tmp");
+        let tmp_path = Path::new(&tmp_path);
+        let json = serde_json::to_string_pretty(orders).expect("orders serialize to JSON");
+        let mut file = File::create(tmp_path).map_err(io_error)?;
+        file.write_all(json.as_bytes())
+            .and_then(|()| file.sync_all())
+            .and_then(|()| fs::rename(tmp_path, &self.path))
+            .map_err(io_error)?;
+        let modified = fs::metadata(&self.path)
+            .and_then(|metadata| metadata.modified())
+            .map_err(io_error)?;
+        Ok(Some(modified))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+
+    fn temp_store(name: &str) -> (PathBuf, OrderStore) {
+        let dir = env::temp_dir().join(format!("order-store-{}-{}", name, std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("orders.json");
+        let _ = fs::remove_file(&path);
+        (dir, OrderStore::new(path))
+    }
+
+    #[test]
+    fn test_crud_is_written_back() {
+        let (dir, store) = temp_store("crud");
+        assert!(store.list(None).unwrap().is_empty());
+
+        let first = store
+            .create(None, "21 Jan 2020".into(), OrderState::Pending)
+            .unwrap();
+        assert_eq!(1, first.order_id);
+        store
+            .create(Some(7), "2 Feb 2020".into(), OrderState::Shipped)
+            .unwrap();
+        assert!(matches!(
+            store.create(Some(7), "2 Feb 2020".into(), OrderState::Shipped),
+            Err(StoreError::DuplicateId(7))
+        ));
+        let next = store
+            .create(None, "3 Feb 2020".into(), OrderState::Pending)
+            .unwrap();
+        assert_eq!(8, next.order_id);
+
+        // 가장 큰 번호가 i32::MAX이면 번호를 붙이지 못하지만, 번호를 주면 더할 수 있다
+        store
+            .create(Some(i32::MAX), "4 Feb 2020".into(), OrderState::Pending)
+            .unwrap();
+        assert!(matches!(
+            store.create(None, "5 Feb 2020".into(), OrderState::Pending),
+            Err(StoreError::IdsExhausted)
+        ));
+        store
+            .create(Some(9), "5 Feb 2020".into(), OrderState::Pending)
+            .unwrap();
+        store.delete(i32::MAX).unwrap();
+        store.delete(9).unwrap();
+
+        let updated = store
+            .update(1, |order| order.order_status = OrderState::Delivered)
+            .unwrap();
+        assert_eq!(OrderState::Delivered, updated.order_status);
+        assert_eq!(1, store.list(Some(OrderState::Pending)).unwrap().len());
+        store.delete(8).unwrap();
+        assert!(matches!(store.get(8), Err(StoreError::NotFound(8))));
+        assert!(matches!(
+            store.update(1, |order| order.order_date = "1 Foo 2020".into()),
+            Err(StoreError::Invalid(_))
+        ));
+        assert_eq!("21 Jan 2020", store.get(1).unwrap().order_date);
+
+        // 새 저장소로 파일을 다시 읽어도 같은 목록이다
+        let reopened = OrderStore::new(dir.join("orders.json"));
+        assert_eq!(store.list(None).unwrap(), reopened.list(None).unwrap());
+        assert!(!dir.join("orders.json.tmp").exists());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_cache_reloads_when_file_changes() {
+        let (dir, store) = temp_store("reload");
+        let path = dir.join("orders.json");
+        fs::write(&path, "[]").unwrap();
+        assert!(store.list(None).unwrap().is_empty());
+
+        // 수정 시각이 확실히 달라지도록 조금 기다렸다가 밖에서 파일을 고친다
+        std::thread::sleep(std::time::Duration::from_millis(20));
+        fs::write(
+            &path,
+            r#"[{"order_id": 3, "order_date": "1 Mar 2020", "order_status": "Shipped"}]"#,
+        )
+        .unwrap();
+        assert_eq!(3, store.get(3).unwrap().order_id);
+
+        fs::write(&path, "not json").unwrap();
+        assert!(matches!(store.list(None), Err(StoreError::Corrupt(..))));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_changes_are_recorded() {
+        let (dir, store) = temp_store("changes");
+        assert_eq!((0, Vec::new()), store.snapshot().unwrap());
+        store
+            .create(None, "21 Jan 2020".into(), OrderState::Pending)
+            .unwrap();
+        store
+            .update(1, |order| order.order_status = OrderState::Shipped)
+            .unwrap();
+        // 실패한 변경은 남지 않는다
+        assert!(store.delete(9).is_err());
+        store.delete(1).unwrap();
+
+        let Changes::Events(events) = store.changes_since(0, Duration::ZERO).unwrap() else {
+            panic!("expected events");
+        };
+        let summary: Vec<(u64, ChangeKind, i32, bool)> = events
+            .iter()
+            .map(|e| (e.id, e.kind, e.order_id, e.order.is_some()))
+            .collect();
+        assert_eq!(
+            vec![
+                (1, ChangeKind::Created, 1, true),
+                (2, ChangeKind::Updated, 1, true),
+                (3, ChangeKind::Deleted, 1, false),
+            ],
+            summary
+        );
+        assert_eq!(
+            Changes::Events(Vec::new()),
+            store.changes_since(3, Duration::ZERO).unwrap()
+        );
+        assert_eq!(
+            Changes::Expired,
+            store.changes_since(4, Duration::ZERO).unwrap()
+        );
+
+        // 기다리는 구독자는 다른 스레드의 변경에 깨어난다
+        let store = std::sync::Arc::new(store);
+        let writer = {
+            let store = std::sync::Arc::clone(&store);
+            std::thread::spawn(move || {
+                std::thread::sleep(Duration::from_millis(50));
+                store
+                    .create(Some(5), "2 Feb 2020".into(), OrderState::Pending)
+                    .unwrap();
+            })
+        };
+        let Changes::Events(events) = store.changes_since(3, Duration::from_secs(5)).unwrap()
+        else {
+            panic!("expected events");
+        };
+        assert_eq!(5, events[0].order_id);
+        writer.join().unwrap();
+
+        // 밖에서 고친 파일도 다시 읽을 때 변경으로 남는다
+        std::thread::sleep(Duration::from_millis(20));
+        fs::write(
+            dir.join("orders.json"),
+            r#"[{"order_id": 5, "order_date": "2 Feb 2020", "order_status": "Delivered"}]"#,
+        )
+        .unwrap();
+        let Changes::Events(events) = store.changes_since(4, Duration::ZERO).unwrap() else {
+            panic!("expected events");
+        };
+        assert_eq!(ChangeKind::Updated, events[0].kind);
+        assert_eq!((5, 1), (events[0].id, store.snapshot().unwrap().1.len()));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_validation() {
+        let order = |id, date: &str| OrderStatus {
+            order_id: id,
+            order_date: date.to_string(),
+            order_status: OrderState::Pending,
+        };
+        assert!(order(1, "29 Feb 2024").validate().is_ok());
+        assert!(order(0, "1 Jan 2020").validate().is_err());
+        for date in [
+            "29 Feb 2023",
+            "31 Apr 2020",
+            "1 January 2020",
+            "2020-01-01",
+            "",
+        ] {
+            assert!(order(1, date).validate().is_err(), "{}", date);
+        }
+    }
+}
@@ -0,0 +1,135 @@
+use std::sync::mpsc::{self, Receiver, SyncSender, TrySendError};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+
+// 고정된 수의 worker 스레드가 대기열에서 작업 항목(예: 커넥션)을 꺼내 처리한다.
+// 대기열은 크기가 정해져 있어서, 가득 차면 try_send가 항목을 그대로 돌려준다.
+// 호출자는 돌려받은 항목으로 503 같은 거절 응답을 보낼 수 있다.
+pub struct WorkerPool<T: Send + 'static> {
+    workers: Vec<Worker>,
+    sender: Option<SyncSender<T>>,
+}
+
+struct Worker {
+    id: usize,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl<T: Send + 'static> WorkerPool<T> {
+    pub fn new<F>(size: usize, queue_capacity: usize, handler: F) -> WorkerPool<T>
+    where
+        F: Fn(T) + Send + Sync + 'static,
+    {
+        assert!(size > 0, "worker pool needs at least one worker");
+        let (sender, receiver) = mpsc::sync_channel(queue_capacity);
+        let receiver = Arc::new(Mutex::new(receiver));
+        let handler = Arc::new(handler);
+
+        let workers = (0..size)
+            .map(|id| Worker::new(id, Arc::clone(&receiver), Arc::clone(&handler)))
+            .collect();
+
+        WorkerPool {
+            workers,
+            sender: Some(sender),
+        }
+    }
+
+    // 쉬는 worker나 대기열에 빈자리가 있으면 항목을 넘기고, 없으면 Err로 돌려준다
+    pub fn try_send(&self, item: T) -> Result<(), T> {
+        match self.sender.as_ref() {
+            Some(sender) => sender.try_send(item).map_err(|e| match e {
+                TrySendError::Full(item) | TrySendError::Disconnected(item) => item,
+            }),
+            None => Err(item),
+        }
+    }
+}
+
+// 풀을 버리면 대기열을 닫고, worker들이 이미 받은 항목과 대기열에 남은 항목을
+// 모두 처리할 때까지 기다린다.
+impl<T: Send + 'static> Drop for WorkerPool<T> {
+    fn drop(&mut self) {
+        drop(self.sender.take());
+        for worker in &mut self.workers {
+            if let Some(thread) = worker.thread.take() {
+                if thread.join().is_err() {
+                    println!("Worker {} panicked", worker.id);
+                }
+            }
+        }
+    }
+}
+
+impl Worker {
+    fn new<T, F>(id: usize, receiver: Arc<Mutex<Receiver<T>>>, handler: Arc<F>) -> Worker
+    where
+        T: Send + 'static,
+        F: Fn(T) + Send + Sync + 'static,
+    {
+        let thread = thread::Builder::new()
+            .name(format!("worker-{}", id))
+            .spawn(move || loop {
+                // 잠금은 항목 하나를 꺼내는 동안만 잡는다
+                let item = match receiver.lock() {
+                    Ok(receiver) => receiver.recv(),
+                    Err(_) => break,
+                };
+                match item {
+                    Ok(item) => handler(item),
+                    // 송신 쪽이 닫혔고 대기열도 비었다
+                    Err(_) => break,
+                }
+            })
+            .expect("failed to spawn worker thread");
+
+        Worker {
+            id,
+            thread: Some(thread),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::mpsc::channel;
+    use std::time::Duration;
+
+    #[test]
+    fn test_rejects_when_saturated() {
+        let (release_tx, release_rx) = channel::<()>();
+        let release_rx = Mutex::new(release_rx);
+        let (started_tx, started_rx) = channel::<usize>();
+        let started_tx = Mutex::new(started_tx);
+        let pool = WorkerPool::new(1, 1, move |item: usize| {
+            started_tx.lock().unwrap().send(item).unwrap();
+            release_rx.lock().unwrap().recv().unwrap();
+        });
+
+        // worker 하나가 첫 항목을 잡고 있고, 대기열에 하나가 들어가면 세 번째는 거절된다
+        pool.try_send(1).unwrap();
+        assert_eq!(1, started_rx.recv_timeout(Duration::from_secs(5)).unwrap());
+        pool.try_send(2).unwrap();
+        assert_eq!(Err(3), pool.try_send(3));
+
+        release_tx.send(()).unwrap();
+        release_tx.send(()).unwrap();
+    }
+
+    #[test]
+    fn test_drop_drains_queued_items() {
+        let handled = Arc::new(AtomicUsize::new(0));
+        let counter = Arc::clone(&handled);
+        let pool = WorkerPool::new(2, 16, move |_item: u32| {
+            thread::sleep(Duration::from_millis(10));
+            counter.fetch_add(1, Ordering::SeqCst);
+        });
+        for i in 0..10 {
+            pool.try_send(i).unwrap();
+        }
+        drop(pool);
+        assert_eq!(10, handled.load(Ordering::SeqCst));
+    }
+}
@@ -1,35 +1,421 @@
-use super::handler::{Handler, PageNotFoundHandler, StaticPageHandler, WebServiceHandler};
-use http::{httprequest, httprequest::HttpRequest, httpresponse::HttpResponse};
-use std::io::prelude::*;
+use super::handler::Handler;
+use super::middleware::{Middleware, Next};
+use super::sse::{self, EventStreamHandler};
+use super::websocket::{self, WebSocketHandler};
+use http::{
+    headers::HeaderMap,
+    httprequest::{HttpRequest, Method, Resource},
+    httpresponse::HttpResponse,
+    status::StatusCode,
+};
 
-pub struct Router;
+// 경로 패턴에서 캡처한 파라미터. 값은 퍼센트 디코딩된 세그먼트다.
+#[derive(Debug, Default, PartialEq)]
+pub struct Params {
+    entries: Vec<(String, String)>,
+}
 
-impl Router {
-    pub fn route(req: HttpRequest, stream: &mut impl Write) -> () {
-        match req.method {
-            httprequest::Method::Get => match &req.resource {
-                httprequest::Resource::Path(s) => {
-                    // 해당 URI를 파싱한다
-                    let route: Vec<&str> = s.split("/").collect();
-                    match route[1] { 
-                        // 경로가 /api로 시작하면 Web 서비스를 호출한다
-                        "api" => {
-                            let resp: HttpResponse = WebServiceHandler::handle(&req);
-                            let _ = resp.send_response(stream);
-                        }
-                        // 그렇지 않면 정적 페이지 핸들러를 호출한다
-                        _ => {
-                            let resp: HttpResponse = StaticPageHandler::handle(&req);
-                            let _ = resp.send_response(stream);
-                        }
+impl Params {
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.entries
+            .iter()
+            .find(|(k, _)| k == name)
+            .map(|(_, v)| v.as_str())
+    }
+}
+
+// 경로 패턴의 세그먼트 하나
+#[derive(Debug)]
+enum Segment {
+    // 그대로 일치해야 하는 세그먼트
+    Literal(String),
+    // {name}: 세그먼트 하나를 캡처한다
+    Param(String),
+    // {*name}: 나머지 세그먼트를 모두 '/'로 이어 캡처한다. 패턴의 마지막에만 올 수 있다.
+    CatchAll(String),
+}
+
+struct Route {
+    method: Method,
+    pattern: Vec<Segment>,
+    handler: Box<dyn Handler>,
+    takeover: Option<Takeover>,
+}
+
+// 응답 헤더를 보낸 뒤 커넥션을 넘겨받는 핸들러
+pub(crate) enum Takeover {
+    // 101 응답 뒤에 웹소켓으로 메시지를 주고받는다
+    WebSocket(Box<dyn WebSocketHandler>),
+    // text/event-stream 응답의 본문을 이벤트로 이어 쓴다
+    EventStream(Box<dyn EventStreamHandler>),
+}
+
+impl Route {
+    fn matches(&self, segments: &[String]) -> Option<Params> {
+        let mut params = Params::default();
+        let mut rest = segments.iter();
+        for segment in &self.pattern {
+            match segment {
+                Segment::Literal(literal) => {
+                    if rest.next() != Some(literal) {
+                        return None;
                     }
                 }
+                Segment::Param(name) => {
+                    let value = rest.next()?;
+                    params.entries.push((name.clone(), value.clone()));
+                }
+                Segment::CatchAll(name) => {
+                    let value = rest.by_ref().cloned().collect::<Vec<_>>().join("/");
+                    params.entries.push((name.clone(), value));
+                }
+            }
+        }
+        // 패턴보다 세그먼트가 더 많으면 일치하지 않는다
+        match rest.next() {
+            Some(_) => None,
+            None => Some(params),
+        }
+    }
+
+    // 패턴이 구체적일수록 큰 값. 앞 세그먼트부터 리터럴, {name}, {*name} 순으로 비교한다.
+    fn specificity(&self) -> Vec<u8> {
+        self.pattern
+            .iter()
+            .map(|segment| match segment {
+                Segment::Literal(_) => 2,
+                Segment::Param(_) => 1,
+                Segment::CatchAll(_) => 0,
+            })
+            .collect()
+    }
+}
+
+// 메서드와 경로 패턴(예: /api/shipping/orders/{id})으로 핸들러를 찾는 라우트 표.
+// 등록한 순서대로 비교해 처음 일치하는 라우트가 요청을 처리한다.
+// GET 라우트는 HEAD 요청도 처리하고, OPTIONS는 경로에 등록된 메서드로 자동 응답한다.
+// 경로는 맞지만 메서드가 없으면 405와 Allow 헤더를, 경로가 없으면 fallback 핸들러의 응답을 반환한다.
+// Allow에는 일치한 패턴 가운데 가장 구체적인 패턴의 메서드만 담는다.
+// 미들웨어는 라우팅 전체를 감싸므로 405, 404 응답도 미들웨어를 거친다.
+pub struct Router {
+    routes: Vec<Route>,
+    middlewares: Vec<Box<dyn Middleware>>,
+    fallback: Box<dyn Handler>,
+}
+
+impl Default for Router {
+    fn default() -> Self {
+        Router {
+            routes: Vec::new(),
+            middlewares: Vec::new(),
+            fallback: Box::new(|_req: &HttpRequest, _params: &Params| {
+                HttpResponse::new(StatusCode::NotFound, None, Some("Not Found".into()))
+            }),
+        }
+    }
+}
+
+impl Router {
+    pub fn new() -> Self {
+        Router::default()
+    }
+
+    pub fn route(mut self, method: Method, pattern: &str, handler: impl Handler + 'static) -> Self {
+        self.routes.push(Route {
+            method,
+            pattern: parse_pattern(pattern),
+            handler: Box::new(handler),
+            takeover: None,
+        });
+        self
+    }
+
+    pub fn get(self, pattern: &str, handler: impl Handler + 'static) -> Self {
+        self.route(Method::Get, pattern, handler)
+    }
+
+    pub fn post(self, pattern: &str, handler: impl Handler + 'static) -> Self {
+        self.route(Method::Post, pattern, handler)
+    }
+
+    pub fn put(self, pattern: &str, handler: impl Handler + 'static) -> Self {
+        self.route(Method::Put, pattern, handler)
+    }
+
+    pub fn patch(self, pattern: &str, handler: impl Handler + 'static) -> Self {
+        self.route(Method::Patch, pattern, handler)
+    }
+
+    pub fn delete(self, pattern: &str, handler: impl Handler + 'static) -> Self {
+        self.route(Method::Delete, pattern, handler)
+    }
+
+    // 웹소켓 엔드포인트. 핸드셰이크는 GET 라우트로 처리하므로 미들웨어를 거치며,
+    // 101로 응답하면 서버가 커넥션을 handler에 넘긴다.
+    pub fn websocket(mut self, pattern: &str, handler: impl WebSocketHandler + 'static) -> Self {
+        self.routes.push(Route {
+            method: Method::Get,
+            pattern: parse_pattern(pattern),
+            handler: Box::new(websocket::handshake),
+            takeover: Some(Takeover::WebSocket(Box::new(handler))),
+        });
+        self
+    }
+
+    // Server-Sent Events 엔드포인트. 응답 헤더는 GET 라우트로 만들어 미들웨어를 거치며,
+    // 헤더를 보낸 뒤 서버가 본문을 handler에 맡긴다.
+    pub fn events(mut self, pattern: &str, handler: impl EventStreamHandler + 'static) -> Self {
+        self.routes.push(Route {
+            method: Method::Get,
+            pattern: parse_pattern(pattern),
+            handler: Box::new(sse::accept),
+            takeover: Some(Takeover::EventStream(Box::new(handler))),
+        });
+        self
+    }
+
+    // 일치하는 라우트가 없을 때 요청을 처리할 핸들러
+    pub fn fallback(mut self, handler: impl Handler + 'static) -> Self {
+        self.fallback = Box::new(handler);
+        self
+    }
+
+    // 미들웨어를 추가한다. 먼저 추가한 미들웨어가 바깥쪽에서 요청을 먼저 받는다.
+    pub fn wrap(mut self, middleware: impl Middleware + 'static) -> Self {
+        self.middlewares.push(Box::new(middleware));
+        self
+    }
+
+    // 요청을 미들웨어와 라우트 표에 통과시켜 응답을 만든다. 응답을 보내는 일은 서버가 맡는다.
+    pub fn dispatch(&self, req: &HttpRequest) -> HttpResponse {
+        Next::new(&self.middlewares, self).run(req)
+    }
+
+    // 미들웨어를 모두 거친 요청을 처리할 핸들러를 골라 응답을 만든다
+    pub(crate) fn resolve(&self, req: &HttpRequest) -> HttpResponse {
+        // OPTIONS * 는 서버 전체가 지원하는 메서드를 묻는다
+        if matches!(req.resource, Resource::Asterisk) {
+            return allow_response(StatusCode::Ok, self.routes.iter());
+        }
+
+        let segments = req.resource.segments();
+        let matched: Vec<(&Route, Params)> = self
+            .routes
+            .iter()
+            .filter_map(|route| route.matches(segments).map(|params| (route, params)))
+            .collect();
+        if matched.is_empty() {
+            return self.fallback.handle(req, &Params::default());
+        }
+
+        let found = matched.iter().find(|(route, _)| {
+            route.method == req.method
+                || (req.method == Method::Head && route.method == Method::Get)
+        });
+        if let Some((route, params)) = found {
+            return route.handler.handle(req, params);
+        }
+        // 예를 들어 /orders/events에는 /orders/{id}의 메서드를 알리지 않는다
+        let specificity = matched.iter().map(|(route, _)| route.specificity()).max();
+        let allowed = matched
+            .iter()
+            .map(|(route, _)| *route)
+            .filter(|route| Some(route.specificity()) == specificity);
+        // OPTIONS 요청에는 지원하는 메서드 목록을 반환한다
+        if req.method == Method::Options {
+            allow_response(StatusCode::Ok, allowed)
+        } else {
+            allow_response(StatusCode::MethodNotAllowed, allowed)
+        }
+    }
+}
+
+impl Router {
+    // 요청을 처리한 라우트가 웹소켓이나 이벤트 스트림 라우트면 커넥션을 넘겨받을 핸들러를 찾는다.
+    // resolve와 같은 순서로 비교하므로 응답 헤더를 만든 라우트의 핸들러다.
+    pub(crate) fn takeover(&self, req: &HttpRequest) -> Option<(&Takeover, Params)> {
+        if req.method != Method::Get {
+            return None;
+        }
+        let segments = req.resource.segments();
+        let (route, params) = self
+            .routes
+            .iter()
+            .filter(|route| route.method == Method::Get)
+            .find_map(|route| route.matches(segments).map(|params| (route, params)))?;
+        Some((route.takeover.as_ref()?, params))
+    }
+}
+
+// 라우트들의 메서드로 Allow 헤더를 채운 응답
+fn allow_response<'a>(status: StatusCode, routes: impl Iterator<Item = &'a Route>) -> HttpResponse {
+    let mut methods: Vec<Method> = Vec::new();
+    for route in routes {
+        if !methods.contains(&route.method) {
+            methods.push(route.method.clone());
+        }
+        if route.method == Method::Get && !methods.contains(&Method::Head) {
+            methods.push(Method::Head);
+        }
+    }
+    methods.push(Method::Options);
+
+    let allow = methods
+        .iter()
+        .map(Method::as_str)
+        .collect::<Vec<_>>()
+        .join(", ");
+    let mut headers = HeaderMap::new();
+    headers.insert("Allow", allow);
+    if status == StatusCode::MethodNotAllowed {
+        headers.insert("Content-Type", "text/plain");
+        let body = status.reason_phrase().to_string();
+        return HttpResponse::new(status, Some(headers), Some(body));
+    }
+    HttpResponse::new(status, Some(headers), None)
+}
+
+fn parse_pattern(pattern: &str) -> Vec<Segment> {
+    let pattern = pattern.strip_prefix('/').unwrap_or(pattern);
+    if pattern.is_empty() {
+        return Vec::new();
+    }
+    pattern
+        .split('/')
+        .map(
+            |segment| match segment.strip_prefix('{').and_then(|s| s.strip_suffix('}')) {
+                Some(name) => match name.strip_prefix('*') {
+                    Some(name) => Segment::CatchAll(name.to_string()),
+                    None => Segment::Param(name.to_string()),
+                },
+                None => Segment::Literal(segment.to_string()),
             },
-            // 메서드가 GET 요청이 아니면 404 페이지를 반환한다.
-            _ => {
-                let resp: HttpResponse = PageNotFoundHandler::handle(&req);
-                let _ = resp.send_response(stream);
-            },
+        )
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(method: &str, target: &str) -> HttpRequest {
+        HttpRequest::try_from(format!(
+            "{} {} HTTP/1.1\r\nHost: localhost\r\n\r\n",
+            method, target
+        ))
+        .unwrap()
+    }
+
+    fn echo_id(_req: &HttpRequest, params: &Params) -> HttpResponse {
+        HttpResponse::new(StatusCode::Ok, None, params.get("id").map(String::from))
+    }
+
+    fn echo_path(_req: &HttpRequest, params: &Params) -> HttpResponse {
+        HttpResponse::new(StatusCode::Ok, None, params.get("path").map(String::from))
+    }
+
+    fn body(resp: &HttpResponse) -> &[u8] {
+        resp.body().as_bytes().unwrap()
+    }
+
+    #[test]
+    fn test_path_params_are_captured() {
+        let router = Router::new()
+            .get("/api/shipping/orders/{id}", echo_id)
+            .get("/{*path}", echo_path);
+
+        let resp = router.dispatch(&request("GET", "/api/shipping/orders/42"));
+        assert_eq!(b"42", body(&resp));
+
+        let resp = router.dispatch(&request("GET", "/css/site%20main.css"));
+        assert_eq!(b"css/site main.css", body(&resp));
+
+        let resp = router.dispatch(&request("HEAD", "/"));
+        assert_eq!(StatusCode::Ok, resp.status());
+        assert_eq!(b"", body(&resp));
+    }
+
+    #[test]
+    fn test_method_mismatch_is_405_with_allow() {
+        let router = Router::new().get("/api/shipping/orders", echo_id).route(
+            Method::Post,
+            "/api/shipping/orders",
+            echo_id,
+        );
+
+        let resp = router.dispatch(&request("DELETE", "/api/shipping/orders"));
+        assert_eq!(StatusCode::MethodNotAllowed, resp.status());
+        assert_eq!(
+            Some("GET, HEAD, POST, OPTIONS"),
+            resp.headers().get("Allow")
+        );
+
+        let resp = router.dispatch(&request("OPTIONS", "/api/shipping/orders"));
+        assert_eq!(StatusCode::Ok, resp.status());
+        assert_eq!(
+            Some("GET, HEAD, POST, OPTIONS"),
+            resp.headers().get("Allow")
+        );
+    }
+
+    #[test]
+    fn test_allow_lists_most_specific_route() {
+        let router = Router::new()
+            .get("/api/shipping/orders/events", echo_id)
+            .get("/api/shipping/orders/{id}", echo_id)
+            .route(Method::Put, "/api/shipping/orders/{id}", echo_id)
+            .route(Method::Delete, "/api/shipping/orders/{id}", echo_id)
+            .route(Method::Post, "/{*path}", echo_path);
+
+        // 리터럴 세그먼트가 {id}와 {*path} 라우트의 메서드를 가린다
+        for method in ["PATCH", "OPTIONS"] {
+            let resp = router.dispatch(&request(method, "/api/shipping/orders/events"));
+            assert_eq!(Some("GET, HEAD, OPTIONS"), resp.headers().get("Allow"));
+        }
+        let resp = router.dispatch(&request("PATCH", "/api/shipping/orders/7"));
+        assert_eq!(StatusCode::MethodNotAllowed, resp.status());
+        assert_eq!(
+            Some("GET, HEAD, PUT, DELETE, OPTIONS"),
+            resp.headers().get("Allow")
+        );
+        let resp = router.dispatch(&request("GET", "/css/site.css"));
+        assert_eq!(Some("POST, OPTIONS"), resp.headers().get("Allow"));
+    }
+
+    #[test]
+    fn test_unmatched_path_is_404() {
+        let router = Router::new().get("/api/shipping/orders/{id}", echo_id);
+        for target in ["/api/shipping/orders", "/api/shipping/orders/1/items", "/"] {
+            let resp = router.dispatch(&request("GET", target));
+            assert_eq!(StatusCode::NotFound, resp.status(), "{}", target);
         }
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_websocket_route_handshakes_and_upgrades() {
+        fn feed(
+            _req: &HttpRequest,
+            _params: &Params,
+            _socket: &mut crate::websocket::WebSocket<'_>,
+        ) -> std::io::Result<()> {
+            Ok(())
+        }
+        let router = Router::new()
+            .websocket("/live/{room}", feed)
+            .get("/live/{room}/history", echo_id);
+
+        // 업그레이드 헤더가 없으면 핸드셰이크 핸들러가 426으로 답한다
+        let req = request("GET", "/live/a");
+        assert_eq!(StatusCode::UpgradeRequired, router.dispatch(&req).status());
+        let (takeover, params) = router.takeover(&req).unwrap();
+        assert!(matches!(takeover, Takeover::WebSocket(_)));
+        assert_eq!(Some("a"), params.get("room"));
+
+        assert!(router
+            .takeover(&request("GET", "/live/a/history"))
+            .is_none());
+        assert!(router.takeover(&request("HEAD", "/live/a")).is_none());
+        let resp = router.dispatch(&request("POST", "/live/a"));
+        assert_eq!(Some("GET, HEAD, OPTIONS"), resp.headers().get("Allow"));
+    }
+}
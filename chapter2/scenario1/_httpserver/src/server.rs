@@ -1,33 +1,892 @@
-use super::router::Router;
-use http::httprequest::HttpRequest;
-use std::io::prelude::*;
-use std::net::TcpListener;
-use std::str;
+use super::access_log::{AccessEntry, AccessLog};
+use super::connection_limit::{IpConnectionLimiter, IpSlot, StreamLimiter, StreamSlot};
+use super::pool::WorkerPool;
+use super::router::{Router, Takeover};
+use super::sse::{is_event_stream, EventStream};
+use super::tls::HttpsRedirect;
+use super::websocket::{Upgraded, WebSocket, CLOSE_NORMAL};
+use http::{
+    chunked::ChunkedWriter,
+    headers::HeaderMap,
+    httprequest::{HttpRequest, Limits, Method, ParseError},
+    httpresponse::HttpResponse,
+    status::StatusCode,
+};
+use rustls::{ServerConfig, ServerConnection, StreamOwned};
+use serde::Deserialize;
+use signal_hook::consts::{SIGINT, SIGTERM};
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime};
 
-pub struct Server<'a> {
-    socket_addr: &'a str,
+// 종료 신호를 확인하기 위해 accept 대기를 깨우는 간격
+const ACCEPT_POLL_INTERVAL: Duration = Duration::from_millis(50);
+// 거절 응답을 보낼 때 느린 클라이언트 때문에 accept 루프가 막히지 않도록 두는 제한
+pub(crate) const REJECT_WRITE_TIMEOUT: Duration = Duration::from_secs(1);
+
+const DEFAULT_WORKERS: usize = 8;
+const DEFAULT_QUEUE_CAPACITY: usize = 64;
+const DEFAULT_MAX_STREAMS: usize = 256;
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum WorkerModel {
+    // 고정 크기 스레드 풀이 커넥션을 하나씩 맡아 처리한다
+    Threads,
+    // tokio 런타임이 커넥션을 받고, 블로킹 스레드 풀에서 처리한다 (async 기능 필요)
+    Async,
+}
+
+// 커넥션마다 적용하는 시간 제한과 요청 크기 제한, 그리고 응답을 기록할 접근 로그
+#[derive(Clone)]
+pub(crate) struct ConnectionOptions {
+    // 요청과 요청 사이에 커넥션을 열어 두고 기다리는 최대 시간
+    keep_alive_timeout: Duration,
+    // 요청의 첫 바이트부터 본문 끝까지 읽는 데 허용하는 시간.
+    // 조금씩 보내며 커넥션을 붙잡는 클라이언트(slowloris)는 이 시간이 지나면 408을 받는다.
+    read_timeout: Duration,
+    // 클라이언트가 응답을 받아 가지 않을 때 쓰기를 기다리는 최대 시간
+    write_timeout: Duration,
+    limits: Limits,
+    // 클라이언트 IP 하나가 동시에 열 수 있는 커넥션 수. 0이면 제한하지 않는다.
+    max_connections_per_ip: usize,
+    access_log: Option<Arc<AccessLog>>,
+    // worker에서 넘겨받아 자기 스레드에서 도는 웹소켓과 이벤트 스트림 커넥션
+    streams: Arc<StreamLimiter>,
+}
+
+impl Default for ConnectionOptions {
+    fn default() -> Self {
+        ConnectionOptions {
+            keep_alive_timeout: Duration::from_secs(5),
+            read_timeout: Duration::from_secs(10),
+            write_timeout: Duration::from_secs(30),
+            limits: Limits::default(),
+            max_connections_per_ip: 0,
+            access_log: None,
+            streams: StreamLimiter::new(DEFAULT_MAX_STREAMS),
+        }
+    }
+}
+
+impl ConnectionOptions {
+    pub(crate) fn limiter(&self) -> Arc<IpConnectionLimiter> {
+        IpConnectionLimiter::new(self.max_connections_per_ip)
+    }
+
+    // worker에서 넘겨받은 커넥션이 모두 닫힐 때까지 기다린다
+    pub(crate) fn wait_for_streams(&self) {
+        self.streams.wait_idle();
+    }
+}
+
+// 리스너 하나가 받은 커넥션을 처리하는 방법. TLS 설정이 있으면 핸드셰이크부터 한다.
+pub(crate) struct Endpoint {
+    router: Arc<Router>,
+    tls: Option<Arc<ServerConfig>>,
+}
+
+impl Endpoint {
+    pub(crate) fn new(router: Arc<Router>, tls: Option<Arc<ServerConfig>>) -> Self {
+        Endpoint { router, tls }
+    }
+
+    fn scheme(&self) -> &'static str {
+        if self.tls.is_some() {
+            "https"
+        } else {
+            "http"
+        }
+    }
 }
 
-impl<'a> Server<'a> {
-    pub fn new(socket_addr: &'a str) -> Self {
-        Server { socket_addr }
+pub(crate) struct Listener {
+    pub(crate) addr: String,
+    pub(crate) endpoint: Arc<Endpoint>,
+}
+
+impl Listener {
+    pub(crate) fn describe(&self) -> String {
+        format!("{}://{}", self.endpoint.scheme(), self.addr)
+    }
+}
+
+pub struct Server {
+    listeners: Vec<Listener>,
+    router: Arc<Router>,
+    workers: usize,
+    queue_capacity: usize,
+    worker_model: WorkerModel,
+    options: ConnectionOptions,
+}
+
+impl Server {
+    pub fn new(router: Router) -> Self {
+        Server {
+            listeners: Vec::new(),
+            router: Arc::new(router),
+            workers: DEFAULT_WORKERS,
+            queue_capacity: DEFAULT_QUEUE_CAPACITY,
+            worker_model: WorkerModel::Threads,
+            options: ConnectionOptions::default(),
+        }
+    }
+
+    // 평문 HTTP로 요청을 받을 주소를 더한다
+    pub fn listen(mut self, addr: impl Into<String>) -> Self {
+        let endpoint = Endpoint::new(Arc::clone(&self.router), None);
+        self.add_listener(addr.into(), endpoint);
+        self
+    }
+
+    // TLS로 요청을 받을 주소를 더한다. 인증서는 설정의 SNI 선택기가 고른다.
+    pub fn listen_tls(mut self, addr: impl Into<String>, config: Arc<ServerConfig>) -> Self {
+        let endpoint = Endpoint::new(Arc::clone(&self.router), Some(config));
+        self.add_listener(addr.into(), endpoint);
+        self
+    }
+
+    // 이 주소로 오는 평문 요청은 처리하지 않고 모두 같은 호스트의 HTTPS 포트로 보낸다
+    pub fn redirect_to_https(mut self, addr: impl Into<String>, https_port: u16) -> Self {
+        let router = Router::new().fallback(HttpsRedirect::new(https_port));
+        self.add_listener(addr.into(), Endpoint::new(Arc::new(router), None));
+        self
+    }
+
+    fn add_listener(&mut self, addr: String, endpoint: Endpoint) {
+        self.listeners.push(Listener {
+            addr,
+            endpoint: Arc::new(endpoint),
+        });
+    }
+
+    // 동시에 처리하는 커넥션 수와, 그 밖에 처리를 기다릴 수 있는 커넥션 수를 정한다.
+    // 둘 다 차 있으면 새 커넥션에는 503을 보내고 닫는다.
+    pub fn workers(mut self, workers: usize, queue_capacity: usize) -> Self {
+        self.workers = workers.max(1);
+        self.queue_capacity = queue_capacity;
+        self
+    }
+
+    pub fn worker_model(mut self, worker_model: WorkerModel) -> Self {
+        self.worker_model = worker_model;
+        self
+    }
+
+    // 다음 요청을 기다리는 시간과 응답 쓰기를 기다리는 시간
+    pub fn timeouts(mut self, keep_alive: Duration, read: Duration, write: Duration) -> Self {
+        self.options.keep_alive_timeout = keep_alive;
+        self.options.read_timeout = read;
+        self.options.write_timeout = write;
+        self
+    }
+
+    // 요청 행, 헤더, 본문의 최대 크기. 넘으면 414, 431, 413으로 응답하고 커넥션을 닫는다.
+    pub fn limits(mut self, limits: Limits) -> Self {
+        self.options.limits = limits;
+        self
+    }
+
+    // 같은 IP에서 이 수보다 많은 커넥션을 열면 429로 거절한다
+    pub fn max_connections_per_ip(mut self, max: usize) -> Self {
+        self.options.max_connections_per_ip = max;
+        self
+    }
+
+    // 웹소켓과 이벤트 스트림을 합쳐 이 수보다 많이 열려 하면 503으로 거절한다. 0이면 제한하지 않는다.
+    pub fn max_streams(mut self, max: usize) -> Self {
+        self.options.streams = StreamLimiter::new(max);
+        self
+    }
+
+    // 응답마다 접근 로그를 한 줄씩 남긴다
+    pub fn access_log(mut self, access_log: AccessLog) -> Self {
+        self.options.access_log = Some(Arc::new(access_log));
+        self
     }
 
     pub fn run(&self) {
-        // 소켓 주소를 리스닝하는 서버를 시작한다.
-        let connection_listener = TcpListener::bind(self.socket_addr).unwrap();
-        println!("Running on {}", self.socket_addr);
+        if self.listeners.is_empty() {
+            println!("No listen address configured");
+            return;
+        }
+        // SIGINT/SIGTERM을 받으면 새 커넥션을 그만 받고, 처리 중인 요청을 마친 뒤 종료한다.
+        let shutdown = Arc::new(AtomicBool::new(false));
+        for signal in [SIGINT, SIGTERM] {
+            signal_hook::flag::register(signal, Arc::clone(&shutdown)).unwrap();
+        }
+
+        let result = match self.worker_model {
+            WorkerModel::Threads => self.run_threads(shutdown),
+            #[cfg(feature = "async")]
+            WorkerModel::Async => super::async_server::run(
+                &self.listeners,
+                self.options.clone(),
+                self.workers,
+                self.queue_capacity,
+                shutdown,
+            ),
+            #[cfg(not(feature = "async"))]
+            WorkerModel::Async => {
+                println!("Async worker model needs the `async` feature, using threads");
+                self.run_threads(shutdown)
+            }
+        };
+        if let Err(e) = result {
+            println!("Server error: {}", e);
+        }
+    }
+
+    fn run_threads(&self, shutdown: Arc<AtomicBool>) -> io::Result<()> {
+        // 주소마다 리스닝하는 소켓을 연다.
+        // 종료 신호를 확인할 수 있도록 accept가 블록되지 않게 한다
+        let mut listeners = Vec::new();
+        for listener in &self.listeners {
+            let socket = TcpListener::bind(&listener.addr)?;
+            socket.set_nonblocking(true)?;
+            println!("Listening on {}", listener.describe());
+            listeners.push((socket, Arc::clone(&listener.endpoint)));
+        }
+        println!("Running with {} workers", self.workers);
+
+        let limiter = self.options.limiter();
+        let pool = {
+            let shutdown = Arc::clone(&shutdown);
+            let options = self.options.clone();
+            WorkerPool::new(
+                self.workers,
+                self.queue_capacity,
+                // 커넥션을 다 처리하면 IP 자리도 함께 돌려준다
+                move |(stream, endpoint, slot): (TcpStream, Arc<Endpoint>, IpSlot)| {
+                    handle_connection(stream, &endpoint, &options, &shutdown, Some(slot))
+                },
+            )
+        };
 
         // 루프 안에서 유입되는 커넥션을 리스닝한다.
-        for stream in connection_listener.incoming() {
-            let mut stream = stream.unwrap();
-            println!("Connection established");
-            let mut read_buffer = [0; 90];
-            stream.read(&mut read_buffer).unwrap();
-            // HTTP 요청을 러스트 데이터 구조를 변환한다.
-            let req: HttpRequest = String::from_utf8(read_buffer.to_vec()).unwrap().into();
-            // 요청을 적절한 핸들러로 라우팅한다.
-            Router::route(req, &mut stream);
+        while !shutdown.load(Ordering::SeqCst) {
+            let mut idle = true;
+            for (socket, endpoint) in &listeners {
+                match socket.accept() {
+                    Ok((stream, peer)) => {
+                        idle = false;
+                        stream.set_nonblocking(false)?;
+                        // 같은 IP가 이미 커넥션을 너무 많이 열었으면 거절한다
+                        let Some(slot) = limiter.try_acquire(peer.ip()) else {
+                            reject(stream, endpoint, StatusCode::TooManyRequests);
+                            continue;
+                        };
+                        // 모든 worker가 바쁘고 대기열도 차 있으면 거절한다
+                        if let Err((stream, endpoint, _)) =
+                            pool.try_send((stream, Arc::clone(endpoint), slot))
+                        {
+                            reject(stream, &endpoint, StatusCode::ServiceUnavailable);
+                        }
+                    }
+                    Err(e) if e.kind() == io::ErrorKind::WouldBlock => {}
+                    Err(e) => println!("Failed to accept connection: {}", e),
+                }
+            }
+            if idle {
+                thread::sleep(ACCEPT_POLL_INTERVAL);
+            }
+        }
+
+        println!("Shutting down, waiting for in-flight requests");
+        // 풀을 버리면 처리 중이거나 대기 중인 커넥션이 끝날 때까지 기다린다
+        drop(pool);
+        self.options.wait_for_streams();
+        Ok(())
+    }
+}
+
+// 커넥션 하나를 처리하는 동안 쓰는 값들. 웹소켓이나 이벤트 스트림으로 넘어간 커넥션은 이것을 들고 다른 스레드로 옮겨 간다.
+struct ConnectionContext {
+    endpoint: Arc<Endpoint>,
+    options: ConnectionOptions,
+    shutdown: Arc<AtomicBool>,
+    peer: Option<SocketAddr>,
+    deadline: RequestDeadline,
+    // 커넥션을 닫을 때 IP 자리도 함께 돌려준다
+    _ip_slot: Option<IpSlot>,
+}
+
+impl ConnectionContext {
+    // 응답을 보낸 뒤 접근 로그를 남긴다. 시간은 요청의 첫 바이트를 받은 때부터 잰다.
+    fn log(&self, req: Option<&HttpRequest>, status: StatusCode, bytes: u64, started: Instant) {
+        if let Some(access_log) = &self.options.access_log {
+            access_log.log(&AccessEntry {
+                remote_addr: self.peer,
+                request: req,
+                status: status.as_u16(),
+                bytes,
+                duration: started.elapsed(),
+                time: SystemTime::now(),
+            });
+        }
+    }
+}
+
+pub(crate) fn handle_connection(
+    stream: TcpStream,
+    endpoint: &Arc<Endpoint>,
+    options: &ConnectionOptions,
+    shutdown: &Arc<AtomicBool>,
+    ip_slot: Option<IpSlot>,
+) {
+    let ctx = ConnectionContext {
+        endpoint: Arc::clone(endpoint),
+        options: options.clone(),
+        shutdown: Arc::clone(shutdown),
+        peer: stream.peer_addr().ok(),
+        deadline: RequestDeadline::default(),
+        _ip_slot: ip_slot,
+    };
+    // 응답을 보내다 실패하면 (예: 클라이언트가 먼저 끊음) 기록만 하고 커넥션을 닫는다.
+    let result = stream
+        .set_write_timeout(Some(options.write_timeout))
+        .and_then(|()| {
+            let stream = TimedStream {
+                stream,
+                deadline: ctx.deadline.clone(),
+                idle_timeout: options.keep_alive_timeout,
+            };
+            match &endpoint.tls {
+                Some(config) => serve_tls(stream, config, ctx),
+                None => serve_connection(stream, ctx),
+            }
+        });
+    if let Err(e) = result {
+        println!("Connection error: {}", e);
+    }
+}
+
+// 처리할 여유가 없는 커넥션에 보낼 503이나 429 응답.
+// TLS 커넥션은 핸드셰이크에 드는 비용을 아끼기 위해 응답 없이 닫으므로 None을 돌려준다.
+pub(crate) fn rejection(endpoint: &Endpoint, status: StatusCode) -> Option<Vec<u8>> {
+    if endpoint.tls.is_some() {
+        return None;
+    }
+    let mut response = Vec::new();
+    HttpResponse::builder()
+        .status(status)
+        .header("Retry-After", "1")
+        .header("Connection", "close")
+        .body(status.reason_phrase())
+        .build()
+        .send_response(&mut response)
+        .ok()?;
+    Some(response)
+}
+
+// 처리할 여유가 없는 커넥션에 503이나 429를 보내고 닫는다.
+fn reject(mut stream: TcpStream, endpoint: &Endpoint, status: StatusCode) {
+    let Some(response) = rejection(endpoint, status) else {
+        return;
+    };
+    let _ = stream.set_write_timeout(Some(REJECT_WRITE_TIMEOUT));
+    if let Err(e) = stream.write_all(&response) {
+        println!("Failed to reject connection: {}", e);
+    }
+}
+
+// TLS 핸드셰이크는 첫 읽기 때 일어나므로 그 뒤로는 평문 커넥션과 똑같이 처리한다.
+fn serve_tls(
+    stream: TimedStream,
+    config: &Arc<ServerConfig>,
+    ctx: ConnectionContext,
+) -> io::Result<()> {
+    let conn = ServerConnection::new(Arc::clone(config)).map_err(io::Error::other)?;
+    serve_connection(StreamOwned::new(conn, stream), ctx)
+}
+
+// 요청을 처리한 스레드와 다른 스레드에서 닫을 수도 있는 커넥션
+trait Connection: Read + Write + Send + 'static {
+    fn close(&mut self) -> io::Result<()>;
+}
+
+impl Connection for TimedStream {
+    fn close(&mut self) -> io::Result<()> {
+        self.flush()
+    }
+}
+
+// 끝낼 때는 close_notify를 보내 클라이언트가 응답이 잘리지 않았음을 알 수 있게 한다.
+impl Connection for StreamOwned<ServerConnection, TimedStream> {
+    fn close(&mut self) -> io::Result<()> {
+        self.conn.send_close_notify();
+        self.flush()
+    }
+}
+
+// 웹소켓 핸드셰이크나 이벤트 스트림의 응답 헤더를 보낸 뒤 커넥션과 함께 넘기는 요청
+struct Handoff {
+    req: HttpRequest,
+    status: StatusCode,
+    started: Instant,
+    _slot: StreamSlot,
+}
+
+// 커넥션 하나에서 요청을 처리하고, 웹소켓이나 이벤트 스트림으로 넘어가면 커넥션을 새 스레드에 넘긴다.
+// worker는 응답 헤더를 보내자마자 풀로 돌아가 다른 커넥션을 받는다.
+fn serve_connection<S: Connection>(stream: S, ctx: ConnectionContext) -> io::Result<()> {
+    let mut connection = BufReader::new(stream);
+    let Some(handoff) = serve_requests(&mut connection, &ctx)? else {
+        return connection.get_mut().close();
+    };
+    // 스레드를 띄우지 못하면 커넥션은 여기서 닫힌다
+    thread::Builder::new()
+        .name("stream".to_string())
+        .spawn(move || {
+            if let Err(e) = serve_takeover(connection, &ctx, handoff) {
+                println!("Connection error: {}", e);
+            }
+        })
+        .map(|_| ())
+}
+
+// 넘겨받은 커넥션을 라우터의 핸들러에 맡기고, 핸들러가 끝나면 닫는다
+fn serve_takeover<S: Connection>(
+    mut connection: BufReader<S>,
+    ctx: &ConnectionContext,
+    handoff: Handoff,
+) -> io::Result<()> {
+    let Handoff {
+        req,
+        status,
+        started,
+        ..
+    } = &handoff;
+    match ctx.endpoint.router.takeover(req) {
+        Some((Takeover::WebSocket(handler), params)) => {
+            ctx.log(Some(req), *status, 0, *started);
+            let mut upgraded = Upgraded(connection);
+            let mut socket = WebSocket::new(&mut upgraded, ctx.deadline.clone(), &ctx.shutdown)
+                .read_timeout(ctx.options.read_timeout)
+                .max_message_size(ctx.options.limits.max_body_bytes);
+            handler
+                .handle(req, &params, &mut socket)
+                .and_then(|()| socket.close(CLOSE_NORMAL, ""))?;
+            upgraded.0.get_mut().close()
+        }
+        Some((Takeover::EventStream(handler), params)) => {
+            let mut writer = ChunkedWriter::new(connection.get_mut());
+            let mut stream = EventStream::new(&mut writer, &ctx.shutdown);
+            let result = handler.handle(req, &params, &mut stream);
+            // 이벤트 스트림은 끝날 때 보낸 바이트 수와 함께 남긴다
+            ctx.log(Some(req), *status, stream.written(), *started);
+            result?;
+            writer.finish(&HeaderMap::new())?;
+            connection.get_mut().close()
+        }
+        None => Ok(()),
+    }
+}
+
+// 처리할 여유가 없는 요청에 503을 보낸다
+fn send_unavailable<S: Write>(
+    stream: &mut S,
+    ctx: &ConnectionContext,
+    req: &HttpRequest,
+    started: Instant,
+) -> io::Result<()> {
+    let status = StatusCode::ServiceUnavailable;
+    let resp = HttpResponse::builder()
+        .status(status)
+        .header("Retry-After", "1")
+        .header("Connection", "close")
+        .body(status.reason_phrase())
+        .build();
+    let bytes = resp.send_response(stream)?;
+    ctx.log(Some(req), status, bytes, started);
+    Ok(())
+}
+
+// 커넥션 하나에서 요청을 차례로 읽어 처리한다.
+// 클라이언트가 응답을 기다리지 않고 여러 요청을 잇달아 보내도(파이프라이닝)
+// 버퍼에 남은 바이트부터 다음 요청으로 읽으므로 보낸 순서대로 응답한다.
+fn serve_requests<S: Read + Write>(
+    connection: &mut BufReader<S>,
+    ctx: &ConnectionContext,
+) -> io::Result<Option<Handoff>> {
+    let options = &ctx.options;
+    let deadline = &ctx.deadline;
+    loop {
+        // 다음 요청의 첫 바이트를 keep-alive 시간 동안 기다린다
+        deadline.clear();
+        match connection.fill_buf().map(|buf| buf.is_empty()) {
+            // 클라이언트가 커넥션을 닫았다
+            Ok(true) => return Ok(None),
+            Ok(false) => {}
+            // 유휴 시간 동안 다음 요청이 오지 않았다
+            Err(e) if is_timeout(&e) => return Ok(None),
+            // 커넥션이 끊겼거나 TLS 핸드셰이크가 실패했다
+            Err(e) => return Err(e),
         }
+
+        // 요청을 읽기 시작했으면 정해진 시간 안에 끝까지 받아야 한다.
+        // HTTP 요청을 러스트 데이터 구조를 변환한다.
+        let started = Instant::now();
+        deadline.start(options.read_timeout);
+        let result = HttpRequest::from_reader_with_limits(&mut *connection, &options.limits);
+        deadline.clear();
+        let req = match result {
+            Ok(req) => req,
+            Err(ParseError::ConnectionClosed) => return Ok(None),
+            Err(ParseError::Io(e)) if !is_timeout(&e) => return Err(e),
+            // 요청을 파싱할 수 없거나 너무 크면 에러 응답을 보내고 커넥션을 닫는다.
+            // 읽지 않은 나머지 요청이 다음 요청으로 해석되지 않도록 커넥션을 이어 쓰지 않는다.
+            Err(e) => {
+                println!("Invalid request: {}", e);
+                let status = match e {
+                    ParseError::Io(_) => StatusCode::RequestTimeout,
+                    e => e.status(),
+                };
+                let mut resp = HttpResponse::new(status, None, Some(status.reason_phrase().into()));
+                resp.headers_mut().insert("Connection", "close");
+                let bytes = resp.send_response(connection.get_mut())?;
+                ctx.log(None, status, bytes, started);
+                return Ok(None);
+            }
+        };
+
+        // 요청을 적절한 핸들러로 라우팅한다.
+        // 종료 중이면 이번 응답을 끝으로 커넥션을 닫는다.
+        let keep_alive = req.keep_alive() && !ctx.shutdown.load(Ordering::SeqCst);
+        let mut resp = ctx.endpoint.router.dispatch(&req);
+
+        // 웹소켓 핸드셰이크에 성공했거나 이벤트 스트림을 시작했으면 응답 헤더를 보낸 뒤 커넥션을 넘긴다
+        let takeover = match ctx.endpoint.router.takeover(&req) {
+            Some((Takeover::WebSocket(_), _)) => resp.status() == StatusCode::SwitchingProtocols,
+            Some((Takeover::EventStream(_), _)) => is_event_stream(&resp),
+            None => false,
+        };
+        if takeover {
+            // 넘겨받을 수 있는 커넥션 수를 넘으면 대신 503을 보낸다
+            let Some(slot) = options.streams.try_acquire() else {
+                send_unavailable(connection.get_mut(), ctx, &req, started)?;
+                return Ok(None);
+            };
+            // 이벤트 스트림은 핸들러가 끝나면 커넥션을 닫는다
+            if resp.status() != StatusCode::SwitchingProtocols {
+                resp.headers_mut().insert("Connection", "close");
+            }
+            resp.send_head(connection.get_mut())?;
+            return Ok(Some(Handoff {
+                req,
+                status: resp.status(),
+                started,
+                _slot: slot,
+            }));
+        }
+
+        resp.headers_mut().insert(
+            "Connection",
+            if keep_alive { "keep-alive" } else { "close" },
+        );
+        // HEAD 요청에는 헤더만 보낸다
+        let status = resp.status();
+        let bytes = if req.method == Method::Head {
+            resp.send_head(connection.get_mut())?;
+            0
+        } else {
+            resp.send_response(connection.get_mut())?
+        };
+        ctx.log(Some(&req), status, bytes, started);
+
+        if !keep_alive {
+            return Ok(None);
+        }
+    }
+}
+
+pub(crate) fn is_timeout(e: &io::Error) -> bool {
+    matches!(
+        e.kind(),
+        io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut
+    )
+}
+
+// 요청 하나를 다 읽어야 하는 시각. 웹소켓이나 이벤트 스트림으로 넘어간 커넥션과 함께 다른 스레드로 옮겨 갈 수 있다.
+#[derive(Clone, Default)]
+pub(crate) struct RequestDeadline(Arc<Mutex<Option<Instant>>>);
+
+impl RequestDeadline {
+    pub(crate) fn start(&self, timeout: Duration) {
+        *self.0.lock().unwrap() = Some(Instant::now() + timeout);
+    }
+
+    pub(crate) fn clear(&self) {
+        *self.0.lock().unwrap() = None;
+    }
+
+    fn get(&self) -> Option<Instant> {
+        *self.0.lock().unwrap()
+    }
+}
+
+// 소켓을 읽을 때마다 읽기 제한 시간을 다시 건다. 요청을 기다리는 동안에는 keep-alive 시간을,
+// 요청을 읽는 동안에는 마감 시각까지 남은 시간을 써서 한 바이트씩 보내는 클라이언트도 마감에 걸리게 한다.
+struct TimedStream {
+    stream: TcpStream,
+    deadline: RequestDeadline,
+    idle_timeout: Duration,
+}
+
+impl Read for TimedStream {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let timeout = match self.deadline.get() {
+            Some(deadline) => deadline
+                .checked_duration_since(Instant::now())
+                .filter(|left| !left.is_zero())
+                .ok_or(io::ErrorKind::TimedOut)?,
+            None => self.idle_timeout,
+        };
+        self.stream.set_read_timeout(Some(timeout))?;
+        self.stream.read(buf)
+    }
+}
+
+impl Write for TimedStream {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.stream.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.stream.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::router::Params;
+    use crate::websocket::Message;
+    use std::net::Shutdown;
+
+    // 커넥션 하나를 주어진 설정으로 처리하는 서버를 띄우고 주소를 돌려준다
+    fn serve_one(options: ConnectionOptions) -> (String, thread::JoinHandle<()>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap().to_string();
+        let handle = thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let router = Router::new().get("/", |_req: &HttpRequest, _params: &_| {
+                HttpResponse::new(StatusCode::Ok, None, Some("ok".into()))
+            });
+            let endpoint = Arc::new(Endpoint::new(Arc::new(router), None));
+            let shutdown = Arc::new(AtomicBool::new(false));
+            handle_connection(stream, &endpoint, &options, &shutdown, None);
+        });
+        (addr, handle)
+    }
+
+    // run_threads로 서버를 띄우고 요청을 받을 수 있을 때까지 기다린다
+    fn run_server(server: Server) -> (String, Arc<AtomicBool>, thread::JoinHandle<()>) {
+        let addr = TcpListener::bind("127.0.0.1:0")
+            .and_then(|listener| listener.local_addr())
+            .unwrap()
+            .to_string();
+        let server = server.listen(addr.clone());
+        let shutdown = Arc::new(AtomicBool::new(false));
+        let handle = {
+            let shutdown = Arc::clone(&shutdown);
+            thread::spawn(move || server.run_threads(shutdown).unwrap())
+        };
+        while TcpStream::connect(&addr).is_err() {
+            thread::sleep(Duration::from_millis(10));
+        }
+        (addr, shutdown, handle)
+    }
+
+    // 요청을 보내고 응답 헤더까지만 읽는다
+    fn send_head(addr: &str, request: &str) -> (TcpStream, String) {
+        let mut client = TcpStream::connect(addr).unwrap();
+        client.write_all(request.as_bytes()).unwrap();
+        let mut head = Vec::new();
+        let mut byte = [0];
+        while !head.ends_with(b"\r\n\r\n") && client.read(&mut byte).unwrap() == 1 {
+            head.push(byte[0]);
+        }
+        (client, String::from_utf8(head).unwrap())
+    }
+
+    fn read_all(stream: &mut TcpStream) -> String {
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        response
+    }
+
+    #[test]
+    fn test_slow_request_gets_408() {
+        let options = ConnectionOptions {
+            read_timeout: Duration::from_millis(300),
+            ..ConnectionOptions::default()
+        };
+        let (addr, server) = serve_one(options);
+        let mut client = TcpStream::connect(addr).unwrap();
+        // 읽기 제한보다 짧은 간격으로 조금씩 보내도 전체 마감에 걸린다
+        for part in ["GET / HT", "TP/1.1\r\n", "Host: a\r\n"] {
+            client.write_all(part.as_bytes()).unwrap();
+            thread::sleep(Duration::from_millis(120));
+        }
+        let response = read_all(&mut client);
+        assert!(response.starts_with("HTTP/1.1 408 Request Timeout\r\n"));
+        assert!(response.contains("Connection: close\r\n"));
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn test_idle_connection_closes_quietly() {
+        let options = ConnectionOptions {
+            keep_alive_timeout: Duration::from_millis(100),
+            ..ConnectionOptions::default()
+        };
+        let (addr, server) = serve_one(options);
+        let mut client = TcpStream::connect(addr).unwrap();
+        client
+            .write_all(b"GET / HTTP/1.1\r\nHost: a\r\n\r\n")
+            .unwrap();
+        let response = read_all(&mut client);
+        assert!(response.starts_with("HTTP/1.1 200 OK\r\n"));
+        assert!(response.ends_with("ok"));
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn test_oversized_request_line_gets_414() {
+        let options = ConnectionOptions {
+            limits: Limits {
+                max_request_line: 32,
+                ..Limits::default()
+            },
+            ..ConnectionOptions::default()
+        };
+        let (addr, server) = serve_one(options);
+        let mut client = TcpStream::connect(addr).unwrap();
+        let request = format!("GET /{} HTTP/1.1\r\nHost: a\r\n\r\n", "a".repeat(64));
+        client.write_all(request.as_bytes()).unwrap();
+        client.shutdown(Shutdown::Write).unwrap();
+        assert!(read_all(&mut client).starts_with("HTTP/1.1 414 URI Too Long\r\n"));
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn test_http_2_request_line_gets_505() {
+        let (addr, server) = serve_one(ConnectionOptions::default());
+        let mut client = TcpStream::connect(addr).unwrap();
+        client
+            .write_all(b"GET / HTTP/2.0\r\nHost: a\r\n\r\n")
+            .unwrap();
+        client.shutdown(Shutdown::Write).unwrap();
+        assert!(read_all(&mut client).starts_with("HTTP/1.1 505 HTTP Version Not Supported\r\n"));
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn test_unknown_transfer_coding_gets_501() {
+        let (addr, server) = serve_one(ConnectionOptions::default());
+        let mut client = TcpStream::connect(addr).unwrap();
+        client
+            .write_all(b"POST / HTTP/1.1\r\nHost: a\r\nTransfer-Encoding: gzip\r\n\r\n")
+            .unwrap();
+        client.shutdown(Shutdown::Write).unwrap();
+        let response = read_all(&mut client);
+        assert!(response.starts_with("HTTP/1.1 501 Not Implemented\r\n"));
+        assert!(response.contains("Connection: close\r\n"));
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn test_websockets_do_not_hold_workers() {
+        let router = Router::new()
+            .get("/", |_req: &HttpRequest, _params: &_| {
+                HttpResponse::new(StatusCode::Ok, None, Some("ok".into()))
+            })
+            .websocket(
+                "/ws",
+                |_req: &HttpRequest, _params: &Params, socket: &mut WebSocket<'_>| {
+                    while !matches!(
+                        socket.read_message(Duration::from_secs(1))?,
+                        Some(Message::Close(_))
+                    ) {}
+                    Ok(())
+                },
+            );
+        let (addr, shutdown, server) = run_server(Server::new(router).workers(2, 2));
+
+        // worker와 대기열을 합친 것보다 많은 웹소켓을 열어 둔다
+        let upgrade = "GET /ws HTTP/1.1\r\nHost: a\r\nUpgrade: websocket\r\nConnection: Upgrade\r\n\
+                       Sec-WebSocket-Key: dGhlIHNhbXBsZSBub25jZQ==\r\nSec-WebSocket-Version: 13\r\n\r\n";
+        let sockets: Vec<TcpStream> = (0..6)
+            .map(|_| {
+                let (socket, head) = send_head(&addr, upgrade);
+                assert!(head.starts_with("HTTP/1.1 101 Switching Protocols\r\n"));
+                socket
+            })
+            .collect();
+
+        // 웹소켓이 열려 있어도 일반 요청은 처리된다
+        for _ in 0..3 {
+            let (mut client, head) = send_head(
+                &addr,
+                "GET / HTTP/1.1\r\nHost: a\r\nConnection: close\r\n\r\n",
+            );
+            assert!(head.starts_with("HTTP/1.1 200 OK\r\n"));
+            assert_eq!("ok", read_all(&mut client));
+        }
+
+        drop(sockets);
+        shutdown.store(true, Ordering::SeqCst);
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn test_event_streams_beyond_cap_get_503() {
+        let router = Router::new()
+            .get("/", |_req: &HttpRequest, _params: &_| {
+                HttpResponse::new(StatusCode::Ok, None, Some("ok".into()))
+            })
+            .events(
+                "/events",
+                |_req: &HttpRequest, _params: &Params, stream: &mut EventStream<'_>| {
+                    while stream.keep_alive()? {
+                        thread::sleep(Duration::from_millis(50));
+                    }
+                    Ok(())
+                },
+            );
+        let (addr, shutdown, server) = run_server(Server::new(router).workers(2, 2).max_streams(3));
+
+        // 상한까지는 worker 수보다 많아도 이벤트 스트림을 연다
+        let request = "GET /events HTTP/1.1\r\nHost: a\r\nAccept: text/event-stream\r\n\r\n";
+        let streams: Vec<TcpStream> = (0..3)
+            .map(|_| {
+                let (stream, head) = send_head(&addr, request);
+                assert!(head.starts_with("HTTP/1.1 200 OK\r\n"));
+                assert!(head.contains("Connection: close\r\n"));
+                stream
+            })
+            .collect();
+
+        // 상한을 넘으면 503으로 거절하고 커넥션을 닫는다
+        let (mut rejected, head) = send_head(&addr, request);
+        assert!(head.starts_with("HTTP/1.1 503 Service Unavailable\r\n"));
+        assert!(head.contains("Retry-After: 1\r\n"));
+        assert_eq!("Service Unavailable", read_all(&mut rejected));
+
+        // 일반 요청은 그대로 처리된다
+        let (mut client, head) = send_head(
+            &addr,
+            "GET / HTTP/1.1\r\nHost: a\r\nConnection: close\r\n\r\n",
+        );
+        assert!(head.starts_with("HTTP/1.1 200 OK\r\n"));
+        assert_eq!("ok", read_all(&mut client));
+
+        shutdown.store(true, Ordering::SeqCst);
+        server.join().unwrap();
+        drop(streams);
     }
 }
@@ -0,0 +1,229 @@
+use super::router::Params;
+use http::{
+    body::Body,
+    httprequest::{HttpRequest, Version},
+    httpresponse::HttpResponse,
+    status::StatusCode,
+};
+use std::io::{self, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+
+// 보낼 이벤트가 없을 때 주석 행을 보내는 간격.
+// 중간 프록시가 조용한 커넥션을 끊지 않게 하고, 떠난 클라이언트를 쓰기 실패로 알아챈다.
+const KEEP_ALIVE_INTERVAL: Duration = Duration::from_secs(15);
+
+// 이벤트 스트림 응답의 본문을 쓴다. 응답 헤더를 보낸 worker는 풀로 돌아가고, 핸들러는 커넥션마다
+// 따로 띄운 스레드에서 돈다. 돌아오면 서버가 본문을 끝내고 커넥션을 닫는다. 클라이언트는 다시 연결해 이어 받는다.
+pub trait EventStreamHandler: Send + Sync {
+    fn handle(
+        &self,
+        req: &HttpRequest,
+        params: &Params,
+        stream: &mut EventStream<'_>,
+    ) -> io::Result<()>;
+}
+
+impl<F> EventStreamHandler for F
+where
+    F: Fn(&HttpRequest, &Params, &mut EventStream<'_>) -> io::Result<()> + Send + Sync,
+{
+    fn handle(
+        &self,
+        req: &HttpRequest,
+        params: &Params,
+        stream: &mut EventStream<'_>,
+    ) -> io::Result<()> {
+        self(req, params, stream)
+    }
+}
+
+// 이벤트 스트림 요청에 보낼 응답 헤더. 길이를 모르는 본문이므로 chunked로 나가고,
+// 본문은 서버가 커넥션을 핸들러에 넘겨 이어 쓴다. chunked가 없는 HTTP/1.0에는 505로 답한다.
+pub fn accept(req: &HttpRequest, _params: &Params) -> HttpResponse {
+    if req.version == Version::V1_0 {
+        return HttpResponse::builder()
+            .status(StatusCode::HttpVersionNotSupported)
+            .header("Content-Type", "text/plain")
+            .body("event streams need HTTP/1.1")
+            .build();
+    }
+    HttpResponse::builder()
+        .header("Content-Type", "text/event-stream")
+        .header("Cache-Control", "no-cache")
+        .body(Body::from_reader(io::empty(), None))
+        .build()
+}
+
+// 응답이 이벤트 스트림의 헤더인지 확인한다
+pub(crate) fn is_event_stream(resp: &HttpResponse) -> bool {
+    resp.status() == StatusCode::Ok
+        && resp
+            .headers()
+            .content_type()
+            .is_some_and(|t| t.eq_ignore_ascii_case("text/event-stream"))
+}
+
+// 이벤트 하나 (HTML Living Standard 9.2.5). data는 줄마다 data: 행 하나가 된다.
+// 데이터 없이 retry만 실으면 클라이언트의 재연결 대기 시간만 바꾼다.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Event {
+    id: Option<String>,
+    event: Option<String>,
+    data: Option<String>,
+    retry: Option<Duration>,
+}
+
+impl Event {
+    pub fn new(data: impl Into<String>) -> Self {
+        Event {
+            data: Some(data.into()),
+            ..Event::default()
+        }
+    }
+
+    // 클라이언트가 다시 연결할 때 Last-Event-ID 헤더로 돌려보내는 id
+    pub fn id(mut self, id: impl Into<String>) -> Self {
+        self.id = Some(id.into());
+        self
+    }
+
+    // 이벤트 이름. 없으면 클라이언트에서 message 이벤트가 된다.
+    pub fn event(mut self, event: impl Into<String>) -> Self {
+        self.event = Some(event.into());
+        self
+    }
+
+    // 커넥션이 끊겼을 때 다시 연결하기 전에 기다릴 시간
+    pub fn retry(mut self, retry: Duration) -> Self {
+        self.retry = Some(retry);
+        self
+    }
+
+    fn encode(&self) -> String {
+        // id와 이름에 줄바꿈이 들어가면 다음 필드로 읽히므로 잘라 낸다. id에는 NUL도 쓸 수 없다.
+        let field = |value: &str| -> String {
+            value
+                .chars()
+                .filter(|c| !matches!(c, '\r' | '\n' | '\0'))
+                .collect()
+        };
+        let mut out = String::new();
+        if let Some(retry) = self.retry {
+            out.push_str(&format!("retry: {}\n", retry.as_millis()));
+        }
+        if let Some(id) = &self.id {
+            out.push_str(&format!("id: {}\n", field(id)));
+        }
+        if let Some(event) = &self.event {
+            out.push_str(&format!("event: {}\n", field(event)));
+        }
+        if let Some(data) = &self.data {
+            for line in data.split("\r\n").flat_map(|line| line.split(['\r', '\n'])) {
+                out.push_str(&format!("data: {}\n", line));
+            }
+        }
+        out.push('\n');
+        out
+    }
+}
+
+// text/event-stream 본문. 이벤트마다 곧바로 흘려 보낸다.
+pub struct EventStream<'a> {
+    writer: &'a mut dyn Write,
+    shutdown: &'a AtomicBool,
+    last_write: Instant,
+    written: u64,
+}
+
+impl<'a> EventStream<'a> {
+    pub(crate) fn new(writer: &'a mut dyn Write, shutdown: &'a AtomicBool) -> Self {
+        EventStream {
+            writer,
+            shutdown,
+            last_write: Instant::now(),
+            written: 0,
+        }
+    }
+
+    pub fn send(&mut self, event: &Event) -> io::Result<()> {
+        self.write(&event.encode())
+    }
+
+    // 핸들러가 기다리는 사이사이 부른다. 서버가 종료 중이면 false를 돌려주고,
+    // 한동안 보낸 것이 없으면 주석 행을 보내 커넥션이 살아 있는지 확인한다.
+    pub fn keep_alive(&mut self) -> io::Result<bool> {
+        if self.shutdown.load(Ordering::SeqCst) {
+            return Ok(false);
+        }
+        if self.last_write.elapsed() >= KEEP_ALIVE_INTERVAL {
+            self.write(":\n\n")?;
+        }
+        Ok(true)
+    }
+
+    // 지금까지 보낸 본문 바이트 수
+    pub(crate) fn written(&self) -> u64 {
+        self.written
+    }
+
+    fn write(&mut self, text: &str) -> io::Result<()> {
+        self.writer.write_all(text.as_bytes())?;
+        self.writer.flush()?;
+        self.written += text.len() as u64;
+        self.last_write = Instant::now();
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_event_encoding() {
+        assert_eq!("data: hi\n\n", Event::new("hi").encode());
+        assert_eq!(
+            "id: 7\nevent: updated\ndata: {\"a\":1}\n\n",
+            Event::new("{\"a\":1}").id("7").event("updated").encode()
+        );
+        assert_eq!(
+            "retry: 3000\n\n",
+            Event::default().retry(Duration::from_secs(3)).encode()
+        );
+        // 여러 줄 데이터는 줄마다 data: 행이 되고, id와 이름의 줄바꿈은 지운다
+        assert_eq!(
+            "id: 12\nevent: ab\ndata: one\ndata: two\ndata: \ndata: three\n\n",
+            Event::new("one\r\ntwo\n\rthree")
+                .id("1\n2")
+                .event("a\rb")
+                .encode()
+        );
+    }
+
+    #[test]
+    fn test_stream_writes_and_stops_on_shutdown() {
+        let mut out = Vec::new();
+        let shutdown = AtomicBool::new(false);
+        let mut stream = EventStream::new(&mut out, &shutdown);
+        stream.send(&Event::new("a").id("1")).unwrap();
+        assert!(stream.keep_alive().unwrap());
+        stream.last_write -= KEEP_ALIVE_INTERVAL;
+        assert!(stream.keep_alive().unwrap());
+        shutdown.store(true, Ordering::SeqCst);
+        assert!(!stream.keep_alive().unwrap());
+        assert_eq!(18, stream.written());
+        assert_eq!("id: 1\ndata: a\n\n:\n\n", String::from_utf8(out).unwrap());
+    }
+
+    #[test]
+    fn test_accept() {
+        let req =
+            |version| HttpRequest::try_from(format!("GET /events {}\r\n\r\n", version)).unwrap();
+        let resp = accept(&req("HTTP/1.1"), &Params::default());
+        assert!(is_event_stream(&resp));
+        assert_eq!(None, resp.body().len());
+        let resp = accept(&req("HTTP/1.0"), &Params::default());
+        assert_eq!(StatusCode::HttpVersionNotSupported, resp.status());
+    }
+}
@@ -0,0 +1,638 @@
+use http::{
+    body::Body,
+    date::{fmt_http_date, parse_http_date},
+    headers::HeaderMap,
+    httprequest::{HttpRequest, Method},
+    httpresponse::HttpResponse,
+    range::{content_range, parse_range_header},
+    status::StatusCode,
+    uri::percent_encode,
+};
+use std::collections::VecDeque;
+use std::fs::{self, File};
+use std::io::{self, Cursor, Read, Seek, SeekFrom};
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+// 캐시가 저장은 하되 쓰기 전에 ETag/Last-Modified로 다시 확인하게 한다
+const CACHE_CONTROL: &str = "no-cache";
+// 한 요청에서 받아 주는 최대 범위 수. 더 많으면 Range 헤더를 무시하고 전체를 보낸다.
+const MAX_RANGES: usize = 16;
+
+// 요청 경로를 공개 디렉터리 안의 실제 경로로 바꾼 결과
+#[derive(Debug, PartialEq)]
+pub enum Resolved {
+    File(PathBuf),
+    Directory(PathBuf),
+    NotFound,
+    // 숨김 파일이거나 공개 디렉터리 밖을 가리킨다
+    Forbidden,
+}
+
+// 정적 파일을 내보내는 공개 디렉터리. 요청 경로는 항상 이 디렉터리 안에서만 찾는다.
+pub struct StaticRoot {
+    root: PathBuf,
+}
+
+impl StaticRoot {
+    // 심볼릭 링크를 따라간 실제 경로를 기준으로 삼는다
+    pub fn new(path: impl AsRef<Path>) -> io::Result<Self> {
+        let root = path.as_ref().canonicalize()?;
+        if !root.is_dir() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("{} is not a directory", root.display()),
+            ));
+        }
+        Ok(StaticRoot { root })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.root
+    }
+
+    // 파일 옆에 확장자를 덧붙인 파일(예: site.css.gz)이 공개 디렉터리 안에 있으면 그 경로
+    pub fn sibling(&self, path: &Path, extension: &str) -> Option<PathBuf> {
+        let mut sibling = path.as_os_str().to_owned();
+        sibling.push(".");
+        sibling.push(extension);
+        let sibling = Path::new(&sibling).canonicalize().ok()?;
+        (sibling.starts_with(&self.root) && sibling.is_file()).then_some(sibling)
+    }
+
+    // 디코딩된 상대 경로(예: "css/site.css")를 찾는다.
+    // '.'으로 시작하는 세그먼트는 숨김 파일로 보고 거절하며,
+    // 심볼릭 링크를 따라간 최종 경로가 공개 디렉터리 밖이면 역시 거절한다.
+    pub fn resolve(&self, relative: &str) -> Resolved {
+        let mut path = self.root.clone();
+        for segment in relative.split('/').filter(|s| !s.is_empty()) {
+            if segment.starts_with('.') || segment.contains(['\\', '\0']) {
+                return Resolved::Forbidden;
+            }
+            path.push(segment);
+        }
+        let path = match path.canonicalize() {
+            Ok(path) => path,
+            Err(_) => return Resolved::NotFound,
+        };
+        if !path.starts_with(&self.root) {
+            return Resolved::Forbidden;
+        }
+        match fs::metadata(&path) {
+            Ok(metadata) if metadata.is_dir() => Resolved::Directory(path),
+            Ok(metadata) if metadata.is_file() => Resolved::File(path),
+            _ => Resolved::NotFound,
+        }
+    }
+}
+
+// 디렉터리 안의 항목을 링크로 나열한 HTML. 숨김 파일은 보여 주지 않는다.
+// url_path는 요청에서 쓴 디렉터리 경로로, '/'로 끝난다.
+pub fn directory_listing(dir: &Path, url_path: &str) -> io::Result<String> {
+    let mut entries = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let name = entry.file_name().to_string_lossy().into_owned();
+        if name.starts_with('.') {
+            continue;
+        }
+        entries.push((!entry.file_type()?.is_dir(), name));
+    }
+    // 디렉터리를 먼저, 그다음 이름순으로 보여 준다
+    entries.sort();
+
+    let title = escape_html(url_path);
+    let mut html = format!(
+        "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\" />\n\
+         <title>Index of {0}</title>\n</head>\n<body>\n<h1>Index of {0}</h1>\n<ul>\n",
+        title
+    );
+    if url_path != "/" {
+        html.push_str("<li><a href=\"../\">../</a></li>\n");
+    }
+    for (is_file, name) in entries {
+        let suffix = if is_file { "" } else { "/" };
+        html.push_str(&format!(
+            "<li><a href=\"{}{}\">{}{}</a></li>\n",
+            percent_encode(&name),
+            suffix,
+            escape_html(&name),
+            suffix
+        ));
+    }
+    html.push_str("</ul>\n</body>\n</html>\n");
+    Ok(html)
+}
+
+fn escape_html(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+// 열어 둔 정적 파일로 응답을 만든다.
+// 검증자(ETag, Last-Modified)를 붙이고, 조건부 요청에는 304를, Range 요청에는 206을 돌려준다.
+pub fn serve_file(req: &HttpRequest, file: File, content_type: &str) -> HttpResponse {
+    let metadata = match file.metadata() {
+        Ok(metadata) => metadata,
+        Err(e) => {
+            println!("Failed to read file metadata: {}", e);
+            return HttpResponse::new(StatusCode::InternalServerError, None, None);
+        }
+    };
+    let len = metadata.len();
+    // HTTP-date는 초 단위이므로 비교할 때도 1초 미만을 버린다
+    let modified = metadata.modified().ok().map(truncate_to_secs);
+    let etag = entity_tag(len, modified);
+
+    let mut headers = HeaderMap::new();
+    headers.insert("ETag", etag.as_str());
+    if let Some(modified) = modified {
+        headers.insert("Last-Modified", fmt_http_date(modified));
+    }
+    headers.insert("Cache-Control", CACHE_CONTROL);
+
+    if is_not_modified(req, &etag, modified) {
+        return HttpResponse::builder()
+            .status(StatusCode::NotModified)
+            .headers(headers)
+            .build();
+    }
+
+    headers.insert("Accept-Ranges", "bytes");
+    // Range는 GET에만 정의되어 있다. If-Range가 맞지 않으면 전체 표현을 보낸다.
+    if req.method == Method::Get && if_range_matches(req, &etag, modified) {
+        if let Some(specs) = req.headers.get("Range").and_then(parse_range_header) {
+            let ranges: Vec<Range<u64>> = specs.iter().filter_map(|s| s.resolve(len)).collect();
+            if ranges.is_empty() {
+                headers.insert("Content-Range", format!("bytes */{}", len));
+                return HttpResponse::builder()
+                    .status(StatusCode::RangeNotSatisfiable)
+                    .headers(headers)
+                    .build();
+            }
+            if specs.len() <= MAX_RANGES {
+                return partial_content(file, len, ranges, content_type, headers);
+            }
+        }
+    }
+
+    headers.insert("Content-Type", content_type);
+    HttpResponse::builder()
+        .headers(headers)
+        .body(Body::File { file, len })
+        .build()
+}
+
+fn partial_content(
+    mut file: File,
+    len: u64,
+    ranges: Vec<Range<u64>>,
+    content_type: &str,
+    mut headers: HeaderMap,
+) -> HttpResponse {
+    // 범위가 하나면 파일의 해당 구간만 그대로 보낸다
+    if let [range] = ranges.as_slice() {
+        if let Err(e) = file.seek(SeekFrom::Start(range.start)) {
+            println!("Failed to seek file: {}", e);
+            return HttpResponse::new(StatusCode::InternalServerError, None, None);
+        }
+        headers.insert("Content-Type", content_type);
+        headers.insert("Content-Range", content_range(range, len));
+        return HttpResponse::builder()
+            .status(StatusCode::PartialContent)
+            .headers(headers)
+            .body(Body::File {
+                file,
+                len: range.end - range.start,
+            })
+            .build();
+    }
+
+    // 여러 범위는 multipart/byteranges로 보낸다 (RFC 9110 14.6)
+    let boundary = multipart_boundary();
+    let mut parts = VecDeque::new();
+    for (i, range) in ranges.iter().enumerate() {
+        let head = format!(
+            "{}--{}\r\nContent-Type: {}\r\nContent-Range: {}\r\n\r\n",
+            if i == 0 { "" } else { "\r\n" },
+            boundary,
+            content_type,
+            content_range(range, len)
+        );
+        parts.push_back(Part::Bytes(Cursor::new(head.into_bytes())));
+        parts.push_back(Part::File {
+            start: range.start,
+            remaining: range.end - range.start,
+            positioned: false,
+        });
+    }
+    parts.push_back(Part::Bytes(Cursor::new(
+        format!("\r\n--{}--\r\n", boundary).into_bytes(),
+    )));
+    let body_len = parts.iter().map(Part::len).sum();
+
+    headers.insert(
+        "Content-Type",
+        format!("multipart/byteranges; boundary={}", boundary),
+    );
+    HttpResponse::builder()
+        .status(StatusCode::PartialContent)
+        .headers(headers)
+        .body(Body::from_reader(
+            MultipartRanges { file, parts },
+            Some(body_len),
+        ))
+        .build()
+}
+
+// If-None-Match가 있으면 그것만, 없으면 If-Modified-Since로 판단한다 (RFC 9110 13.2.2)
+fn is_not_modified(req: &HttpRequest, etag: &str, modified: Option<SystemTime>) -> bool {
+    if req.method != Method::Get && req.method != Method::Head {
+        return false;
+    }
+    if let Some(if_none_match) = req.headers.get("If-None-Match") {
+        return if_none_match.trim() == "*"
+            || if_none_match
+                .split(',')
+                .any(|tag| weak_eq(tag.trim(), etag));
+    }
+    match (
+        req.headers
+            .get("If-Modified-Since")
+            .and_then(parse_http_date),
+        modified,
+    ) {
+        (Some(since), Some(modified)) => modified <= since,
+        _ => false,
+    }
+}
+
+// If-Range가 없거나 현재 표현과 같으면 true. 엔터티 태그는 강한 비교를 한다.
+fn if_range_matches(req: &HttpRequest, etag: &str, modified: Option<SystemTime>) -> bool {
+    let Some(if_range) = req.headers.get("If-Range").map(str::trim) else {
+        return true;
+    };
+    if if_range.starts_with('"') || if_range.starts_with("W/") {
+        return !if_range.starts_with("W/") && if_range == etag;
+    }
+    parse_http_date(if_range).is_some_and(|date| Some(date) == modified)
+}
+
+// 약한 비교: W/ 접두사를 빼고 태그가 같으면 같은 것으로 본다
+fn weak_eq(a: &str, b: &str) -> bool {
+    a.trim_start_matches("W/") == b.trim_start_matches("W/")
+}
+
+// 크기와 수정 시각으로 만든 강한 엔터티 태그
+fn entity_tag(len: u64, modified: Option<SystemTime>) -> String {
+    let secs = modified
+        .and_then(|m| m.duration_since(UNIX_EPOCH).ok())
+        .map_or(0, |d| d.as_secs());
+    format!("\"{:x}-{:x}\"", secs, len)
+}
+
+fn truncate_to_secs(time: SystemTime) -> SystemTime {
+    let secs = time.duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs());
+    UNIX_EPOCH + Duration::from_secs(secs)
+}
+
+fn multipart_boundary() -> String {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_nanos());
+    format!("{:x}{:x}", nanos, std::process::id())
+}
+
+// multipart/byteranges 본문의 한 조각
+enum Part {
+    // 경계와 부분 헤더
+    Bytes(Cursor<Vec<u8>>),
+    // 파일의 한 구간. 처음 읽을 때 시작 위치로 이동한다.
+    File {
+        start: u64,
+        remaining: u64,
+        positioned: bool,
+    },
+}
+
+impl Part {
+    fn len(&self) -> u64 {
+        match self {
+            Part::Bytes(cursor) => cursor.get_ref().len() as u64,
+            Part::File { remaining, .. } => *remaining,
+        }
+    }
+}
+
+// 조각들을 차례로 읽어 파일 전체를 메모리에 올리지 않고 multipart 본문을 만든다
+struct MultipartRanges {
+    file: File,
+    parts: VecDeque<Part>,
+}
+
+impl Read for MultipartRanges {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        while let Some(part) = self.parts.front_mut() {
+            let n = match part {
+                Part::Bytes(cursor) => cursor.read(buf)?,
+                Part::File {
+                    start,
+                    remaining,
+                    positioned,
+                } => {
+                    if *remaining == 0 {
+                        0
+                    } else {
+                        if !*positioned {
+                            self.file.seek(SeekFrom::Start(*start))?;
+                            *positioned = true;
+                        }
+                        let max = buf.len().min(*remaining as usize);
+                        let n = self.file.read(&mut buf[..max])?;
+                        if n == 0 {
+                            return Err(io::ErrorKind::UnexpectedEof.into());
+                        }
+                        *remaining -= n as u64;
+                        n
+                    }
+                }
+            };
+            if n > 0 {
+                return Ok(n);
+            }
+            self.parts.pop_front();
+        }
+        Ok(0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+    use std::fs;
+    use std::path::PathBuf;
+
+    struct TempFile(PathBuf);
+    impl TempFile {
+        fn new(name: &str, contents: &[u8]) -> Self {
+            let path = env::temp_dir().join(format!("{}-{}", name, std::process::id()));
+            fs::write(&path, contents).unwrap();
+            TempFile(path)
+        }
+        fn open(&self) -> File {
+            File::open(&self.0).unwrap()
+        }
+    }
+    impl Drop for TempFile {
+        fn drop(&mut self) {
+            let _ = fs::remove_file(&self.0);
+        }
+    }
+
+    fn request(method: &str, headers: &[(&str, &str)]) -> HttpRequest {
+        let mut raw = format!("{} /file HTTP/1.1\r\nHost: localhost\r\n", method);
+        for (k, v) in headers {
+            raw.push_str(&format!("{}: {}\r\n", k, v));
+        }
+        raw.push_str("\r\n");
+        HttpRequest::try_from(raw).unwrap()
+    }
+
+    fn body(mut resp: HttpResponse) -> Vec<u8> {
+        resp.take_body().into_bytes().unwrap()
+    }
+
+    // 테스트마다 따로 쓰는 임시 공개 디렉터리
+    struct TempDir(PathBuf);
+    impl TempDir {
+        fn new(name: &str) -> Self {
+            let path = env::temp_dir().join(format!("{}-{}", name, std::process::id()));
+            let _ = fs::remove_dir_all(&path);
+            fs::create_dir_all(&path).unwrap();
+            TempDir(path)
+        }
+        fn write(&self, relative: &str, contents: &str) {
+            let path = self.0.join(relative);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, contents).unwrap();
+        }
+    }
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    #[test]
+    fn test_resolve_stays_inside_root() {
+        let dir = TempDir::new("static-root");
+        dir.write("public/css/site.css", "h1 {}");
+        dir.write("public/.env", "SECRET=1");
+        dir.write("secret.txt", "outside");
+        let root = StaticRoot::new(dir.0.join("public")).unwrap();
+
+        assert_eq!(
+            Resolved::File(root.path().join("css/site.css")),
+            root.resolve("css/site.css")
+        );
+        assert_eq!(
+            Resolved::Directory(root.path().join("css")),
+            root.resolve("css/")
+        );
+        assert_eq!(
+            Resolved::Directory(root.path().to_path_buf()),
+            root.resolve("")
+        );
+        assert_eq!(Resolved::NotFound, root.resolve("css/missing.css"));
+        // %2F로 인코딩된 '/'가 풀려 들어와도 ..은 거절한다
+        assert_eq!(Resolved::Forbidden, root.resolve("css/../../secret.txt"));
+        assert_eq!(Resolved::Forbidden, root.resolve(".env"));
+        assert_eq!(Resolved::Forbidden, root.resolve("css\\..\\..\\secret.txt"));
+
+        // 공개 디렉터리 밖을 가리키는 심볼릭 링크는 따라가지 않는다
+        #[cfg(unix)]
+        {
+            std::os::unix::fs::symlink(dir.0.join("secret.txt"), dir.0.join("public/link"))
+                .unwrap();
+            assert_eq!(Resolved::Forbidden, root.resolve("link"));
+            std::os::unix::fs::symlink(dir.0.join("secret.txt"), dir.0.join("public/link.gz"))
+                .unwrap();
+            assert_eq!(None, root.sibling(&root.path().join("link"), "gz"));
+        }
+
+        dir.write("public/css/site.css.gz", "");
+        let site = root.path().join("css/site.css");
+        assert_eq!(
+            Some(root.path().join("css/site.css.gz")),
+            root.sibling(&site, "gz")
+        );
+        assert_eq!(None, root.sibling(&site, "br"));
+    }
+
+    #[test]
+    fn test_directory_listing() {
+        let dir = TempDir::new("static-listing");
+        dir.write("b <&>.txt", "");
+        dir.write("a dir/x", "");
+        dir.write(".hidden", "");
+        let html = directory_listing(&dir.0, "/files/").unwrap();
+        let items: Vec<&str> = html.lines().filter(|l| l.starts_with("<li>")).collect();
+        assert_eq!(
+            vec![
+                "<li><a href=\"../\">../</a></li>",
+                "<li><a href=\"a%20dir/\">a dir/</a></li>",
+                "<li><a href=\"b%20%3C%26%3E.txt\">b &lt;&amp;&gt;.txt</a></li>",
+            ],
+            items
+        );
+        assert!(html.contains("<title>Index of /files/</title>"));
+    }
+
+    #[test]
+    fn test_full_response_has_validators() {
+        // 바이너리 파일도 바이트 그대로 보낸다
+        let file = TempFile::new("static-full", &[0, 159, 146, 150, 255]);
+        let resp = serve_file(&request("GET", &[]), file.open(), "image/png");
+        assert_eq!(StatusCode::Ok, resp.status());
+        assert!(resp.headers().get("ETag").is_some());
+        assert!(resp.headers().get("Last-Modified").is_some());
+        assert_eq!(Some("no-cache"), resp.headers().get("Cache-Control"));
+        assert_eq!(Some("bytes"), resp.headers().get("Accept-Ranges"));
+        assert_eq!(vec![0, 159, 146, 150, 255], body(resp));
+    }
+
+    #[test]
+    fn test_conditional_get_returns_304() {
+        let file = TempFile::new("static-conditional", b"hello");
+        let resp = serve_file(&request("GET", &[]), file.open(), "text/plain");
+        let etag = resp.headers().get("ETag").unwrap().to_string();
+        let last_modified = resp.headers().get("Last-Modified").unwrap().to_string();
+
+        let weak = format!("W/{}", etag);
+        let not_modified = [
+            request("GET", &[("If-None-Match", &etag)]),
+            request(
+                "HEAD",
+                &[("If-None-Match", &format!("\"other\", {}", weak))],
+            ),
+            request("GET", &[("If-None-Match", "*")]),
+            request("GET", &[("If-Modified-Since", &last_modified)]),
+        ];
+        for req in &not_modified {
+            let resp = serve_file(req, file.open(), "text/plain");
+            assert_eq!(StatusCode::NotModified, resp.status());
+            assert_eq!(Some(etag.as_str()), resp.headers().get("ETag"));
+        }
+
+        // If-None-Match가 있으면 If-Modified-Since는 보지 않는다
+        let req = request(
+            "GET",
+            &[
+                ("If-None-Match", "\"other\""),
+                ("If-Modified-Since", &last_modified),
+            ],
+        );
+        let resp = serve_file(&req, file.open(), "text/plain");
+        assert_eq!(StatusCode::Ok, resp.status());
+
+        let req = request(
+            "GET",
+            &[("If-Modified-Since", "Thu, 01 Jan 1970 00:00:00 GMT")],
+        );
+        assert_eq!(
+            StatusCode::Ok,
+            serve_file(&req, file.open(), "text/plain").status()
+        );
+    }
+
+    #[test]
+    fn test_single_range() {
+        let file = TempFile::new("static-range", b"0123456789");
+        let req = request("GET", &[("Range", "bytes=2-5")]);
+        let resp = serve_file(&req, file.open(), "text/plain");
+        assert_eq!(StatusCode::PartialContent, resp.status());
+        assert_eq!(Some("bytes 2-5/10"), resp.headers().get("Content-Range"));
+        assert_eq!(b"2345".to_vec(), body(resp));
+
+        let req = request("GET", &[("Range", "bytes=-3")]);
+        assert_eq!(
+            b"789".to_vec(),
+            body(serve_file(&req, file.open(), "text/plain"))
+        );
+
+        // HEAD에는 Range를 적용하지 않는다
+        let req = request("HEAD", &[("Range", "bytes=2-5")]);
+        assert_eq!(
+            StatusCode::Ok,
+            serve_file(&req, file.open(), "text/plain").status()
+        );
+    }
+
+    #[test]
+    fn test_multiple_ranges_are_multipart() {
+        let file = TempFile::new("static-multipart", b"0123456789");
+        let req = request("GET", &[("Range", "bytes=0-1, 7-")]);
+        let resp = serve_file(&req, file.open(), "text/plain");
+        assert_eq!(StatusCode::PartialContent, resp.status());
+        let content_type = resp.headers().get("Content-Type").unwrap().to_string();
+        let boundary = content_type
+            .strip_prefix("multipart/byteranges; boundary=")
+            .unwrap()
+            .to_string();
+        let declared = resp.body().len();
+
+        let body = body(resp);
+        assert_eq!(Some(body.len() as u64), declared);
+        let expected = format!(
+            "--{b}\r\nContent-Type: text/plain\r\nContent-Range: bytes 0-1/10\r\n\r\n01\r\n\
+             --{b}\r\nContent-Type: text/plain\r\nContent-Range: bytes 7-9/10\r\n\r\n789\r\n\
+             --{b}--\r\n",
+            b = boundary
+        );
+        assert_eq!(expected, String::from_utf8(body).unwrap());
+    }
+
+    #[test]
+    fn test_unsatisfiable_range_and_if_range() {
+        let file = TempFile::new("static-if-range", b"0123456789");
+        let req = request("GET", &[("Range", "bytes=20-30")]);
+        let resp = serve_file(&req, file.open(), "text/plain");
+        assert_eq!(StatusCode::RangeNotSatisfiable, resp.status());
+        assert_eq!(Some("bytes */10"), resp.headers().get("Content-Range"));
+
+        // 문법이 틀린 Range는 무시한다
+        let req = request("GET", &[("Range", "bytes=5-1")]);
+        assert_eq!(
+            StatusCode::Ok,
+            serve_file(&req, file.open(), "text/plain").status()
+        );
+
+        let etag = serve_file(&request("GET", &[]), file.open(), "text/plain")
+            .headers()
+            .get("ETag")
+            .unwrap()
+            .to_string();
+        let req = request("GET", &[("Range", "bytes=0-0"), ("If-Range", &etag)]);
+        assert_eq!(
+            StatusCode::PartialContent,
+            serve_file(&req, file.open(), "text/plain").status()
+        );
+        let req = request("GET", &[("Range", "bytes=0-0"), ("If-Range", "\"stale\"")]);
+        assert_eq!(
+            StatusCode::Ok,
+            serve_file(&req, file.open(), "text/plain").status()
+        );
+    }
+}
@@ -0,0 +1,308 @@
+use super::handler::Handler;
+use super::router::Params;
+use http::{
+    headers::HeaderMap, httprequest::HttpRequest, httpresponse::HttpResponse, status::StatusCode,
+};
+use rustls::crypto::ring::{default_provider, sign::any_supported_type};
+use rustls::pki_types::{pem::PemObject, CertificateDer, PrivateKeyDer};
+use rustls::server::{ClientHello, ResolvesServerCert};
+use rustls::sign::CertifiedKey;
+use rustls::ServerConfig;
+use std::collections::HashMap;
+use std::fmt;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+// 인증서 체인과 개인 키 파일(PEM), 그리고 이 인증서로 응답할 서버 이름들.
+// 이름에는 "*.example.com" 같은 와일드카드를 쓸 수 있다.
+pub struct TlsCertificate {
+    pub names: Vec<String>,
+    pub cert_path: PathBuf,
+    pub key_path: PathBuf,
+}
+
+#[derive(Debug)]
+pub enum TlsError {
+    // PEM 파일을 읽거나 해석할 수 없다
+    Pem(PathBuf, rustls::pki_types::pem::Error),
+    NoCertificates(PathBuf),
+    InvalidKey(PathBuf, rustls::Error),
+    NoCertificatesConfigured,
+    Config(rustls::Error),
+}
+
+impl fmt::Display for TlsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TlsError::Pem(path, e) => write!(f, "{}: {:?}", path.display(), e),
+            TlsError::NoCertificates(path) => {
+                write!(f, "{}: no certificates found", path.display())
+            }
+            TlsError::InvalidKey(path, e) => write!(f, "{}: {}", path.display(), e),
+            TlsError::NoCertificatesConfigured => write!(f, "no TLS certificates configured"),
+            TlsError::Config(e) => write!(f, "invalid TLS configuration: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for TlsError {}
+
+// 인증서들로 TLS 서버 설정을 만든다.
+// 클라이언트가 SNI로 보낸 이름에 맞는 인증서를 고르고, 맞는 것이 없거나 SNI가 없으면 첫 번째 인증서를 쓴다.
+pub fn server_config(certificates: &[TlsCertificate]) -> Result<Arc<ServerConfig>, TlsError> {
+    let mut resolver = SniResolver::default();
+    for certificate in certificates {
+        let key = load_certified_key(certificate)?;
+        if resolver.default.is_none() {
+            resolver.default = Some(Arc::clone(&key));
+        }
+        for name in &certificate.names {
+            resolver
+                .by_name
+                .insert(name.to_ascii_lowercase(), Arc::clone(&key));
+        }
+    }
+    if resolver.default.is_none() {
+        return Err(TlsError::NoCertificatesConfigured);
+    }
+
+    let mut config = ServerConfig::builder_with_provider(Arc::new(default_provider()))
+        .with_safe_default_protocol_versions()
+        .map_err(TlsError::Config)?
+        .with_no_client_auth()
+        .with_cert_resolver(Arc::new(resolver));
+    config.alpn_protocols = vec![b"http/1.1".to_vec()];
+    Ok(Arc::new(config))
+}
+
+fn load_certified_key(certificate: &TlsCertificate) -> Result<Arc<CertifiedKey>, TlsError> {
+    let cert_path = &certificate.cert_path;
+    let certs = CertificateDer::pem_file_iter(cert_path)
+        .and_then(|certs| certs.collect::<Result<Vec<_>, _>>())
+        .map_err(|e| TlsError::Pem(cert_path.clone(), e))?;
+    if certs.is_empty() {
+        return Err(TlsError::NoCertificates(cert_path.clone()));
+    }
+    let key_path = &certificate.key_path;
+    let key =
+        PrivateKeyDer::from_pem_file(key_path).map_err(|e| TlsError::Pem(key_path.clone(), e))?;
+    let signing_key =
+        any_supported_type(&key).map_err(|e| TlsError::InvalidKey(key_path.clone(), e))?;
+    Ok(Arc::new(CertifiedKey::new(certs, signing_key)))
+}
+
+#[derive(Debug, Default)]
+struct SniResolver {
+    by_name: HashMap<String, Arc<CertifiedKey>>,
+    default: Option<Arc<CertifiedKey>>,
+}
+
+impl ResolvesServerCert for SniResolver {
+    fn resolve(&self, client_hello: ClientHello<'_>) -> Option<Arc<CertifiedKey>> {
+        let by_name = client_hello.server_name().and_then(|name| {
+            let name = name.to_ascii_lowercase();
+            let wildcard = name
+                .split_once('.')
+                .map(|(_, parent)| format!("*.{}", parent));
+            self.by_name
+                .get(&name)
+                .or_else(|| wildcard.and_then(|w| self.by_name.get(&w)))
+        });
+        by_name.or(self.default.as_ref()).cloned()
+    }
+}
+
+// 평문 HTTP 요청을 같은 호스트의 HTTPS 주소로 보낸다.
+// 308을 쓰므로 클라이언트는 메서드와 본문을 바꾸지 않고 다시 요청한다.
+pub struct HttpsRedirect {
+    https_port: u16,
+}
+
+impl HttpsRedirect {
+    pub fn new(https_port: u16) -> Self {
+        HttpsRedirect { https_port }
+    }
+}
+
+impl Handler for HttpsRedirect {
+    fn handle(&self, req: &HttpRequest, _params: &Params) -> HttpResponse {
+        let (Some(host), Some(target)) = (
+            req.headers.host().map(strip_port),
+            path_and_query(&req.resource.to_string()),
+        ) else {
+            return HttpResponse::new(StatusCode::BadRequest, None, Some("Bad Request".into()));
+        };
+        let location = match self.https_port {
+            443 => format!("https://{}{}", host, target),
+            port => format!("https://{}:{}{}", host, port, target),
+        };
+        let mut headers = HeaderMap::new();
+        headers.insert("Location", location);
+        HttpResponse::new(StatusCode::PermanentRedirect, Some(headers), None)
+    }
+}
+
+// Host 헤더에서 포트를 뗀다. IPv6 주소는 대괄호를 그대로 둔다.
+fn strip_port(host: &str) -> &str {
+    match host.rfind(':') {
+        Some(i) if !host[i..].contains(']') => &host[..i],
+        _ => host,
+    }
+}
+
+// 요청 대상에서 경로와 쿼리만 남긴다. absolute-form이면 스킴과 authority를 뗀다.
+fn path_and_query(target: &str) -> Option<String> {
+    if target.starts_with('/') {
+        return Some(target.to_string());
+    }
+    let (_, rest) = target.split_once("://")?;
+    match rest.find(['/', '?']) {
+        Some(i) if rest[i..].starts_with('/') => Some(rest[i..].to_string()),
+        Some(i) => Some(format!("/{}", &rest[i..])),
+        None => Some("/".to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::router::Router;
+    use crate::server::{handle_connection, ConnectionOptions, Endpoint};
+    use rustls::pki_types::ServerName;
+    use rustls::{ClientConfig, ClientConnection, RootCertStore, StreamOwned};
+    use std::env;
+    use std::fs;
+    use std::io::{Read, Write};
+    use std::net::{TcpListener, TcpStream};
+    use std::sync::atomic::AtomicBool;
+    use std::thread;
+
+    // 테스트 때마다 자체 서명 인증서를 만들어 임시 파일로 쓴다
+    fn self_signed(name: &str) -> (TlsCertificate, CertificateDer<'static>) {
+        let rcgen::CertifiedKey { cert, signing_key } =
+            rcgen::generate_simple_self_signed(vec![name.to_string()]).unwrap();
+        let dir = env::temp_dir().join(format!("tls-test-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let cert_path = dir.join(format!("{}.crt", name));
+        let key_path = dir.join(format!("{}.key", name));
+        fs::write(&cert_path, cert.pem()).unwrap();
+        fs::write(&key_path, signing_key.serialize_pem()).unwrap();
+        let certificate = TlsCertificate {
+            names: vec![name.to_string()],
+            cert_path,
+            key_path,
+        };
+        (certificate, cert.der().clone())
+    }
+
+    // TLS로 GET 요청을 하나 보내고 서버 인증서와 응답을 돌려준다
+    fn https_get(
+        addr: &str,
+        server_name: &str,
+        roots: &[CertificateDer<'static>],
+    ) -> (CertificateDer<'static>, String) {
+        let mut root_store = RootCertStore::empty();
+        for root in roots {
+            root_store.add(root.clone()).unwrap();
+        }
+        let config = ClientConfig::builder_with_provider(Arc::new(default_provider()))
+            .with_safe_default_protocol_versions()
+            .unwrap()
+            .with_root_certificates(root_store)
+            .with_no_client_auth();
+        let conn = ClientConnection::new(
+            Arc::new(config),
+            ServerName::try_from(server_name.to_string()).unwrap(),
+        )
+        .unwrap();
+        let mut tls = StreamOwned::new(conn, TcpStream::connect(addr).unwrap());
+        write!(
+            tls,
+            "GET / HTTP/1.1\r\nHost: {}\r\nConnection: close\r\n\r\n",
+            server_name
+        )
+        .unwrap();
+        let mut response = String::new();
+        // 서버가 close_notify 없이 끊어도 받은 데이터는 확인한다
+        let _ = tls.read_to_string(&mut response);
+        let peer = tls.conn.peer_certificates().unwrap()[0].clone();
+        (peer, response)
+    }
+
+    #[test]
+    fn test_tls_with_sni_certificate_selection() {
+        let (a, a_der) = self_signed("a.test");
+        let (b, b_der) = self_signed("b.test");
+        let config = server_config(&[a, b]).unwrap();
+        let router = Router::new().get("/", |_req: &HttpRequest, _params: &Params| {
+            HttpResponse::new(StatusCode::Ok, None, Some("secure".into()))
+        });
+        let endpoint = Arc::new(Endpoint::new(Arc::new(router), Some(config)));
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap().to_string();
+        let server = thread::spawn(move || {
+            for _ in 0..2 {
+                let (stream, _) = listener.accept().unwrap();
+                handle_connection(
+                    stream,
+                    &endpoint,
+                    &ConnectionOptions::default(),
+                    &Arc::new(AtomicBool::new(false)),
+                    None,
+                );
+            }
+        });
+
+        let roots = [a_der.clone(), b_der.clone()];
+        let (peer, response) = https_get(&addr, "b.test", &roots);
+        assert_eq!(b_der, peer);
+        assert!(response.starts_with("HTTP/1.1 200 OK\r\n"));
+        assert!(response.ends_with("secure"));
+
+        let (peer, _) = https_get(&addr, "a.test", &roots);
+        assert_eq!(a_der, peer);
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn test_missing_files_are_reported() {
+        let certificate = TlsCertificate {
+            names: vec![],
+            cert_path: PathBuf::from("/nonexistent/cert.pem"),
+            key_path: PathBuf::from("/nonexistent/key.pem"),
+        };
+        let err = server_config(&[certificate]).unwrap_err();
+        assert!(err.to_string().starts_with("/nonexistent/cert.pem"));
+        assert!(matches!(
+            server_config(&[]),
+            Err(TlsError::NoCertificatesConfigured)
+        ));
+    }
+
+    #[test]
+    fn test_https_redirect() {
+        let redirect = HttpsRedirect::new(3443);
+        let req = HttpRequest::try_from(
+            "POST /api/shipping/orders?x=1 HTTP/1.1\r\nHost: localhost:3000\r\n\r\n".to_string(),
+        )
+        .unwrap();
+        let resp = redirect.handle(&req, &Params::default());
+        assert_eq!(StatusCode::PermanentRedirect, resp.status());
+        assert_eq!(
+            Some("https://localhost:3443/api/shipping/orders?x=1"),
+            resp.headers().get("Location")
+        );
+
+        let req = HttpRequest::try_from(
+            "GET http://[::1]:80?q HTTP/1.1\r\nHost: [::1]:80\r\n\r\n".to_string(),
+        )
+        .unwrap();
+        let resp = HttpsRedirect::new(443).handle(&req, &Params::default());
+        assert_eq!(Some("https://[::1]/?q"), resp.headers().get("Location"));
+
+        let req = HttpRequest::try_from("GET / HTTP/1.0\r\n\r\n".to_string()).unwrap();
+        let resp = redirect.handle(&req, &Params::default());
+        assert_eq!(StatusCode::BadRequest, resp.status());
+    }
+}
@@ -0,0 +1,895 @@
+use super::router::Params;
+use super::server::{is_timeout, RequestDeadline};
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use http::{
+    httprequest::{HttpRequest, Version},
+    httpresponse::HttpResponse,
+    status::StatusCode,
+};
+use sha1::{Digest, Sha1};
+use std::fmt;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+
+// Sec-WebSocket-Key 뒤에 붙여 해시하는 고정 GUID (RFC 6455 1.3)
+const GUID: &str = "258EAFA5-E914-47DA-95CA-C5AB0DC85B11";
+// 이 시간 동안 아무 프레임도 오지 않으면 Ping을 보내고, 한 번 더 지나도 조용하면 끊는다
+const PING_INTERVAL: Duration = Duration::from_secs(30);
+// 메시지를 기다리는 동안 종료 신호를 확인하는 간격
+const SHUTDOWN_POLL_INTERVAL: Duration = Duration::from_millis(200);
+// 이보다 긴 메시지는 여러 프레임으로 나눠 보낸다
+const MAX_FRAME_SIZE: usize = 16 * 1024;
+
+// Close 프레임의 상태 코드 (RFC 6455 7.4.1)
+pub const CLOSE_NORMAL: u16 = 1000;
+pub const CLOSE_GOING_AWAY: u16 = 1001;
+pub const CLOSE_PROTOCOL_ERROR: u16 = 1002;
+pub const CLOSE_INVALID_DATA: u16 = 1007;
+pub const CLOSE_TOO_BIG: u16 = 1009;
+pub const CLOSE_INTERNAL_ERROR: u16 = 1011;
+
+// 업그레이드한 커넥션을 넘겨받아 메시지를 주고받는다.
+// 핸드셰이크 응답을 보낸 worker는 풀로 돌아가고, 핸들러는 커넥션마다 따로 띄운 스레드에서 돈다.
+// 핸들러가 돌아오면 서버가 커넥션을 닫는다.
+pub trait WebSocketHandler: Send + Sync {
+    fn handle(
+        &self,
+        req: &HttpRequest,
+        params: &Params,
+        socket: &mut WebSocket<'_>,
+    ) -> io::Result<()>;
+}
+
+impl<F> WebSocketHandler for F
+where
+    F: Fn(&HttpRequest, &Params, &mut WebSocket<'_>) -> io::Result<()> + Send + Sync,
+{
+    fn handle(
+        &self,
+        req: &HttpRequest,
+        params: &Params,
+        socket: &mut WebSocket<'_>,
+    ) -> io::Result<()> {
+        self(req, params, socket)
+    }
+}
+
+// 업그레이드 요청을 확인하고 101 응답을 만든다 (RFC 6455 4.2).
+// 웹소켓 요청이 아니거나 버전이 13이 아니면 426으로 필요한 업그레이드를 알리고,
+// 키가 잘못되었으면 400을 돌려준다.
+pub fn handshake(req: &HttpRequest, _params: &Params) -> HttpResponse {
+    let upgrade = req
+        .headers
+        .get_all("Upgrade")
+        .iter()
+        .flat_map(|v| v.split(','))
+        .any(|protocol| protocol.trim().eq_ignore_ascii_case("websocket"));
+    if !upgrade
+        || !req.headers.has_connection_option("upgrade")
+        || req.headers.get("Sec-WebSocket-Version") != Some("13")
+    {
+        return HttpResponse::builder()
+            .status(StatusCode::UpgradeRequired)
+            .header("Upgrade", "websocket")
+            .header("Sec-WebSocket-Version", "13")
+            .header("Content-Type", "text/plain")
+            .body(StatusCode::UpgradeRequired.reason_phrase())
+            .build();
+    }
+    // 키는 16바이트 난수를 base64로 인코딩한 값이어야 한다
+    let key = req.headers.get("Sec-WebSocket-Key").unwrap_or("");
+    let key_valid = BASE64.decode(key).is_ok_and(|nonce| nonce.len() == 16);
+    if !key_valid || req.version == Version::V1_0 {
+        return HttpResponse::builder()
+            .status(StatusCode::BadRequest)
+            .header("Content-Type", "text/plain")
+            .body("invalid WebSocket handshake")
+            .build();
+    }
+    HttpResponse::builder()
+        .status(StatusCode::SwitchingProtocols)
+        .header("Upgrade", "websocket")
+        .header("Connection", "Upgrade")
+        .header("Sec-WebSocket-Accept", accept_key(key))
+        .build()
+}
+
+// Sec-WebSocket-Accept = base64(SHA-1(key + GUID))
+fn accept_key(key: &str) -> String {
+    let mut sha1 = Sha1::new();
+    sha1.update(key.as_bytes());
+    sha1.update(GUID.as_bytes());
+    BASE64.encode(sha1.finalize())
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Opcode {
+    Continuation,
+    Text,
+    Binary,
+    Close,
+    Ping,
+    Pong,
+}
+
+impl Opcode {
+    fn from_u8(value: u8) -> Option<Opcode> {
+        match value {
+            0x0 => Some(Opcode::Continuation),
+            0x1 => Some(Opcode::Text),
+            0x2 => Some(Opcode::Binary),
+            0x8 => Some(Opcode::Close),
+            0x9 => Some(Opcode::Ping),
+            0xA => Some(Opcode::Pong),
+            _ => None,
+        }
+    }
+
+    fn as_u8(self) -> u8 {
+        match self {
+            Opcode::Continuation => 0x0,
+            Opcode::Text => 0x1,
+            Opcode::Binary => 0x2,
+            Opcode::Close => 0x8,
+            Opcode::Ping => 0x9,
+            Opcode::Pong => 0xA,
+        }
+    }
+
+    // 제어 프레임은 나눌 수 없고 페이로드가 125바이트를 넘을 수 없다
+    fn is_control(self) -> bool {
+        matches!(self, Opcode::Close | Opcode::Ping | Opcode::Pong)
+    }
+}
+
+#[derive(Debug)]
+pub enum FrameError {
+    Io(io::Error),
+    // 규칙에 맞지 않는 프레임
+    Protocol(&'static str),
+    // 페이로드가 허용한 크기를 넘는다
+    TooLarge,
+}
+
+impl From<io::Error> for FrameError {
+    fn from(e: io::Error) -> Self {
+        FrameError::Io(e)
+    }
+}
+
+impl fmt::Display for FrameError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            FrameError::Io(e) => write!(f, "{}", e),
+            FrameError::Protocol(message) => write!(f, "{}", message),
+            FrameError::TooLarge => write!(f, "frame too large"),
+        }
+    }
+}
+
+// 프레임 하나 (RFC 6455 5.2). payload는 마스킹을 벗긴 데이터이고,
+// mask는 선로에서 쓴(쓸) 마스킹 키다. 클라이언트가 보내는 프레임만 마스킹한다.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Frame {
+    pub fin: bool,
+    pub opcode: Opcode,
+    pub mask: Option<[u8; 4]>,
+    pub payload: Vec<u8>,
+}
+
+impl Frame {
+    pub fn new(opcode: Opcode, payload: impl Into<Vec<u8>>) -> Self {
+        Frame {
+            fin: true,
+            opcode,
+            mask: None,
+            payload: payload.into(),
+        }
+    }
+
+    // 프레임 하나를 읽는다. 페이로드가 max_payload를 넘으면 읽기 전에 거절한다.
+    pub fn read(reader: &mut impl Read, max_payload: usize) -> Result<Frame, FrameError> {
+        let mut head = [0u8; 2];
+        reader.read_exact(&mut head)?;
+        // 확장을 협상하지 않았으므로 RSV 비트는 모두 0이어야 한다
+        if head[0] & 0x70 != 0 {
+            return Err(FrameError::Protocol("reserved bits set"));
+        }
+        let fin = head[0] & 0x80 != 0;
+        let opcode =
+            Opcode::from_u8(head[0] & 0x0F).ok_or(FrameError::Protocol("unknown opcode"))?;
+        let len = match head[1] & 0x7F {
+            126 => {
+                let mut len = [0u8; 2];
+                reader.read_exact(&mut len)?;
+                u16::from_be_bytes(len) as u64
+            }
+            127 => {
+                let mut len = [0u8; 8];
+                reader.read_exact(&mut len)?;
+                let len = u64::from_be_bytes(len);
+                if len >> 63 != 0 {
+                    return Err(FrameError::Protocol("invalid payload length"));
+                }
+                len
+            }
+            len => len as u64,
+        };
+        if opcode.is_control() && (!fin || len > 125) {
+            return Err(FrameError::Protocol("invalid control frame"));
+        }
+        if len > max_payload as u64 {
+            return Err(FrameError::TooLarge);
+        }
+        let mask = if head[1] & 0x80 != 0 {
+            let mut key = [0u8; 4];
+            reader.read_exact(&mut key)?;
+            Some(key)
+        } else {
+            None
+        };
+        let mut payload = vec![0; len as usize];
+        reader.read_exact(&mut payload)?;
+        if let Some(key) = mask {
+            apply_mask(&mut payload, key);
+        }
+        Ok(Frame {
+            fin,
+            opcode,
+            mask,
+            payload,
+        })
+    }
+
+    // 길이는 가장 짧은 형식으로 쓰고, mask가 있으면 페이로드를 마스킹해 쓴다
+    pub fn write(&self, writer: &mut impl Write) -> io::Result<()> {
+        let mut head = Vec::with_capacity(14);
+        head.push(if self.fin { 0x80 } else { 0 } | self.opcode.as_u8());
+        let mask_bit = if self.mask.is_some() { 0x80 } else { 0 };
+        let len = self.payload.len();
+        if len < 126 {
+            head.push(mask_bit | len as u8);
+        } else if len <= u16::MAX as usize {
+            head.push(mask_bit | 126);
+            head.extend_from_slice(&(len as u16).to_be_bytes());
+        } else {
+            head.push(mask_bit | 127);
+            head.extend_from_slice(&(len as u64).to_be_bytes());
+        }
+        match self.mask {
+            Some(key) => {
+                head.extend_from_slice(&key);
+                let mut payload = self.payload.clone();
+                apply_mask(&mut payload, key);
+                writer.write_all(&head)?;
+                writer.write_all(&payload)
+            }
+            None => {
+                writer.write_all(&head)?;
+                writer.write_all(&self.payload)
+            }
+        }
+    }
+}
+
+// 마스킹과 마스킹 해제는 같은 연산이다
+fn apply_mask(data: &mut [u8], key: [u8; 4]) {
+    for (i, byte) in data.iter_mut().enumerate() {
+        *byte ^= key[i % 4];
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CloseFrame {
+    pub code: u16,
+    pub reason: String,
+}
+
+// 조각을 모두 이어 붙인 메시지. Ping과 Pong은 WebSocket이 알아서 처리한다.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Message {
+    Text(String),
+    Binary(Vec<u8>),
+    // 상대가 보낸 Close. 상태 코드 없이 닫을 수도 있다.
+    Close(Option<CloseFrame>),
+}
+
+// 버퍼로 읽고 쓰는 양방향 스트림. 업그레이드한 커넥션을 핸들러에 넘길 때 스트림 타입을 감춘다.
+pub trait Transport: BufRead + Write {}
+impl<T: BufRead + Write> Transport for T {}
+
+// 요청을 읽던 버퍼를 그대로 넘겨, 핸드셰이크 뒤에 이미 들어온 프레임도 잃지 않게 한다
+pub(crate) struct Upgraded<S>(pub(crate) BufReader<S>);
+
+impl<S: Read> Read for Upgraded<S> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.0.read(buf)
+    }
+}
+
+impl<S: Read> BufRead for Upgraded<S> {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        self.0.fill_buf()
+    }
+
+    fn consume(&mut self, amt: usize) {
+        self.0.consume(amt)
+    }
+}
+
+impl<S: Write> Write for Upgraded<S> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.get_mut().write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.0.get_mut().flush()
+    }
+}
+
+// 서버 쪽 웹소켓 커넥션 (RFC 6455).
+// 메시지를 기다리는 동안에는 조용한 상대에게 Ping을 보내고, 프레임을 읽기 시작하면 read_timeout 안에 다 받아야 한다.
+// 서버가 종료 중이면 1001로 닫는다.
+pub struct WebSocket<'a> {
+    stream: &'a mut dyn Transport,
+    deadline: RequestDeadline,
+    shutdown: &'a AtomicBool,
+    read_timeout: Duration,
+    ping_interval: Duration,
+    max_message_size: usize,
+    max_frame_size: usize,
+    // 마지막으로 프레임을 받은 때와, 그 뒤로 Ping을 보냈는지
+    last_seen: Instant,
+    ping_sent: bool,
+    // Close 프레임을 보냈으면 더는 데이터를 보낼 수 없다
+    closed: bool,
+}
+
+impl<'a> WebSocket<'a> {
+    // deadline은 스트림의 읽기 제한 시간을 정하는 서버의 마감 시각이다
+    pub(crate) fn new(
+        stream: &'a mut dyn Transport,
+        deadline: RequestDeadline,
+        shutdown: &'a AtomicBool,
+    ) -> Self {
+        WebSocket {
+            stream,
+            deadline,
+            shutdown,
+            read_timeout: Duration::from_secs(10),
+            ping_interval: PING_INTERVAL,
+            max_message_size: 16 * 1024 * 1024,
+            max_frame_size: MAX_FRAME_SIZE,
+            last_seen: Instant::now(),
+            ping_sent: false,
+            closed: false,
+        }
+    }
+
+    // 프레임 하나를 읽기 시작해서 다 받을 때까지 허용하는 시간
+    pub(crate) fn read_timeout(mut self, read_timeout: Duration) -> Self {
+        self.read_timeout = read_timeout;
+        self
+    }
+
+    // 조각을 모두 이어 붙인 메시지의 최대 크기. 넘으면 1009로 닫는다.
+    pub(crate) fn max_message_size(mut self, max_message_size: usize) -> Self {
+        self.max_message_size = max_message_size;
+        self
+    }
+
+    // 다음 메시지를 최대 wait만큼 기다린다. 그동안 메시지가 오지 않으면 None을 돌려준다.
+    // 상대가 닫으면 Close로 답한 뒤 Message::Close를 돌려주고, 규칙을 어긴 프레임을 받으면
+    // 알맞은 상태 코드로 닫은 뒤 InvalidData 에러를 돌려준다.
+    pub fn read_message(&mut self, wait: Duration) -> io::Result<Option<Message>> {
+        if self.closed {
+            return Err(io::Error::new(
+                io::ErrorKind::NotConnected,
+                "WebSocket is closed",
+            ));
+        }
+        let started = Instant::now();
+        let mut fragments: Option<(Opcode, Vec<u8>)> = None;
+        loop {
+            // 메시지와 메시지 사이에서만 기다린다
+            if fragments.is_none() && !self.wait_for_frame(started, wait)? {
+                if self.closed {
+                    return Ok(Some(Message::Close(Some(CloseFrame {
+                        code: CLOSE_GOING_AWAY,
+                        reason: "server shutting down".to_string(),
+                    }))));
+                }
+                return Ok(None);
+            }
+
+            self.deadline.start(self.read_timeout);
+            let frame = Frame::read(&mut self.stream, self.max_message_size);
+            self.deadline.clear();
+            let frame = match frame {
+                Ok(frame) => frame,
+                Err(FrameError::Io(e)) => return Err(e),
+                Err(FrameError::Protocol(message)) => {
+                    return Err(self.fail(CLOSE_PROTOCOL_ERROR, message))
+                }
+                Err(FrameError::TooLarge) => {
+                    return Err(self.fail(CLOSE_TOO_BIG, "message too large"))
+                }
+            };
+            self.last_seen = Instant::now();
+            self.ping_sent = false;
+            // 클라이언트가 보내는 프레임은 모두 마스킹해야 한다 (RFC 6455 5.1)
+            if frame.mask.is_none() {
+                return Err(self.fail(CLOSE_PROTOCOL_ERROR, "unmasked client frame"));
+            }
+
+            match frame.opcode {
+                Opcode::Ping => self.write_frame(&Frame::new(Opcode::Pong, frame.payload))?,
+                Opcode::Pong => {}
+                Opcode::Close => {
+                    let close = match parse_close(&frame.payload) {
+                        Ok(close) => close,
+                        Err(message) => return Err(self.fail(CLOSE_PROTOCOL_ERROR, message)),
+                    };
+                    // 받은 상태 코드를 그대로 돌려보내 닫기 핸드셰이크를 마친다
+                    self.closed = true;
+                    let payload = match &close {
+                        Some(close) => close.code.to_be_bytes().to_vec(),
+                        None => Vec::new(),
+                    };
+                    self.write_frame(&Frame::new(Opcode::Close, payload))?;
+                    return Ok(Some(Message::Close(close)));
+                }
+                Opcode::Text | Opcode::Binary => {
+                    if fragments.is_some() {
+                        return Err(self.fail(CLOSE_PROTOCOL_ERROR, "expected continuation frame"));
+                    }
+                    if frame.fin {
+                        return self.finish(frame.opcode, frame.payload).map(Some);
+                    }
+                    fragments = Some((frame.opcode, frame.payload));
+                }
+                Opcode::Continuation => {
+                    let Some((_, data)) = fragments.as_mut() else {
+                        return Err(
+                            self.fail(CLOSE_PROTOCOL_ERROR, "unexpected continuation frame")
+                        );
+                    };
+                    if data.len() + frame.payload.len() > self.max_message_size {
+                        return Err(self.fail(CLOSE_TOO_BIG, "message too large"));
+                    }
+                    data.extend_from_slice(&frame.payload);
+                    if frame.fin {
+                        let (opcode, data) = fragments.take().unwrap();
+                        return self.finish(opcode, data).map(Some);
+                    }
+                }
+            }
+        }
+    }
+
+    // 다음 프레임의 첫 바이트가 올 때까지 기다린다. 기다린 시간이 wait를 넘거나
+    // 서버가 종료 중이면(이때는 1001로 닫는다) false를 돌려준다.
+    fn wait_for_frame(&mut self, started: Instant, wait: Duration) -> io::Result<bool> {
+        loop {
+            if self.shutdown.load(Ordering::SeqCst) {
+                self.close(CLOSE_GOING_AWAY, "server shutting down")?;
+                return Ok(false);
+            }
+            let silent = self.last_seen.elapsed();
+            if self.ping_sent && silent >= self.ping_interval * 2 {
+                return Err(io::Error::new(
+                    io::ErrorKind::TimedOut,
+                    "WebSocket peer stopped responding",
+                ));
+            }
+            if !self.ping_sent && silent >= self.ping_interval {
+                self.write_frame(&Frame::new(Opcode::Ping, Vec::new()))?;
+                self.ping_sent = true;
+            }
+            let waited = started.elapsed();
+            if waited >= wait {
+                return Ok(false);
+            }
+
+            let heartbeat = if self.ping_sent { 2 } else { 1 } * self.ping_interval;
+            let timeout = (wait - waited)
+                .min(heartbeat.saturating_sub(silent))
+                .min(SHUTDOWN_POLL_INTERVAL)
+                .max(Duration::from_millis(1));
+            self.deadline.start(timeout);
+            let ready = self.stream.fill_buf().map(|buf| !buf.is_empty());
+            self.deadline.clear();
+            match ready {
+                Ok(true) => return Ok(true),
+                Ok(false) => {
+                    return Err(io::Error::new(
+                        io::ErrorKind::UnexpectedEof,
+                        "WebSocket closed without a close frame",
+                    ))
+                }
+                Err(e) if is_timeout(&e) => {}
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    fn finish(&mut self, opcode: Opcode, data: Vec<u8>) -> io::Result<Message> {
+        match opcode {
+            Opcode::Text => match String::from_utf8(data) {
+                Ok(text) => Ok(Message::Text(text)),
+                Err(_) => Err(self.fail(CLOSE_INVALID_DATA, "invalid UTF-8 in text message")),
+            },
+            _ => Ok(Message::Binary(data)),
+        }
+    }
+
+    // 텍스트나 바이너리 메시지를 보낸다. 긴 메시지는 여러 프레임으로 나눈다.
+    // Message::Close를 보내면 close와 같다.
+    pub fn send(&mut self, message: &Message) -> io::Result<()> {
+        let (opcode, data) = match message {
+            Message::Text(text) => (Opcode::Text, text.as_bytes()),
+            Message::Binary(data) => (Opcode::Binary, &data[..]),
+            Message::Close(close) => {
+                return match close {
+                    Some(close) => self.close(close.code, &close.reason),
+                    None => self.close(CLOSE_NORMAL, ""),
+                }
+            }
+        };
+        if self.closed {
+            return Err(io::Error::new(
+                io::ErrorKind::NotConnected,
+                "WebSocket is closed",
+            ));
+        }
+        let mut chunks = data.chunks(self.max_frame_size).peekable();
+        let mut frame = Frame::new(opcode, Vec::new());
+        // 빈 메시지도 프레임 하나로 보낸다
+        if chunks.peek().is_none() {
+            return self.write_frame(&frame);
+        }
+        while let Some(chunk) = chunks.next() {
+            frame.payload = chunk.to_vec();
+            frame.fin = chunks.peek().is_none();
+            self.write_frame(&frame)?;
+            frame.opcode = Opcode::Continuation;
+        }
+        Ok(())
+    }
+
+    // Close 프레임을 보내고 상대의 Close를 read_timeout까지 기다린다. 그 사이 온 메시지는 버린다.
+    // 이미 닫았으면 아무것도 하지 않는다.
+    pub fn close(&mut self, code: u16, reason: &str) -> io::Result<()> {
+        if self.closed {
+            return Ok(());
+        }
+        self.send_close(code, reason)?;
+        self.deadline.start(self.read_timeout);
+        while let Ok(frame) = Frame::read(&mut self.stream, self.max_message_size) {
+            if frame.opcode == Opcode::Close {
+                break;
+            }
+        }
+        self.deadline.clear();
+        Ok(())
+    }
+
+    // 규칙을 어긴 상대에게 상태 코드와 까닭을 담은 Close를 보내고 돌려줄 에러를 만든다
+    fn fail(&mut self, code: u16, message: &'static str) -> io::Error {
+        if !self.closed {
+            let _ = self.send_close(code, message);
+        }
+        io::Error::new(io::ErrorKind::InvalidData, message)
+    }
+
+    fn send_close(&mut self, code: u16, reason: &str) -> io::Result<()> {
+        self.closed = true;
+        // 제어 프레임의 페이로드는 125바이트까지이므로 까닭은 123바이트까지만 싣는다
+        let mut end = reason.len().min(123);
+        while !reason.is_char_boundary(end) {
+            end -= 1;
+        }
+        let mut payload = code.to_be_bytes().to_vec();
+        payload.extend_from_slice(&reason.as_bytes()[..end]);
+        self.write_frame(&Frame::new(Opcode::Close, payload))
+    }
+
+    fn write_frame(&mut self, frame: &Frame) -> io::Result<()> {
+        frame.write(&mut self.stream)?;
+        self.stream.flush()
+    }
+}
+
+// Close 프레임의 페이로드: 비어 있거나, 상태 코드 2바이트 뒤에 UTF-8 까닭이 온다.
+// 1005, 1006, 1015처럼 선로에 실을 수 없는 코드는 거절한다.
+fn parse_close(payload: &[u8]) -> Result<Option<CloseFrame>, &'static str> {
+    match payload {
+        [] => Ok(None),
+        [_] => Err("invalid close frame"),
+        [high, low, reason @ ..] => {
+            let code = u16::from_be_bytes([*high, *low]);
+            if !matches!(code, 1000..=1003 | 1007..=1011 | 3000..=4999) {
+                return Err("invalid close code");
+            }
+            let reason = String::from_utf8(reason.to_vec()).map_err(|_| "invalid close reason")?;
+            Ok(Some(CloseFrame { code, reason }))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    // 미리 넣어 둔 바이트를 읽고, 쓴 바이트를 모아 두는 스트림
+    struct Pipe {
+        input: Cursor<Vec<u8>>,
+        output: Vec<u8>,
+    }
+
+    impl Pipe {
+        fn new(frames: &[Frame]) -> Self {
+            let mut input = Vec::new();
+            for frame in frames {
+                frame.write(&mut input).unwrap();
+            }
+            Pipe {
+                input: Cursor::new(input),
+                output: Vec::new(),
+            }
+        }
+
+        // 서버가 보낸 프레임들
+        fn sent(&self) -> Vec<Frame> {
+            let mut reader = &self.output[..];
+            let mut frames = Vec::new();
+            while !reader.is_empty() {
+                frames.push(Frame::read(&mut reader, usize::MAX).unwrap());
+            }
+            frames
+        }
+    }
+
+    impl Read for Pipe {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            self.input.read(buf)
+        }
+    }
+
+    impl BufRead for Pipe {
+        fn fill_buf(&mut self) -> io::Result<&[u8]> {
+            self.input.fill_buf()
+        }
+
+        fn consume(&mut self, amt: usize) {
+            self.input.consume(amt)
+        }
+    }
+
+    impl Write for Pipe {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.output.write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    // 클라이언트가 보내는 마스킹한 프레임
+    fn client(opcode: Opcode, fin: bool, payload: &[u8]) -> Frame {
+        Frame {
+            fin,
+            opcode,
+            mask: Some([0x37, 0xfa, 0x21, 0x3d]),
+            payload: payload.to_vec(),
+        }
+    }
+
+    fn request(headers: &str) -> HttpRequest {
+        HttpRequest::try_from(format!("GET /live HTTP/1.1\r\nHost: a\r\n{}\r\n", headers)).unwrap()
+    }
+
+    const WAIT: Duration = Duration::from_secs(1);
+
+    #[test]
+    fn test_handshake() {
+        // RFC 6455 1.3의 예
+        let resp = handshake(
+            &request(
+                "Upgrade: websocket\r\nConnection: keep-alive, Upgrade\r\nSec-WebSocket-Key: dGhlIHNhbXBsZSBub25jZQ==\r\nSec-WebSocket-Version: 13\r\n",
+            ),
+            &Params::default(),
+        );
+        assert_eq!(StatusCode::SwitchingProtocols, resp.status());
+        assert_eq!(
+            Some("s3pPLMBiTxaQ9kYGzzhZRbK+xOo="),
+            resp.headers().get("Sec-WebSocket-Accept")
+        );
+
+        let resp = handshake(&request(""), &Params::default());
+        assert_eq!(StatusCode::UpgradeRequired, resp.status());
+        let resp = handshake(
+            &request("Upgrade: websocket\r\nConnection: Upgrade\r\nSec-WebSocket-Key: c2hvcnQ=\r\nSec-WebSocket-Version: 13\r\n"),
+            &Params::default(),
+        );
+        assert_eq!(StatusCode::BadRequest, resp.status());
+        let resp = handshake(
+            &request("Upgrade: websocket\r\nConnection: Upgrade\r\nSec-WebSocket-Key: dGhlIHNhbXBsZSBub25jZQ==\r\nSec-WebSocket-Version: 8\r\n"),
+            &Params::default(),
+        );
+        assert_eq!(StatusCode::UpgradeRequired, resp.status());
+        assert_eq!(Some("13"), resp.headers().get("Sec-WebSocket-Version"));
+    }
+
+    #[test]
+    fn test_frame_encoding() {
+        // RFC 6455 5.7의 예
+        let mut raw = Vec::new();
+        Frame::new(Opcode::Text, "Hello").write(&mut raw).unwrap();
+        assert_eq!(b"\x81\x05Hello".to_vec(), raw);
+
+        let masked = Frame {
+            mask: Some([0x37, 0xfa, 0x21, 0x3d]),
+            ..Frame::new(Opcode::Text, "Hello")
+        };
+        let mut raw = Vec::new();
+        masked.write(&mut raw).unwrap();
+        assert_eq!(
+            b"\x81\x85\x37\xfa\x21\x3d\x7f\x9f\x4d\x51\x58".to_vec(),
+            raw
+        );
+        assert_eq!(masked, Frame::read(&mut &raw[..], 125).unwrap());
+
+        for len in [125, 126, 65535, 65536] {
+            let frame = Frame::new(Opcode::Binary, vec![7; len]);
+            let mut raw = Vec::new();
+            frame.write(&mut raw).unwrap();
+            assert_eq!(frame, Frame::read(&mut &raw[..], len).unwrap());
+            assert!(matches!(
+                Frame::read(&mut &raw[..], len - 1),
+                Err(FrameError::TooLarge)
+            ));
+        }
+
+        for raw in [
+            &b"\xc1\x00"[..],    // RSV1
+            b"\x83\x00",         // 정의되지 않은 opcode
+            b"\x09\x00",         // 나눈 Ping
+            b"\x89\x7e\x00\x7e", // 125바이트보다 긴 Ping
+        ] {
+            assert!(matches!(
+                Frame::read(&mut &raw[..], 1024),
+                Err(FrameError::Protocol(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn test_fragments_and_control_frames() {
+        let mut pipe = Pipe::new(&[
+            client(Opcode::Text, false, "Hel".as_bytes()),
+            client(Opcode::Ping, true, b"hb"),
+            client(Opcode::Continuation, true, "lo".as_bytes()),
+            client(Opcode::Binary, true, &[1, 2, 3]),
+            client(Opcode::Close, true, &[0x03, 0xe8, b'b', b'y', b'e']),
+        ]);
+        let shutdown = AtomicBool::new(false);
+        let mut socket = WebSocket::new(&mut pipe, RequestDeadline::default(), &shutdown);
+        assert_eq!(
+            Some(Message::Text("Hello".to_string())),
+            socket.read_message(WAIT).unwrap()
+        );
+        assert_eq!(
+            Some(Message::Binary(vec![1, 2, 3])),
+            socket.read_message(WAIT).unwrap()
+        );
+        assert_eq!(
+            Some(Message::Close(Some(CloseFrame {
+                code: CLOSE_NORMAL,
+                reason: "bye".to_string()
+            }))),
+            socket.read_message(WAIT).unwrap()
+        );
+        assert!(socket.send(&Message::Text("late".to_string())).is_err());
+
+        // Ping에는 같은 페이로드의 Pong으로, Close에는 같은 상태 코드의 Close로 답한다
+        assert_eq!(
+            vec![
+                Frame::new(Opcode::Pong, "hb"),
+                Frame::new(Opcode::Close, vec![0x03, 0xe8])
+            ],
+            pipe.sent()
+        );
+    }
+
+    #[test]
+    fn test_protocol_errors_close_the_connection() {
+        let cases: [(&[Frame], u16); 5] = [
+            (
+                &[Frame::new(Opcode::Text, "unmasked")],
+                CLOSE_PROTOCOL_ERROR,
+            ),
+            (
+                &[client(Opcode::Continuation, true, b"x")],
+                CLOSE_PROTOCOL_ERROR,
+            ),
+            (
+                &[
+                    client(Opcode::Text, false, b"a"),
+                    client(Opcode::Text, true, b"b"),
+                ],
+                CLOSE_PROTOCOL_ERROR,
+            ),
+            (
+                &[client(Opcode::Text, true, &[0xff, 0xfe])],
+                CLOSE_INVALID_DATA,
+            ),
+            (
+                &[
+                    client(Opcode::Text, false, &[b'a'; 6]),
+                    client(Opcode::Continuation, true, &[b'a'; 6]),
+                ],
+                CLOSE_TOO_BIG,
+            ),
+        ];
+        for (frames, code) in cases {
+            let mut pipe = Pipe::new(frames);
+            let shutdown = AtomicBool::new(false);
+            let mut socket = WebSocket::new(&mut pipe, RequestDeadline::default(), &shutdown)
+                .max_message_size(10);
+            let e = socket.read_message(WAIT).unwrap_err();
+            assert_eq!(io::ErrorKind::InvalidData, e.kind());
+            let sent = pipe.sent();
+            assert_eq!(Opcode::Close, sent[0].opcode);
+            assert_eq!(code.to_be_bytes(), sent[0].payload[..2]);
+        }
+    }
+
+    #[test]
+    fn test_send_splits_long_messages() {
+        let mut pipe = Pipe::new(&[client(Opcode::Close, true, &[])]);
+        let shutdown = AtomicBool::new(false);
+        let mut socket = WebSocket::new(&mut pipe, RequestDeadline::default(), &shutdown);
+        socket.max_frame_size = 4;
+        socket
+            .send(&Message::Text("abcdefghij".to_string()))
+            .unwrap();
+        socket.send(&Message::Binary(Vec::new())).unwrap();
+        socket.close(CLOSE_GOING_AWAY, "bye").unwrap();
+
+        let sent = pipe.sent();
+        let summary: Vec<(Opcode, bool, &[u8])> = sent
+            .iter()
+            .map(|f| (f.opcode, f.fin, &f.payload[..]))
+            .collect();
+        assert_eq!(
+            vec![
+                (Opcode::Text, false, &b"abcd"[..]),
+                (Opcode::Continuation, false, b"efgh"),
+                (Opcode::Continuation, true, b"ij"),
+                (Opcode::Binary, true, b""),
+                (Opcode::Close, true, b"\x03\xe9bye"),
+            ],
+            summary
+        );
+    }
+
+    #[test]
+    fn test_shutdown_closes_with_going_away() {
+        let mut pipe = Pipe::new(&[]);
+        let shutdown = AtomicBool::new(true);
+        let mut socket = WebSocket::new(&mut pipe, RequestDeadline::default(), &shutdown);
+        assert!(matches!(
+            socket.read_message(WAIT).unwrap(),
+            Some(Message::Close(Some(CloseFrame {
+                code: CLOSE_GOING_AWAY,
+                ..
+            })))
+        ));
+        assert_eq!(b"\x03\xe9", &pipe.sent()[0].payload[..2]);
+    }
+}
@@ -0,0 +1,397 @@
+// 빌드한 httpserver를 띄우고 http 크레이트의 클라이언트로 요청해 보는 통합 테스트
+use http::client::Client;
+use http::httprequest::{HttpRequest, Method};
+use http::status::StatusCode;
+use std::fs;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::path::PathBuf;
+use std::process::{Child, Command};
+use std::thread;
+use std::time::{Duration, Instant};
+
+// 테스트가 끝나면(실패해도) 서버 프로세스를 끝낸다
+struct TestServer {
+    child: Child,
+    url: String,
+    dir: PathBuf,
+}
+
+impl TestServer {
+    fn start(name: &str) -> TestServer {
+        let dir =
+            std::env::temp_dir().join(format!("httpserver-it-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("public/docs")).unwrap();
+        fs::create_dir_all(dir.join("data")).unwrap();
+        fs::write(dir.join("public/index.html"), "<h1>home</h1>".repeat(100)).unwrap();
+        fs::write(dir.join("public/docs/index.html"), "<h1>docs</h1>").unwrap();
+        fs::write(dir.join("public/404.html"), "missing").unwrap();
+        fs::copy(
+            concat!(env!("CARGO_MANIFEST_DIR"), "/data/orders.json"),
+            dir.join("data/orders.json"),
+        )
+        .unwrap();
+
+        // 빈 포트를 하나 얻어 서버에 넘긴다
+        let addr = TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap();
+        let child = Command::new(env!("CARGO_BIN_EXE_httpserver"))
+            .args(["--listen", &addr.to_string()])
+            .arg("--document-root")
+            .arg(dir.join("public"))
+            .arg("--data-dir")
+            .arg(dir.join("data"))
+            .stdout(std::process::Stdio::null())
+            .spawn()
+            .unwrap();
+        let started = Instant::now();
+        while TcpStream::connect(addr).is_err() {
+            assert!(
+                started.elapsed() < Duration::from_secs(10),
+                "server did not start"
+            );
+            thread::sleep(Duration::from_millis(20));
+        }
+        TestServer {
+            child,
+            url: format!("http://{}", addr),
+            dir,
+        }
+    }
+}
+
+impl Drop for TestServer {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+        let _ = fs::remove_dir_all(&self.dir);
+    }
+}
+
+fn json_request(method: Method, url: &str, body: &str) -> HttpRequest {
+    HttpRequest::builder()
+        .method(method)
+        .target(url)
+        .header("Content-Type", "application/json")
+        .body(body)
+        .build()
+        .unwrap()
+}
+
+fn json(body: &http::body::Body) -> serde_json::Value {
+    serde_json::from_slice(body.as_bytes().unwrap()).unwrap()
+}
+
+#[test]
+fn test_order_api_over_http() {
+    let server = TestServer::start("orders");
+    let client = Client::new().timeout(Duration::from_secs(5));
+    let orders = format!("{}/api/shipping/orders", server.url);
+
+    let resp = client.get(&orders).unwrap();
+    assert_eq!(StatusCode::Ok, resp.status());
+    assert_eq!(2, json(resp.body()).as_array().unwrap().len());
+
+    let resp = client
+        .send(json_request(
+            Method::Post,
+            &orders,
+            r#"{"order_date": "5 May 2021", "order_status": "Pending"}"#,
+        ))
+        .unwrap();
+    assert_eq!(StatusCode::Created, resp.status());
+    let location = resp.headers().get("Location").unwrap().to_string();
+
+    let resp = client
+        .send(json_request(
+            Method::Patch,
+            &format!("{}{}", server.url, location),
+            r#"{"order_status": "Shipped"}"#,
+        ))
+        .unwrap();
+    assert_eq!("Shipped", json(resp.body())["order_status"]);
+
+    let resp = client
+        .get(&format!("{}?order_status=Shipped", orders))
+        .unwrap();
+    assert_eq!(3, json(resp.body())[0]["order_id"]);
+
+    let delete = HttpRequest::builder()
+        .method(Method::Delete)
+        .target(format!("{}{}", server.url, location))
+        .build()
+        .unwrap();
+    assert_eq!(StatusCode::NoContent, client.send(delete).unwrap().status());
+    let resp = client.get(&format!("{}{}", server.url, location)).unwrap();
+    assert_eq!(StatusCode::NotFound, resp.status());
+}
+
+#[test]
+fn test_static_files_over_http() {
+    let server = TestServer::start("static");
+    let client = Client::new().timeout(Duration::from_secs(5));
+
+    // 디렉터리 주소는 '/'를 붙인 주소로 리다이렉트되고, 클라이언트가 따라간다
+    let resp = client.get(&format!("{}/docs", server.url)).unwrap();
+    assert_eq!(StatusCode::Ok, resp.status());
+    assert_eq!(Some(&b"<h1>docs</h1>"[..]), resp.body().as_bytes());
+
+    // 파일을 읽으면서 압축한 본문은 chunked로 온다
+    let req = HttpRequest::builder()
+        .target(format!("{}/index.html", server.url))
+        .header("Accept-Encoding", "gzip")
+        .build()
+        .unwrap();
+    let resp = client.send(req).unwrap();
+    assert_eq!(Some("gzip"), resp.headers().get("Content-Encoding"));
+    assert_eq!(Some("chunked"), resp.headers().get("Transfer-Encoding"));
+    let mut html = String::new();
+    flate2::read::GzDecoder::new(resp.body().as_bytes().unwrap())
+        .read_to_string(&mut html)
+        .unwrap();
+    assert_eq!("<h1>home</h1>".repeat(100), html);
+
+    let resp = client.get(&format!("{}/nope", server.url)).unwrap();
+    assert_eq!(StatusCode::NotFound, resp.status());
+    assert_eq!(Some(&b"missing"[..]), resp.body().as_bytes());
+}
+
+// 서버가 보낸 (마스킹하지 않은) 프레임 하나의 opcode와 페이로드
+fn read_frame(reader: &mut impl Read) -> (u8, Vec<u8>) {
+    let mut head = [0u8; 2];
+    reader.read_exact(&mut head).unwrap();
+    assert_eq!(0, head[1] & 0x80, "server frames are not masked");
+    let len = match head[1] & 0x7f {
+        126 => {
+            let mut len = [0u8; 2];
+            reader.read_exact(&mut len).unwrap();
+            u16::from_be_bytes(len) as usize
+        }
+        len => len as usize,
+    };
+    let mut payload = vec![0; len];
+    reader.read_exact(&mut payload).unwrap();
+    (head[0] & 0x0f, payload)
+}
+
+// 클라이언트는 프레임을 마스킹해 보내야 한다
+fn write_frame(stream: &mut TcpStream, opcode: u8, payload: &[u8]) {
+    let key = [1u8, 2, 3, 4];
+    let mut frame = vec![0x80 | opcode, 0x80 | payload.len() as u8];
+    frame.extend_from_slice(&key);
+    frame.extend(payload.iter().enumerate().map(|(i, b)| b ^ key[i % 4]));
+    stream.write_all(&frame).unwrap();
+}
+
+#[test]
+fn test_http_1_0_gets_uncompressed_static_file() {
+    let server = TestServer::start("http10");
+    let expected = "<h1>home</h1>".repeat(100);
+    // chunked를 읽지 못하는 HTTP/1.0 클라이언트에게는 압축하지 않고 길이를 알려 보낸다
+    let mut stream = TcpStream::connect(server.url.trim_start_matches("http://")).unwrap();
+    stream
+        .set_read_timeout(Some(Duration::from_secs(5)))
+        .unwrap();
+    stream
+        .write_all(b"GET /index.html HTTP/1.0\r\nHost: localhost\r\nAccept-Encoding: gzip\r\n\r\n")
+        .unwrap();
+    let mut response = String::new();
+    stream.read_to_string(&mut response).unwrap();
+    let (head, body) = response.split_once("\r\n\r\n").unwrap();
+    assert!(head.starts_with("HTTP/1.1 200 OK\r\n"));
+    assert!(!head.contains("Transfer-Encoding"));
+    assert!(!head.contains("Content-Encoding"));
+    let content_length = format!("Content-Length: {}", expected.len());
+    assert!(head.lines().any(|line| line == content_length));
+    assert_eq!(expected, body);
+
+    // HTTP/1.1 클라이언트는 그대로 압축해서 받는다
+    let resp = Client::new()
+        .send(
+            HttpRequest::builder()
+                .target(format!("{}/index.html", server.url))
+                .header("Accept-Encoding", "gzip")
+                .build()
+                .unwrap(),
+        )
+        .unwrap();
+    assert_eq!(Some("gzip"), resp.headers().get("Content-Encoding"));
+}
+
+#[test]
+fn test_order_feed_over_websocket() {
+    let server = TestServer::start("websocket");
+    let client = Client::new().timeout(Duration::from_secs(5));
+    let live = format!("{}/api/shipping/orders/live", server.url);
+
+    // 업그레이드하지 않은 요청은 426을 받는다
+    let resp = client.get(&live).unwrap();
+    assert_eq!(StatusCode::UpgradeRequired, resp.status());
+
+    let mut stream = TcpStream::connect(server.url.trim_start_matches("http://")).unwrap();
+    stream
+        .set_read_timeout(Some(Duration::from_secs(5)))
+        .unwrap();
+    stream
+        .write_all(
+            b"GET /api/shipping/orders/live HTTP/1.1\r\nHost: localhost\r\nUpgrade: websocket\r\nConnection: Upgrade\r\nSec-WebSocket-Key: dGhlIHNhbXBsZSBub25jZQ==\r\nSec-WebSocket-Version: 13\r\n\r\n",
+        )
+        .unwrap();
+    let mut reader = BufReader::new(stream.try_clone().unwrap());
+    let mut head = String::new();
+    while !head.ends_with("\r\n\r\n") {
+        reader.read_line(&mut head).unwrap();
+    }
+    assert!(head.starts_with("HTTP/1.1 101 Switching Protocols\r\n"));
+    assert!(head.contains("Sec-WebSocket-Accept: s3pPLMBiTxaQ9kYGzzhZRbK+xOo=\r\n"));
+
+    let (opcode, payload) = read_frame(&mut reader);
+    assert_eq!(0x1, opcode);
+    let snapshot: serde_json::Value = serde_json::from_slice(&payload).unwrap();
+    assert_eq!("snapshot", snapshot["type"]);
+    assert_eq!(2, snapshot["orders"].as_array().unwrap().len());
+
+    // 다른 커넥션에서 만든 주문이 이벤트로 온다
+    let resp = client
+        .send(json_request(
+            Method::Post,
+            &format!("{}/api/shipping/orders", server.url),
+            r#"{"order_date": "5 May 2021", "order_status": "Pending"}"#,
+        ))
+        .unwrap();
+    assert_eq!(StatusCode::Created, resp.status());
+    let (opcode, payload) = read_frame(&mut reader);
+    assert_eq!(0x1, opcode);
+    let event: serde_json::Value = serde_json::from_slice(&payload).unwrap();
+    assert_eq!("created", event["type"]);
+    assert_eq!(3, event["order"]["order_id"]);
+
+    write_frame(&mut stream, 0x9, b"hi");
+    assert_eq!((0xA, b"hi".to_vec()), read_frame(&mut reader));
+
+    // 닫기 핸드셰이크: 같은 상태 코드로 답하고 커넥션을 닫는다
+    write_frame(&mut stream, 0x8, &1000u16.to_be_bytes());
+    assert_eq!(
+        (0x8, 1000u16.to_be_bytes().to_vec()),
+        read_frame(&mut reader)
+    );
+    assert_eq!(0, reader.read(&mut [0u8; 1]).unwrap());
+}
+
+// 이벤트 스트림 요청을 보내고 응답 헤더를 읽는다
+fn open_events(server: &TestServer, last_event_id: Option<&str>) -> BufReader<TcpStream> {
+    let mut stream = TcpStream::connect(server.url.trim_start_matches("http://")).unwrap();
+    stream
+        .set_read_timeout(Some(Duration::from_secs(5)))
+        .unwrap();
+    let resume = last_event_id
+        .map(|id| format!("Last-Event-ID: {}\r\n", id))
+        .unwrap_or_default();
+    write!(
+        stream,
+        "GET /api/shipping/orders/events HTTP/1.1\r\nHost: localhost\r\nAccept: text/event-stream\r\n{}\r\n",
+        resume
+    )
+    .unwrap();
+    let mut reader = BufReader::new(stream);
+    let mut head = String::new();
+    while !head.ends_with("\r\n\r\n") {
+        reader.read_line(&mut head).unwrap();
+    }
+    assert!(head.starts_with("HTTP/1.1 200 OK\r\n"));
+    assert!(head.contains("Content-Type: text/event-stream\r\n"));
+    assert!(head.contains("Transfer-Encoding: chunked\r\n"));
+    reader
+}
+
+// chunked 본문에서 이벤트 하나를 읽어 (필드, 값) 목록으로 돌려준다. 주석 행은 건너뛴다.
+fn read_event(reader: &mut BufReader<TcpStream>, buf: &mut String) -> Vec<(String, String)> {
+    loop {
+        if let Some(end) = buf.find("\n\n") {
+            let event: String = buf.drain(..end + 2).collect();
+            let fields: Vec<(String, String)> = event
+                .lines()
+                .filter(|line| !line.is_empty() && !line.starts_with(':'))
+                .map(|line| {
+                    let (name, value) = line.split_once(": ").unwrap();
+                    (name.to_string(), value.to_string())
+                })
+                .collect();
+            if fields.is_empty() {
+                continue;
+            }
+            return fields;
+        }
+        let mut size = String::new();
+        reader.read_line(&mut size).unwrap();
+        let size = usize::from_str_radix(size.trim_end(), 16).unwrap();
+        let mut chunk = vec![0; size + 2];
+        reader.read_exact(&mut chunk).unwrap();
+        buf.push_str(std::str::from_utf8(&chunk[..size]).unwrap());
+    }
+}
+
+fn field<'a>(event: &'a [(String, String)], name: &str) -> &'a str {
+    event
+        .iter()
+        .find(|(n, _)| n == name)
+        .map(|(_, value)| value.as_str())
+        .unwrap()
+}
+
+#[test]
+fn test_order_events_over_sse() {
+    let server = TestServer::start("sse");
+    let client = Client::new().timeout(Duration::from_secs(5));
+
+    let mut reader = open_events(&server, None);
+    let mut buf = String::new();
+    assert_eq!(
+        vec![("retry".to_string(), "3000".to_string())],
+        read_event(&mut reader, &mut buf)
+    );
+    let snapshot = read_event(&mut reader, &mut buf);
+    assert_eq!("snapshot", field(&snapshot, "event"));
+    let orders: serde_json::Value = serde_json::from_str(field(&snapshot, "data")).unwrap();
+    assert_eq!(2, orders.as_array().unwrap().len());
+    let (generation, id) = field(&snapshot, "id").split_once('-').unwrap();
+    assert_eq!("0", id);
+    let generation = generation.to_string();
+
+    // 다른 커넥션에서 만든 주문이 created 이벤트로 온다
+    let resp = client
+        .send(json_request(
+            Method::Post,
+            &format!("{}/api/shipping/orders", server.url),
+            r#"{"order_date": "5 May 2021", "order_status": "Pending"}"#,
+        ))
+        .unwrap();
+    assert_eq!(StatusCode::Created, resp.status());
+    let created = read_event(&mut reader, &mut buf);
+    assert_eq!("created", field(&created, "event"));
+    assert_eq!(format!("{}-1", generation), field(&created, "id"));
+    let event: serde_json::Value = serde_json::from_str(field(&created, "data")).unwrap();
+    assert_eq!(3, event["order"]["order_id"]);
+    drop(reader);
+
+    // 마지막으로 받은 id로 다시 연결하면 놓친 변경부터 이어 받는다
+    let mut reader = open_events(&server, Some(&format!("{}-0", generation)));
+    let mut buf = String::new();
+    read_event(&mut reader, &mut buf);
+    let resumed = read_event(&mut reader, &mut buf);
+    assert_eq!("created", field(&resumed, "event"));
+    assert_eq!(format!("{}-1", generation), field(&resumed, "id"));
+
+    // 알아볼 수 없는 id면 목록을 처음부터 다시 받는다
+    let mut reader = open_events(&server, Some("bogus"));
+    let mut buf = String::new();
+    read_event(&mut reader, &mut buf);
+    let snapshot = read_event(&mut reader, &mut buf);
+    assert_eq!("snapshot", field(&snapshot, "event"));
+    assert_eq!(format!("{}-1", generation), field(&snapshot, "id"));
+    let orders: serde_json::Value = serde_json::from_str(field(&snapshot, "data")).unwrap();
+    assert_eq!(3, orders.as_array().unwrap().len());
+}